@@ -0,0 +1,79 @@
+-- Base schema: users and tasks as the client reads and writes them, plus the
+-- schema_migrations bookkeeping used by `todo_tui db migrate` and the
+-- startup version check.
+
+CREATE EXTENSION IF NOT EXISTS pgcrypto;
+
+CREATE TABLE IF NOT EXISTS schema_migrations (
+    version INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    applied_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Highest applied migration, exposed to PostgREST as rpc/schema_version
+CREATE OR REPLACE FUNCTION schema_version()
+RETURNS INTEGER
+LANGUAGE sql
+STABLE
+SECURITY DEFINER
+SET search_path = public
+AS $$
+    SELECT COALESCE(MAX(version), 0) FROM schema_migrations;
+$$;
+
+-- Plain Postgres + PostgREST setups lack the Supabase roles and auth.uid()
+-- that later migrations rely on; provide equivalents when they are missing.
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_roles WHERE rolname = 'anon') THEN
+        CREATE ROLE anon NOLOGIN;
+    END IF;
+    IF NOT EXISTS (SELECT 1 FROM pg_roles WHERE rolname = 'authenticated') THEN
+        CREATE ROLE authenticated NOLOGIN;
+    END IF;
+
+    IF NOT EXISTS (
+        SELECT 1 FROM pg_proc p JOIN pg_namespace n ON n.oid = p.pronamespace
+        WHERE n.nspname = 'auth' AND p.proname = 'uid'
+    ) THEN
+        CREATE SCHEMA IF NOT EXISTS auth;
+        -- PostgREST exposes the verified JWT claims as a setting
+        CREATE FUNCTION auth.uid() RETURNS UUID
+        LANGUAGE sql STABLE
+        AS 'SELECT (NULLIF(current_setting(''request.jwt.claims'', true), '''')::json->>''sub'')::uuid';
+        GRANT USAGE ON SCHEMA auth TO anon, authenticated;
+    END IF;
+END;
+$$;
+
+CREATE TABLE IF NOT EXISTS users (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    username TEXT NOT NULL UNIQUE,
+    email TEXT NOT NULL,
+    password_hash TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS tasks (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    title TEXT NOT NULL,
+    description TEXT NOT NULL DEFAULT '',
+    done BOOLEAN NOT NULL DEFAULT FALSE,
+    -- 1 = low, 2 = medium, 3 = high
+    priority INTEGER NOT NULL DEFAULT 2 CHECK (priority BETWEEN 1 AND 3),
+    due_date TIMESTAMPTZ,
+    tags TEXT[] NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- Manual sort order within the user's list
+    position INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE INDEX IF NOT EXISTS tasks_user_id_idx ON tasks (user_id, position);
+
+-- Each migration grants the API roles access to the tables it creates, so a
+-- table is never exposed without a deliberate grant
+GRANT USAGE ON SCHEMA public TO anon, authenticated;
+GRANT SELECT ON schema_migrations TO anon, authenticated;
+GRANT SELECT, INSERT, UPDATE, DELETE ON users, tasks TO anon, authenticated;
@@ -0,0 +1,15 @@
+-- Replace the boolean done flag with a workflow status.
+-- The done column is kept and maintained by the client so older builds
+-- keep working; it can be dropped once every client writes status.
+
+ALTER TABLE tasks
+    ADD COLUMN IF NOT EXISTS status TEXT NOT NULL DEFAULT 'todo';
+
+UPDATE tasks SET status = 'done' WHERE done = TRUE AND status = 'todo';
+
+ALTER TABLE tasks
+    DROP CONSTRAINT IF EXISTS tasks_status_check;
+
+ALTER TABLE tasks
+    ADD CONSTRAINT tasks_status_check
+    CHECK (status IN ('todo', 'in_progress', 'blocked', 'waiting', 'done', 'cancelled'));
@@ -0,0 +1,29 @@
+-- Rename or merge a tag across all of a user's tasks in one statement.
+-- Replaces p_old with p_new in every tags array that contains it, keeping
+-- the original tag order and dropping the duplicate when a task already
+-- carries p_new. Returns the number of tasks changed.
+
+CREATE OR REPLACE FUNCTION rename_tag(p_user_id UUID, p_old TEXT, p_new TEXT)
+RETURNS INTEGER
+LANGUAGE plpgsql
+AS $$
+DECLARE
+    changed INTEGER;
+BEGIN
+    UPDATE tasks
+    SET tags = (
+            SELECT COALESCE(array_agg(tag ORDER BY first_pos), '{}')
+            FROM (
+                SELECT tag, MIN(pos) AS first_pos
+                FROM unnest(array_replace(tasks.tags, p_old, p_new)) WITH ORDINALITY AS u(tag, pos)
+                GROUP BY tag
+            ) deduped
+        ),
+        updated_at = NOW()
+    WHERE user_id = p_user_id
+      AND p_old = ANY(tags);
+
+    GET DIAGNOSTICS changed = ROW_COUNT;
+    RETURN changed;
+END;
+$$;
@@ -0,0 +1,21 @@
+-- Named projects that group a user's tasks. Deleting a project moves its
+-- tasks back to the inbox (project_id NULL) rather than deleting them.
+
+CREATE TABLE IF NOT EXISTS projects (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    color TEXT,
+    position INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (user_id, name)
+);
+
+CREATE INDEX IF NOT EXISTS projects_user_id_idx ON projects (user_id, position);
+
+GRANT SELECT, INSERT, UPDATE, DELETE ON projects TO anon, authenticated;
+
+ALTER TABLE tasks
+    ADD COLUMN IF NOT EXISTS project_id UUID REFERENCES projects(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS tasks_project_id_idx ON tasks (project_id);
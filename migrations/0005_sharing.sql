@@ -0,0 +1,127 @@
+-- Project sharing, task assignment and row-level security.
+--
+-- Policies identify the caller with auth.uid(), so clients must send the
+-- Supabase Auth access token of a user whose users.id equals their auth id
+-- (the app signs in to Supabase Auth at login and registration). Requests
+-- made with only the anon key see no projects or tasks once this is applied.
+
+CREATE TABLE IF NOT EXISTS project_members (
+    project_id UUID NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    role TEXT NOT NULL CHECK (role IN ('owner', 'editor', 'viewer')),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (project_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS project_members_user_id_idx ON project_members (user_id);
+
+GRANT SELECT, INSERT, UPDATE, DELETE ON project_members TO anon, authenticated;
+
+ALTER TABLE tasks
+    ADD COLUMN IF NOT EXISTS assignee_id UUID REFERENCES users(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS tasks_assignee_id_idx ON tasks (assignee_id);
+
+-- Every project owner is also a member with the owner role
+INSERT INTO project_members (project_id, user_id, role)
+SELECT id, user_id, 'owner' FROM projects
+ON CONFLICT DO NOTHING;
+
+CREATE OR REPLACE FUNCTION add_project_owner()
+RETURNS TRIGGER
+LANGUAGE plpgsql
+SECURITY DEFINER
+SET search_path = public
+AS $$
+BEGIN
+    INSERT INTO project_members (project_id, user_id, role)
+    VALUES (NEW.id, NEW.user_id, 'owner')
+    ON CONFLICT DO NOTHING;
+    RETURN NEW;
+END;
+$$;
+
+DROP TRIGGER IF EXISTS projects_add_owner ON projects;
+CREATE TRIGGER projects_add_owner
+    AFTER INSERT ON projects
+    FOR EACH ROW EXECUTE FUNCTION add_project_owner();
+
+-- The caller's role in a project, or NULL. SECURITY DEFINER so policies on
+-- project_members can use it without recursing into themselves.
+CREATE OR REPLACE FUNCTION project_role(p_project_id UUID)
+RETURNS TEXT
+LANGUAGE sql
+STABLE
+SECURITY DEFINER
+SET search_path = public
+AS $$
+    SELECT role FROM project_members
+    WHERE project_id = p_project_id AND user_id = auth.uid();
+$$;
+
+-- projects
+
+ALTER TABLE projects ENABLE ROW LEVEL SECURITY;
+
+DROP POLICY IF EXISTS projects_select ON projects;
+CREATE POLICY projects_select ON projects FOR SELECT
+    USING (user_id = auth.uid() OR project_role(id) IS NOT NULL);
+
+DROP POLICY IF EXISTS projects_insert ON projects;
+CREATE POLICY projects_insert ON projects FOR INSERT
+    WITH CHECK (user_id = auth.uid());
+
+DROP POLICY IF EXISTS projects_update ON projects;
+CREATE POLICY projects_update ON projects FOR UPDATE
+    USING (user_id = auth.uid() OR project_role(id) = 'owner');
+
+DROP POLICY IF EXISTS projects_delete ON projects;
+CREATE POLICY projects_delete ON projects FOR DELETE
+    USING (user_id = auth.uid());
+
+-- project_members: visible to fellow members, managed by owners
+
+ALTER TABLE project_members ENABLE ROW LEVEL SECURITY;
+
+DROP POLICY IF EXISTS project_members_select ON project_members;
+CREATE POLICY project_members_select ON project_members FOR SELECT
+    USING (user_id = auth.uid() OR project_role(project_id) IS NOT NULL);
+
+DROP POLICY IF EXISTS project_members_manage ON project_members;
+CREATE POLICY project_members_manage ON project_members FOR ALL
+    USING (project_role(project_id) = 'owner')
+    WITH CHECK (project_role(project_id) = 'owner');
+
+-- tasks
+
+ALTER TABLE tasks ENABLE ROW LEVEL SECURITY;
+
+DROP POLICY IF EXISTS tasks_select ON tasks;
+CREATE POLICY tasks_select ON tasks FOR SELECT
+    USING (
+        user_id = auth.uid()
+        OR assignee_id = auth.uid()
+        OR (project_id IS NOT NULL AND project_role(project_id) IS NOT NULL)
+    );
+
+DROP POLICY IF EXISTS tasks_insert ON tasks;
+CREATE POLICY tasks_insert ON tasks FOR INSERT
+    WITH CHECK (
+        user_id = auth.uid()
+        AND (project_id IS NULL OR project_role(project_id) IN ('owner', 'editor'))
+    );
+
+DROP POLICY IF EXISTS tasks_update ON tasks;
+CREATE POLICY tasks_update ON tasks FOR UPDATE
+    USING (
+        (project_id IS NULL AND user_id = auth.uid())
+        OR assignee_id = auth.uid()
+        OR project_role(project_id) IN ('owner', 'editor')
+    );
+
+DROP POLICY IF EXISTS tasks_delete ON tasks;
+CREATE POLICY tasks_delete ON tasks FOR DELETE
+    USING (
+        (project_id IS NULL AND user_id = auth.uid())
+        OR project_role(project_id) IN ('owner', 'editor')
+    );
@@ -0,0 +1,40 @@
+-- Append-only activity log and comments per task.
+--
+-- The client records an entry after each change it makes to a task
+-- (created, renamed, priority/status changes, completion, reassignment, ...)
+-- and stores comments here with action 'comment'. Rows are never updated or
+-- deleted except when their task is deleted.
+
+CREATE TABLE IF NOT EXISTS task_activity (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    task_id UUID NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+    user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    action TEXT NOT NULL CHECK (action IN (
+        'created', 'renamed', 'priority_changed', 'status_changed', 'completed',
+        'reassigned', 'rescheduled', 'moved', 'retagged', 'commented'
+    )),
+    detail TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS task_activity_task_id_idx ON task_activity (task_id, created_at);
+
+ALTER TABLE task_activity ENABLE ROW LEVEL SECURITY;
+
+-- Anyone who can see the task can read its timeline (the subquery is
+-- filtered by the tasks policies from migration 0005)
+DROP POLICY IF EXISTS task_activity_select ON task_activity;
+CREATE POLICY task_activity_select ON task_activity FOR SELECT
+    USING (EXISTS (SELECT 1 FROM tasks WHERE tasks.id = task_id));
+
+DROP POLICY IF EXISTS task_activity_insert ON task_activity;
+CREATE POLICY task_activity_insert ON task_activity FOR INSERT
+    WITH CHECK (
+        user_id = auth.uid()
+        AND EXISTS (SELECT 1 FROM tasks WHERE tasks.id = task_id)
+    );
+
+-- No UPDATE or DELETE policies: with RLS enabled those are denied, and they
+-- aren't granted either so the log stays append-only
+GRANT SELECT, INSERT ON task_activity TO anon, authenticated;
+REVOKE UPDATE, DELETE ON task_activity FROM anon, authenticated;
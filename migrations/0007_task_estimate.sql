@@ -0,0 +1,13 @@
+-- Estimated effort per task, in minutes. Filled in by the natural-language
+-- parser ("30 min", "about 2h") or edited in the add preview; NULL when
+-- unknown.
+
+ALTER TABLE tasks
+    ADD COLUMN IF NOT EXISTS estimate_minutes INTEGER;
+
+ALTER TABLE tasks
+    DROP CONSTRAINT IF EXISTS tasks_estimate_minutes_check;
+
+ALTER TABLE tasks
+    ADD CONSTRAINT tasks_estimate_minutes_check
+    CHECK (estimate_minutes IS NULL OR estimate_minutes > 0);
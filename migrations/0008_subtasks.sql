@@ -0,0 +1,7 @@
+-- Subtasks, e.g. the steps proposed by `:breakdown`. A subtask is an
+-- ordinary task pointing at its parent; deleting the parent deletes them.
+
+ALTER TABLE tasks
+    ADD COLUMN IF NOT EXISTS parent_id UUID REFERENCES tasks(id) ON DELETE CASCADE;
+
+CREATE INDEX IF NOT EXISTS tasks_parent_id_idx ON tasks (parent_id);
@@ -0,0 +1,85 @@
+-- Checks on the new row of task updates. 0005's tasks_update policy only
+-- looked at the row before the update, so anyone allowed to edit a task
+-- could move it into a project they can't edit or give it to another user.
+
+-- Whether the caller may edit tasks of `p_user_id` in project `p_project_id`:
+-- their own personal tasks, or any task of a project they own or edit
+CREATE OR REPLACE FUNCTION can_edit_tasks_in(p_project_id UUID, p_user_id UUID)
+RETURNS BOOLEAN
+LANGUAGE sql
+STABLE
+AS $$
+    SELECT COALESCE(
+        (p_project_id IS NULL AND p_user_id = auth.uid())
+        OR project_role(p_project_id) IN ('owner', 'editor'),
+        FALSE
+    );
+$$;
+
+-- The creator of a task never changes, and a task only moves between
+-- projects the caller may edit tasks in. Policies can't compare the old and
+-- new row, hence a trigger. Direct database connections (no auth.uid())
+-- bypass RLS anyway and are let through.
+CREATE OR REPLACE FUNCTION check_task_move()
+RETURNS TRIGGER
+LANGUAGE plpgsql
+AS $$
+BEGIN
+    IF auth.uid() IS NULL THEN
+        RETURN NEW;
+    END IF;
+    IF NEW.user_id IS DISTINCT FROM OLD.user_id THEN
+        RAISE EXCEPTION 'The creator of a task cannot be changed'
+            USING ERRCODE = 'insufficient_privilege';
+    END IF;
+    IF NEW.project_id IS DISTINCT FROM OLD.project_id
+        AND NOT (can_edit_tasks_in(OLD.project_id, OLD.user_id) AND can_edit_tasks_in(NEW.project_id, NEW.user_id))
+    THEN
+        RAISE EXCEPTION 'Only owners and editors of both projects can move a task'
+            USING ERRCODE = 'insufficient_privilege';
+    END IF;
+    RETURN NEW;
+END;
+$$;
+
+DROP TRIGGER IF EXISTS tasks_check_move ON tasks;
+CREATE TRIGGER tasks_check_move
+    BEFORE UPDATE ON tasks
+    FOR EACH ROW EXECUTE FUNCTION check_task_move();
+
+-- The updated task has to end up somewhere the caller may edit: their own
+-- personal task or a project they own or edit. Assignees keep editing their
+-- task in place; the trigger above stops them moving it.
+DROP POLICY IF EXISTS tasks_update ON tasks;
+CREATE POLICY tasks_update ON tasks FOR UPDATE
+    USING (assignee_id = auth.uid() OR can_edit_tasks_in(project_id, user_id))
+    WITH CHECK (assignee_id = auth.uid() OR can_edit_tasks_in(project_id, user_id));
+
+-- rename_tag took the user whose tasks to change from the client; it now
+-- always works on the caller's own tasks
+DROP FUNCTION IF EXISTS rename_tag(UUID, TEXT, TEXT);
+
+CREATE OR REPLACE FUNCTION rename_tag(p_old TEXT, p_new TEXT)
+RETURNS INTEGER
+LANGUAGE plpgsql
+AS $$
+DECLARE
+    changed INTEGER;
+BEGIN
+    UPDATE tasks
+    SET tags = (
+            SELECT COALESCE(array_agg(tag ORDER BY first_pos), '{}')
+            FROM (
+                SELECT tag, MIN(pos) AS first_pos
+                FROM unnest(array_replace(tasks.tags, p_old, p_new)) WITH ORDINALITY AS u(tag, pos)
+                GROUP BY tag
+            ) deduped
+        ),
+        updated_at = NOW()
+    WHERE user_id = auth.uid()
+      AND p_old = ANY(tags);
+
+    GET DIAGNOSTICS changed = ROW_COUNT;
+    RETURN changed;
+END;
+$$;
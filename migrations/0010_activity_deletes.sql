@@ -0,0 +1,31 @@
+-- Record task deletions in the activity log. The client adds a 'deleted'
+-- entry once a task is gone, so entries no longer reference tasks by
+-- foreign key and outlive their task instead of being cascaded away.
+
+ALTER TABLE task_activity DROP CONSTRAINT IF EXISTS task_activity_task_id_fkey;
+
+ALTER TABLE task_activity DROP CONSTRAINT IF EXISTS task_activity_action_check;
+ALTER TABLE task_activity ADD CONSTRAINT task_activity_action_check CHECK (action IN (
+    'created', 'renamed', 'priority_changed', 'status_changed', 'completed',
+    'reassigned', 'rescheduled', 'moved', 'retagged', 'commented', 'deleted'
+));
+
+-- Whoever deleted a task can still see that they did
+DROP POLICY IF EXISTS task_activity_select ON task_activity;
+CREATE POLICY task_activity_select ON task_activity FOR SELECT
+    USING (
+        EXISTS (SELECT 1 FROM tasks WHERE tasks.id = task_id)
+        OR (action = 'deleted' AND user_id = auth.uid())
+    );
+
+-- A deletion is recorded after the task is gone, so it can't be checked
+-- against the task; it is always attributed to the caller
+DROP POLICY IF EXISTS task_activity_insert ON task_activity;
+CREATE POLICY task_activity_insert ON task_activity FOR INSERT
+    WITH CHECK (
+        user_id = auth.uid()
+        AND (
+            EXISTS (SELECT 1 FROM tasks WHERE tasks.id = task_id)
+            OR (action = 'deleted' AND NOT EXISTS (SELECT 1 FROM tasks WHERE tasks.id = task_id))
+        )
+    );
@@ -0,0 +1,55 @@
+-- Row-level security for users. 0005 left the table out, and 0001 granted it
+-- to anon and authenticated in full, so anyone with the anon key could read
+-- every password hash, overwrite one to take over the account, or delete a
+-- user along with their tasks and projects.
+--
+-- Other users' rows now show only id and username (enough to share projects
+-- by name), a user may only change or delete their own row, and the password
+-- is checked by verify_login so the hash never leaves the database.
+
+ALTER TABLE users ENABLE ROW LEVEL SECURITY;
+
+REVOKE ALL ON users FROM anon, authenticated;
+GRANT SELECT (id, username) ON users TO anon, authenticated;
+GRANT INSERT (id, username, email, password_hash) ON users TO authenticated;
+GRANT UPDATE (username, email, password_hash) ON users TO authenticated;
+GRANT DELETE ON users TO authenticated;
+
+DROP POLICY IF EXISTS users_select ON users;
+CREATE POLICY users_select ON users FOR SELECT
+    USING (TRUE);
+
+-- Registration creates the row for the caller's own Supabase Auth account
+DROP POLICY IF EXISTS users_insert ON users;
+CREATE POLICY users_insert ON users FOR INSERT
+    WITH CHECK (id = auth.uid());
+
+DROP POLICY IF EXISTS users_update ON users;
+CREATE POLICY users_update ON users FOR UPDATE
+    USING (id = auth.uid())
+    WITH CHECK (id = auth.uid());
+
+DROP POLICY IF EXISTS users_delete ON users;
+CREATE POLICY users_delete ON users FOR DELETE
+    USING (id = auth.uid());
+
+-- The user with this username and password, without the hash; no row when
+-- either is wrong. The client hashes with bcrypt's $2b$ prefix, which
+-- pgcrypto doesn't know; $2a$ is the same algorithm for any password under
+-- 72 bytes, so the hash is compared under that prefix.
+CREATE OR REPLACE FUNCTION verify_login(p_username TEXT, p_password TEXT)
+RETURNS TABLE (id UUID, username TEXT, email TEXT, created_at TIMESTAMPTZ)
+LANGUAGE sql
+STABLE
+SECURITY DEFINER
+SET search_path = public, extensions
+AS $$
+    SELECT u.id, u.username, u.email, u.created_at
+    FROM users u
+    WHERE u.username = p_username
+      AND u.password_hash ~ '^\$2[abxy]\$'
+      AND crypt(p_password, '$2a' || substr(u.password_hash, 4)) = '$2a' || substr(u.password_hash, 4);
+$$;
+
+REVOKE ALL ON FUNCTION verify_login(TEXT, TEXT) FROM PUBLIC;
+GRANT EXECUTE ON FUNCTION verify_login(TEXT, TEXT) TO anon, authenticated;
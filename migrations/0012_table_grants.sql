@@ -0,0 +1,8 @@
+-- 0001 used to grant anon and authenticated full access to every table
+-- created later through default privileges, so a table a migration forgot to
+-- cover with row-level security was writable by anyone with the anon key.
+-- Migrations now grant each table explicitly; drop the default for databases
+-- set up before that. Tables that already exist keep their grants.
+
+ALTER DEFAULT PRIVILEGES IN SCHEMA public
+    REVOKE SELECT, INSERT, UPDATE, DELETE ON TABLES FROM anon, authenticated;
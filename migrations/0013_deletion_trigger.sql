@@ -0,0 +1,32 @@
+-- Deletion entries are written by the database. 0010 let clients insert a
+-- 'deleted' entry themselves, which with the foreign key gone meant anyone
+-- could log one for any task id that no longer exists, with any detail.
+-- Clients may now only log changes to tasks they can see.
+
+-- The deleting user is recorded unless they are being deleted themselves
+-- (their tasks go with them)
+CREATE OR REPLACE FUNCTION log_task_deletion()
+RETURNS TRIGGER
+LANGUAGE plpgsql
+SECURITY DEFINER
+SET search_path = public
+AS $$
+BEGIN
+    INSERT INTO task_activity (task_id, user_id, action, detail)
+    VALUES (OLD.id, (SELECT id FROM users WHERE id = auth.uid()), 'deleted', OLD.title);
+    RETURN OLD;
+END;
+$$;
+
+DROP TRIGGER IF EXISTS tasks_log_deletion ON tasks;
+CREATE TRIGGER tasks_log_deletion
+    AFTER DELETE ON tasks
+    FOR EACH ROW EXECUTE FUNCTION log_task_deletion();
+
+DROP POLICY IF EXISTS task_activity_insert ON task_activity;
+CREATE POLICY task_activity_insert ON task_activity FOR INSERT
+    WITH CHECK (
+        user_id = auth.uid()
+        AND action <> 'deleted'
+        AND EXISTS (SELECT 1 FROM tasks WHERE tasks.id = task_id)
+    );
@@ -0,0 +1,57 @@
+-- Repeating tasks. recurrence holds an interval such as '1 day' or
+-- '2 weeks', set from phrases like "every monday" or "monthly" in the add
+-- preview; NULL for one-off tasks.
+--
+-- Completing a repeating task creates its next occurrence here rather than
+-- in the client, so every client (and every way of marking a task done)
+-- repeats it exactly once. The completed task stops repeating; the new one
+-- carries the rule on.
+
+ALTER TABLE tasks
+    ADD COLUMN IF NOT EXISTS recurrence TEXT;
+
+ALTER TABLE tasks
+    DROP CONSTRAINT IF EXISTS tasks_recurrence_check;
+
+ALTER TABLE tasks
+    ADD CONSTRAINT tasks_recurrence_check
+    CHECK (recurrence IS NULL OR recurrence ~ '^[1-9][0-9]{0,2} (day|week|month|year)s?$');
+
+-- The next occurrence is due one interval after the completed one (or after
+-- now when it had no due date) and goes to the end of the owner's list
+CREATE OR REPLACE FUNCTION repeat_completed_task()
+RETURNS TRIGGER
+LANGUAGE plpgsql
+SECURITY DEFINER
+SET search_path = public
+AS $$
+DECLARE
+    next_id UUID;
+BEGIN
+    INSERT INTO tasks (
+        user_id, title, description, status, done, priority, due_date, tags,
+        position, project_id, assignee_id, estimate_minutes, parent_id, recurrence
+    )
+    VALUES (
+        NEW.user_id, NEW.title, NEW.description, 'todo', FALSE, NEW.priority,
+        COALESCE(NEW.due_date, NOW()) + NEW.recurrence::interval, NEW.tags,
+        (SELECT COALESCE(MAX(position), -1) + 1 FROM tasks WHERE user_id = NEW.user_id),
+        NEW.project_id, NEW.assignee_id, NEW.estimate_minutes,
+        NEW.parent_id, NEW.recurrence
+    )
+    RETURNING id INTO next_id;
+
+    INSERT INTO task_activity (task_id, user_id, action, detail)
+    VALUES (next_id, (SELECT id FROM users WHERE id = auth.uid()), 'created', NULL);
+
+    NEW.recurrence := NULL;
+    RETURN NEW;
+END;
+$$;
+
+DROP TRIGGER IF EXISTS tasks_repeat_completed ON tasks;
+CREATE TRIGGER tasks_repeat_completed
+    BEFORE UPDATE ON tasks
+    FOR EACH ROW
+    WHEN (NEW.status = 'done' AND OLD.status <> 'done' AND NEW.recurrence IS NOT NULL)
+    EXECUTE FUNCTION repeat_completed_task();
@@ -0,0 +1,186 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use crate::config::NetworkSettings;
+use crate::core::error::{AppError, Result};
+use crate::net::HttpClient;
+use super::json::JsonOutput;
+use super::provider::{ChatMessage, ChatRole, LlmProvider, ModelOptions, ToolCall};
+
+const API_VERSION: &str = "2023-06-01";
+
+// Anthropic's messages API
+pub struct Anthropic {
+    http: HttpClient,
+    options: ModelOptions,
+}
+
+#[derive(Serialize)]
+struct MessagesRequest<'a> {
+    model: &'a str,
+    max_tokens: u32,
+    temperature: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system: Option<String>,
+    messages: Vec<Value>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_choice: Option<Value>,
+}
+
+#[derive(Deserialize)]
+struct MessagesResponse {
+    content: Vec<ContentBlock>,
+}
+
+#[derive(Deserialize)]
+struct ContentBlock {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    text: String,
+    // Call id, tool name and arguments of a tool_use block
+    #[serde(default)]
+    id: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    input: Option<Value>,
+}
+
+fn tool(tool: &JsonOutput) -> Value {
+    json!({ "name": tool.name, "description": tool.description, "input_schema": tool.schema })
+}
+
+// The conversation without system prompts, in Anthropic's format. Tool
+// calls and results are content blocks, and the results answering one turn
+// go back together in a single user message.
+fn wire_messages(messages: &[ChatMessage]) -> Vec<Value> {
+    let mut wire: Vec<Value> = Vec::new();
+    for message in messages.iter().filter(|m| m.role != ChatRole::System) {
+        match message.role {
+            ChatRole::Tool => {
+                let block = json!({
+                    "type": "tool_result",
+                    "tool_use_id": message.tool_call_id,
+                    "content": message.content,
+                });
+                let previous = wire
+                    .last_mut()
+                    .filter(|last| last["role"] == "user")
+                    .and_then(|last| last["content"].as_array_mut());
+                match previous {
+                    Some(blocks) => blocks.push(block),
+                    None => wire.push(json!({ "role": "user", "content": [block] })),
+                }
+            }
+            _ if !message.tool_calls.is_empty() => {
+                let mut blocks = Vec::new();
+                if !message.content.is_empty() {
+                    blocks.push(json!({ "type": "text", "text": message.content }));
+                }
+                for call in &message.tool_calls {
+                    blocks.push(json!({ "type": "tool_use", "id": call.id, "name": call.name, "input": call.arguments }));
+                }
+                wire.push(json!({ "role": "assistant", "content": blocks }));
+            }
+            _ => wire.push(json!({ "role": message.role, "content": message.content })),
+        }
+    }
+    wire
+}
+
+// The text blocks of a reply
+fn text(blocks: &[ContentBlock]) -> Result<String> {
+    let text: String = blocks.iter().filter(|b| b.kind == "text").map(|b| b.text.as_str()).collect();
+    if text.is_empty() {
+        return Err(AppError::Serialization("No response from AI".to_string()));
+    }
+    Ok(text)
+}
+
+impl Anthropic {
+    pub fn new(options: ModelOptions, network: &NetworkSettings) -> Self {
+        Self {
+            http: HttpClient::new(network, network.ai_request_timeout_secs),
+            options,
+        }
+    }
+
+    async fn send(&self, messages: &[ChatMessage], tools: Vec<Value>, tool_choice: Option<Value>) -> Result<Vec<ContentBlock>> {
+        // System prompts go in their own field rather than the message list
+        let system: Vec<&str> = messages
+            .iter()
+            .filter(|m| m.role == ChatRole::System)
+            .map(|m| m.content.as_str())
+            .collect();
+        let body = MessagesRequest {
+            model: &self.options.model,
+            max_tokens: self.options.max_tokens,
+            temperature: self.options.temperature,
+            system: (!system.is_empty()).then(|| system.join("\n\n")),
+            messages: wire_messages(messages),
+            tools,
+            tool_choice,
+        };
+
+        let request = self
+            .http
+            .post(format!("{}/v1/messages", self.options.base_url))
+            .header("x-api-key", self.options.api_key.as_deref().unwrap_or_default())
+            .header("anthropic-version", API_VERSION)
+            .json(&body);
+        let response = self.http.send_idempotent(request).await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(AppError::from_response("AI request failed", status.as_u16(), body));
+        }
+
+        let reply: MessagesResponse = response.json().await?;
+        Ok(reply.content)
+    }
+}
+
+#[async_trait]
+impl LlmProvider for Anthropic {
+    fn name(&self) -> &str {
+        &self.options.model
+    }
+
+    async fn complete(&self, messages: &[ChatMessage]) -> Result<String> {
+        text(&self.send(messages, Vec::new(), None).await?)
+    }
+
+    // The model is made to call a tool whose input schema is the expected
+    // JSON, and the tool's input is returned as the reply
+    async fn complete_json(&self, messages: &[ChatMessage], output: &JsonOutput) -> Result<String> {
+        if !self.options.json_mode {
+            return self.complete(messages).await;
+        }
+        let choice = json!({ "type": "tool", "name": output.name });
+        let blocks = self.send(messages, vec![tool(output)], Some(choice)).await?;
+        match blocks.iter().find(|b| b.kind == "tool_use").and_then(|b| b.input.as_ref()) {
+            Some(input) => Ok(input.to_string()),
+            None => text(&blocks),
+        }
+    }
+
+    async fn complete_tools(&self, messages: &[ChatMessage], tools: &[JsonOutput]) -> Result<ChatMessage> {
+        let blocks = self.send(messages, tools.iter().map(tool).collect(), None).await?;
+        let calls = blocks
+            .iter()
+            .filter(|b| b.kind == "tool_use")
+            .map(|b| ToolCall {
+                id: b.id.clone(),
+                name: b.name.clone(),
+                arguments: b.input.clone().unwrap_or(Value::Null),
+            })
+            .collect();
+        let content: String = blocks.iter().filter(|b| b.kind == "text").map(|b| b.text.as_str()).collect();
+        Ok(ChatMessage::tool_calls(content, calls))
+    }
+}
+
@@ -0,0 +1,180 @@
+use serde::Deserialize;
+use serde_json::json;
+use crate::core::Task;
+use crate::core::dates;
+use crate::core::error::{AppError, Result};
+use super::json::{extract_json, JsonOutput};
+use super::provider::ChatMessage;
+use super::{AIAssistant, MAX_ESTIMATE_MINUTES};
+
+// Most subtasks a single breakdown may propose
+const MAX_SUBTASKS: usize = 12;
+
+// A step proposed by `:breakdown`, before the user has reviewed it
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Subtask {
+    pub title: String,
+    #[serde(default)]
+    pub estimate_minutes: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ParsedBreakdown {
+    subtasks: Vec<Subtask>,
+}
+
+impl ParsedBreakdown {
+    fn from_reply(reply: &str) -> Result<Vec<Subtask>> {
+        let value = extract_json(reply)
+            .ok_or_else(|| AppError::Serialization("the reply contains no JSON object".to_string()))?;
+        let parsed: ParsedBreakdown = serde_json::from_value(value)
+            .map_err(|e| AppError::Serialization(format!("the JSON doesn't match the expected fields: {}", e)))?;
+        parsed.validate()?;
+        Ok(parsed.subtasks)
+    }
+
+    fn validate(&self) -> Result<()> {
+        let invalid = |msg: String| Err(AppError::Serialization(msg));
+
+        if self.subtasks.is_empty() {
+            return invalid("\"subtasks\" must not be empty".to_string());
+        }
+        if self.subtasks.len() > MAX_SUBTASKS {
+            return invalid(format!("\"subtasks\" has {} items; propose at most {}", self.subtasks.len(), MAX_SUBTASKS));
+        }
+        for (i, subtask) in self.subtasks.iter().enumerate() {
+            if subtask.title.trim().is_empty() {
+                return invalid(format!("subtask {} has an empty \"title\"", i + 1));
+            }
+            if let Some(minutes) = subtask.estimate_minutes {
+                if !(1..=MAX_ESTIMATE_MINUTES).contains(&minutes) {
+                    return invalid(format!(
+                        "subtask {} \"estimate_minutes\" must be between 1 and {}, not {}",
+                        i + 1,
+                        MAX_ESTIMATE_MINUTES,
+                        minutes
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+const BREAKDOWN_INSTRUCTIONS: &str = r#"Break the user's task into the concrete steps needed to finish it and return ONLY a JSON object with one field:
+- subtasks: the steps in the order they should be done (array), each with
+  - title: a short, actionable step (string)
+  - estimate_minutes: a realistic estimate of the effort for that step (integer)
+
+Propose between 2 and 8 steps, never more than 12. Don't repeat the task itself as a step.
+Return only valid JSON, no explanation."#;
+
+fn breakdown_output() -> JsonOutput {
+    JsonOutput {
+        name: "propose_subtasks",
+        description: "Propose the subtasks needed to finish a task",
+        schema: json!({
+            "type": "object",
+            "properties": {
+                "subtasks": {
+                    "type": "array",
+                    "maxItems": MAX_SUBTASKS,
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "title": { "type": "string", "description": "A short, actionable step" },
+                            "estimate_minutes": { "type": "integer", "minimum": 1, "maximum": MAX_ESTIMATE_MINUTES }
+                        },
+                        "required": ["title"]
+                    }
+                }
+            },
+            "required": ["subtasks"]
+        }),
+    }
+}
+
+// What the model is told about the task being broken down
+fn describe(task: &Task) -> String {
+    let mut text = format!("Task: \"{}\"", task.title);
+    if !task.description.is_empty() {
+        text.push_str(&format!("\nNotes: {}", task.description));
+    }
+    if let Some(minutes) = task.estimate_minutes {
+        text.push_str(&format!("\nEstimated effort for the whole task: {}", dates::format_duration(minutes)));
+    }
+    text
+}
+
+impl AIAssistant {
+    // Proposed subtasks for `task`. Unlike parsing there is no offline
+    // fallback: without a model there is nothing sensible to propose.
+    pub async fn breakdown(&self, task: &Task) -> Result<Vec<Subtask>> {
+        let messages = vec![
+            ChatMessage::system(BREAKDOWN_INSTRUCTIONS),
+            ChatMessage::user(describe(task)),
+        ];
+        self.complete_valid(messages, &breakdown_output(), ParsedBreakdown::from_reply).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::mock::MockProvider;
+
+    #[test]
+    fn replies_are_validated() {
+        let too_many = json!({ "subtasks": vec![json!({ "title": "step" }); 13] }).to_string();
+        let cases = [
+            (r#"{"subtasks": [{"title": "Draft notes", "estimate_minutes": 30}, {"title": "Tag release"}]}"#, None),
+            (r#"{"subtasks": []}"#, Some("must not be empty")),
+            (too_many.as_str(), Some("at most 12")),
+            (r#"{"subtasks": [{"title": "Draft"}, {"title": " "}]}"#, Some("subtask 2 has an empty")),
+            (r#"{"subtasks": [{"title": "Draft", "estimate_minutes": 0}]}"#, Some("between 1 and")),
+            (r#"{"steps": [{"title": "Draft"}]}"#, Some("expected fields")),
+            ("Sorry, I can't help with that", Some("no JSON object")),
+        ];
+        for (reply, problem) in cases {
+            match (ParsedBreakdown::from_reply(reply), problem) {
+                (Ok(_), None) => {}
+                (Err(AppError::Serialization(message)), Some(problem)) => {
+                    assert!(message.contains(problem), "{:?}: {}", reply, message)
+                }
+                (result, _) => panic!("{:?} gave {:?}", reply, result),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn breakdown_sends_the_task_and_repairs_bad_replies() {
+        let mock = MockProvider::new()
+            .reply(r#"{"subtasks": []}"#)
+            .reply(r#"{"subtasks": [{"title": "Freeze the branch", "estimate_minutes": 15}, {"title": "Write changelog"}]}"#);
+        let mut task = Task::new("Prepare quarterly release".to_string());
+        task.description = "ship on the 30th".to_string();
+
+        let subtasks = AIAssistant::new(Box::new(mock.clone())).breakdown(&task).await.unwrap();
+        assert_eq!(
+            subtasks,
+            [
+                Subtask { title: "Freeze the branch".to_string(), estimate_minutes: Some(15) },
+                Subtask { title: "Write changelog".to_string(), estimate_minutes: None },
+            ]
+        );
+
+        let received = mock.received();
+        assert_eq!(received.len(), 2);
+        assert!(received[0][1].content.contains("Prepare quarterly release"));
+        assert!(received[0][1].content.contains("ship on the 30th"));
+        assert!(received[1][3].content.contains("must not be empty"));
+    }
+
+    #[tokio::test]
+    async fn provider_errors_are_reported() {
+        let mock = MockProvider::new();
+        let task = Task::new("Prepare quarterly release".to_string());
+        let result = AIAssistant::new(Box::new(mock)).breakdown(&task).await;
+        assert!(matches!(result, Err(AppError::Network(_))));
+    }
+}
@@ -0,0 +1,725 @@
+use chrono::{DateTime, Duration, Local, NaiveDate};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use crate::config::Workflow;
+use crate::core::activity;
+use crate::core::error::{AppError, Result};
+use crate::core::projects::resolve;
+use crate::core::tags::normalize_tag;
+use crate::core::{dates, Project, Status, Task};
+use super::json::JsonOutput;
+use super::provider::{ChatMessage, ToolCall};
+use super::{parse_priority, priority_name, task_output, AIAssistant, ParsedTask};
+
+// Rounds of tool calls in one turn before the model has to answer
+const MAX_TOOL_ROUNDS: usize = 8;
+// Most tasks one list_tasks call returns
+const MAX_LISTED: usize = 50;
+// Leading characters of a task's uuid the model refers to it by
+const SHORT_ID: usize = 8;
+
+// What the chat mode works on: a fresh copy of the user's tasks and
+// projects. Tools only ever change this copy; the result is a proposal.
+#[derive(Debug, Clone)]
+pub struct ChatContext {
+    pub tasks: Vec<Task>,
+    pub projects: Vec<Project>,
+    pub user_id: String,
+    pub workflow: Workflow,
+    // Project new tasks go to when the request doesn't name one
+    pub default_project: Option<String>,
+}
+
+impl ChatContext {
+    // Same rule as AppState::can_edit: a viewer of a shared project can
+    // only change the tasks assigned to them
+    fn can_edit(&self, task: &Task) -> bool {
+        let project = task
+            .project_id
+            .as_ref()
+            .and_then(|id| self.projects.iter().find(|p| &p.id == id));
+        project.is_none_or(|p| p.role.can_edit()) || task.assignee_id.as_deref() == Some(self.user_id.as_str())
+    }
+
+    fn project_name(&self, task: &Task) -> Option<&str> {
+        let id = task.project_id.as_ref()?;
+        self.projects.iter().find(|p| &p.id == id).map(|p| p.name.as_str())
+    }
+}
+
+// One change the assistant proposes, shown to the user before it is saved
+#[derive(Debug, Clone)]
+pub enum TaskChange {
+    Create(Task),
+    // The previous version is boxed to keep the enum small
+    Update { before: Box<Task>, after: Task },
+    Delete(Task),
+}
+
+impl TaskChange {
+    pub fn task_id(&self) -> &str {
+        match self {
+            TaskChange::Create(task) | TaskChange::Delete(task) => &task.id,
+            TaskChange::Update { after, .. } => &after.id,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ChatReply {
+    // The conversation including this turn, to send along with the next one
+    pub history: Vec<ChatMessage>,
+    pub text: String,
+    pub changes: Vec<TaskChange>,
+}
+
+const CHAT_INSTRUCTIONS: &str = r#"You are the assistant of a terminal todo app. Answer the user's questions about their tasks and make the changes they ask for with the tools.
+- Look tasks up with list_tasks before answering or changing them, and refer to them by the ids it returns.
+- update_tasks, create_task and delete_tasks only propose changes. The user reviews all of them and confirms before anything is saved, so say briefly what you proposed instead of claiming it is done.
+- Tasks marked read_only can't be changed.
+- Dates are in the user's local time, as YYYY-MM-DD or YYYY-MM-DDTHH:MM.
+Keep answers short; they are shown in a terminal."#;
+
+fn chat_prompt(now: DateTime<Local>, projects: &[Project]) -> String {
+    let mut prompt = format!(
+        "{}\n\nCurrent local time: {} (UTC{})",
+        CHAT_INSTRUCTIONS,
+        now.format("%A %Y-%m-%d %H:%M"),
+        now.format("%:z"),
+    );
+    if !projects.is_empty() {
+        let names: Vec<&str> = projects.iter().map(|p| p.name.as_str()).collect();
+        prompt.push_str(&format!("\nProjects: {}", names.join(", ")));
+    }
+    prompt
+}
+
+fn chat_tools() -> Vec<JsonOutput> {
+    let priority = json!({ "type": "string", "enum": ["low", "medium", "high"] });
+    let status = json!({
+        "type": "string",
+        "enum": ["todo", "in_progress", "blocked", "waiting", "done", "cancelled"]
+    });
+    vec![
+        JsonOutput {
+            name: "list_tasks",
+            description: "List the user's tasks matching all of the given filters",
+            schema: json!({
+                "type": "object",
+                "properties": {
+                    "status": {
+                        "type": "string",
+                        "enum": ["open", "closed", "all", "todo", "in_progress", "blocked", "waiting", "done", "cancelled"],
+                        "description": "Defaults to open"
+                    },
+                    "tag": { "type": "string" },
+                    "project": { "type": "string", "description": "Project name, or \"inbox\" for tasks without one" },
+                    "text": { "type": "string", "description": "Words in the title or notes" },
+                    "due_before": { "type": "string", "description": "Due on or before this local date, YYYY-MM-DD" },
+                    "overdue": { "type": "boolean" }
+                }
+            }),
+        },
+        JsonOutput {
+            name: "update_tasks",
+            description: "Propose changes to existing tasks; only the given fields change",
+            schema: json!({
+                "type": "object",
+                "properties": {
+                    "updates": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "id": { "type": "string", "description": "Task id from list_tasks" },
+                                "title": { "type": "string" },
+                                "status": status,
+                                "priority": priority,
+                                "due": { "type": "string", "description": "New local due date, YYYY-MM-DD or YYYY-MM-DDTHH:MM" },
+                                "clear_due": { "type": "boolean", "description": "Remove the due date" },
+                                "shift_days": { "type": "integer", "description": "Move the due date by this many days" },
+                                "add_tags": { "type": "array", "items": { "type": "string" } },
+                                "remove_tags": { "type": "array", "items": { "type": "string" } },
+                                "project": { "type": "string", "description": "Project name, or \"inbox\"" }
+                            },
+                            "required": ["id"]
+                        }
+                    }
+                },
+                "required": ["updates"]
+            }),
+        },
+        JsonOutput { name: "create_task", description: "Propose a new task", ..task_output() },
+        JsonOutput {
+            name: "delete_tasks",
+            description: "Propose deleting tasks",
+            schema: json!({
+                "type": "object",
+                "properties": {
+                    "ids": { "type": "array", "items": { "type": "string" }, "description": "Task ids from list_tasks" }
+                },
+                "required": ["ids"]
+            }),
+        },
+    ]
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ListArgs {
+    status: Option<String>,
+    tag: Option<String>,
+    project: Option<String>,
+    text: Option<String>,
+    due_before: Option<String>,
+    #[serde(default)]
+    overdue: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct UpdateArgs {
+    updates: Vec<TaskUpdate>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TaskUpdate {
+    id: String,
+    title: Option<String>,
+    status: Option<String>,
+    priority: Option<String>,
+    due: Option<String>,
+    #[serde(default)]
+    clear_due: bool,
+    shift_days: Option<i64>,
+    #[serde(default)]
+    add_tags: Vec<String>,
+    #[serde(default)]
+    remove_tags: Vec<String>,
+    project: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeleteArgs {
+    ids: Vec<String>,
+}
+
+enum StatusFilter {
+    Open,
+    Closed,
+    All,
+    Is(Status),
+}
+
+fn short_id(id: &str) -> &str {
+    &id[..id.len().min(SHORT_ID)]
+}
+
+// Tool arguments; a model may send null for "no arguments"
+fn arguments<T: DeserializeOwned>(value: &Value) -> std::result::Result<T, String> {
+    let value = if value.is_null() { json!({}) } else { value.clone() };
+    serde_json::from_value(value).map_err(|e| format!("invalid arguments: {}", e))
+}
+
+// The tasks as the conversation sees them: the context with the changes
+// proposed so far applied. Tool errors are plain text for the model.
+struct Proposal<'a> {
+    context: &'a ChatContext,
+    changes: Vec<TaskChange>,
+}
+
+impl Proposal<'_> {
+    fn tasks(&self) -> Vec<Task> {
+        let mut tasks = self.context.tasks.clone();
+        for change in &self.changes {
+            match change {
+                TaskChange::Create(task) => tasks.push(task.clone()),
+                TaskChange::Update { after, .. } => {
+                    if let Some(task) = tasks.iter_mut().find(|t| t.id == after.id) {
+                        *task = after.clone();
+                    }
+                }
+                TaskChange::Delete(task) => tasks.retain(|t| t.id != task.id),
+            }
+        }
+        tasks
+    }
+
+    // A task by the id list_tasks gave, or any longer prefix of its uuid
+    fn find(&self, id: &str) -> std::result::Result<Task, String> {
+        let id = id.trim().trim_start_matches('#');
+        if id.len() < 4 {
+            return Err(format!("{:?} is not a task id from list_tasks", id));
+        }
+        let mut found: Vec<Task> = self.tasks().into_iter().filter(|t| t.id.starts_with(id)).collect();
+        match found.len() {
+            0 => Err(format!("no task has the id {:?}", id)),
+            1 => Ok(found.remove(0)),
+            _ => Err(format!("the id {:?} matches more than one task", id)),
+        }
+    }
+
+    fn run(&mut self, call: &ToolCall) -> String {
+        let result = match call.name.as_str() {
+            "list_tasks" => self.list(&call.arguments),
+            "update_tasks" => self.update(&call.arguments),
+            "create_task" => self.create(&call.arguments),
+            "delete_tasks" => self.delete(&call.arguments),
+            other => Err(format!("there is no tool named {:?}", other)),
+        };
+        match result {
+            Ok(value) => value.to_string(),
+            Err(problem) => json!({ "error": problem }).to_string(),
+        }
+    }
+
+    fn describe(&self, task: &Task) -> Value {
+        let mut value = json!({
+            "id": short_id(&task.id),
+            "title": task.title,
+            "status": task.status.as_str(),
+            "priority": priority_name(task.priority),
+        });
+        if let Some(due) = task.due_date {
+            value["due"] = json!(dates::format_local(due));
+        }
+        if !task.tags.is_empty() {
+            value["tags"] = json!(task.tags);
+        }
+        if let Some(project) = self.context.project_name(task) {
+            value["project"] = json!(project);
+        }
+        if let Some(minutes) = task.estimate_minutes {
+            value["estimate_minutes"] = json!(minutes);
+        }
+        if !task.description.is_empty() {
+            value["notes"] = json!(task.description);
+        }
+        if !self.context.can_edit(task) {
+            value["read_only"] = json!(true);
+        }
+        value
+    }
+
+    // A project name as a project id; "inbox" is no project
+    fn project_id(&self, name: &str) -> std::result::Result<Option<String>, String> {
+        if name.trim().eq_ignore_ascii_case("inbox") {
+            return Ok(None);
+        }
+        resolve(&self.context.projects, name)
+            .map(|p| Some(p.id.clone()))
+            .ok_or_else(|| format!("there is no project named {:?}", name))
+    }
+
+    fn list(&self, args: &Value) -> std::result::Result<Value, String> {
+        let args: ListArgs = arguments(args)?;
+        let status = match args.status.as_deref().unwrap_or("open") {
+            "open" => StatusFilter::Open,
+            "closed" => StatusFilter::Closed,
+            "all" => StatusFilter::All,
+            other => StatusFilter::Is(Status::parse(other).ok_or_else(|| format!("{:?} is not a status", other))?),
+        };
+        let project = args.project.as_deref().map(|name| self.project_id(name)).transpose()?;
+        let tag = match args.tag.as_deref() {
+            Some(tag) => Some(normalize_tag(tag).ok_or_else(|| format!("{:?} is not a usable tag", tag))?),
+            None => None,
+        };
+        let due_before = match args.due_before.as_deref() {
+            Some(date) => Some(
+                NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+                    .map_err(|_| format!("\"due_before\" must be a date like 2024-05-01, not {:?}", date))?,
+            ),
+            None => None,
+        };
+        let text = args.text.map(|t| t.to_lowercase());
+        let today = Local::now().date_naive();
+        let due_day = |task: &Task| task.due_date.map(|d| d.with_timezone(&Local).date_naive());
+
+        let matching: Vec<Task> = self
+            .tasks()
+            .into_iter()
+            .filter(|t| match status {
+                StatusFilter::Open => !t.status.is_closed(),
+                StatusFilter::Closed => t.status.is_closed(),
+                StatusFilter::All => true,
+                StatusFilter::Is(status) => t.status == status,
+            })
+            .filter(|t| tag.as_ref().is_none_or(|tag| t.tags.contains(tag)))
+            .filter(|t| project.as_ref().is_none_or(|id| &t.project_id == id))
+            .filter(|t| {
+                text.as_ref().is_none_or(|text| {
+                    t.title.to_lowercase().contains(text) || t.description.to_lowercase().contains(text)
+                })
+            })
+            .filter(|t| due_before.is_none_or(|date| due_day(t).is_some_and(|due| due <= date)))
+            .filter(|t| !args.overdue || (!t.status.is_closed() && due_day(t).is_some_and(|due| due < today)))
+            .collect();
+
+        let listed: Vec<Value> = matching.iter().take(MAX_LISTED).map(|t| self.describe(t)).collect();
+        let mut result = json!({ "count": matching.len(), "tasks": listed });
+        if matching.len() > MAX_LISTED {
+            result["note"] = json!(format!("only the first {} are listed; narrow the filters to see the rest", MAX_LISTED));
+        }
+        Ok(result)
+    }
+
+    fn update(&mut self, args: &Value) -> std::result::Result<Value, String> {
+        let args: UpdateArgs = arguments(args)?;
+        if args.updates.is_empty() {
+            return Err("\"updates\" must not be empty".to_string());
+        }
+        // All or nothing, so a bad entry doesn't leave half a request proposed
+        let saved = self.changes.clone();
+        for update in &args.updates {
+            let result = self
+                .find(&update.id)
+                .and_then(|task| self.apply(update, &task).map_err(|e| format!("task {}: {}", update.id, e)));
+            match result {
+                Ok(after) => self.propose_update(after),
+                Err(problem) => {
+                    self.changes = saved;
+                    return Err(problem);
+                }
+            }
+        }
+        Ok(json!({ "proposed": format!("{} update(s), shown to the user for review", args.updates.len()) }))
+    }
+
+    fn apply(&self, update: &TaskUpdate, task: &Task) -> std::result::Result<Task, String> {
+        if !self.context.can_edit(task) {
+            return Err("it is read-only for the user".to_string());
+        }
+        let mut after = task.clone();
+        if let Some(title) = &update.title {
+            if title.trim().is_empty() {
+                return Err("\"title\" must not be empty".to_string());
+            }
+            after.title = title.trim().to_string();
+        }
+        if let Some(name) = &update.status {
+            let status = Status::parse(name).ok_or_else(|| format!("{:?} is not a status", name))?;
+            if !self.context.workflow.allows(task.status, status) {
+                return Err(format!(
+                    "the workflow doesn't allow going from {} to {}",
+                    task.status.as_str(),
+                    status.as_str()
+                ));
+            }
+            after.status = status;
+        }
+        if let Some(name) = &update.priority {
+            after.priority = parse_priority(name)
+                .ok_or_else(|| format!("\"priority\" must be \"low\", \"medium\" or \"high\", not {:?}", name))?;
+        }
+        if update.clear_due {
+            after.due_date = None;
+        }
+        if let Some(due) = &update.due {
+            after.due_date = Some(
+                dates::parse_iso(due)
+                    .ok_or_else(|| format!("\"due\" must be a date like 2024-05-01 or 2024-05-01T17:30, not {:?}", due))?,
+            );
+        }
+        if let Some(days) = update.shift_days {
+            let due = after.due_date.ok_or("it has no due date to shift")?.with_timezone(&Local);
+            // By calendar days, so the time of day survives a DST change
+            after.due_date = Some(
+                dates::local_datetime(due.date_naive() + Duration::days(days), due.time())
+                    .ok_or("the shifted due date doesn't exist in the local timezone")?,
+            );
+        }
+        for tag in &update.remove_tags {
+            let tag = normalize_tag(tag).unwrap_or_default();
+            after.tags.retain(|t| *t != tag);
+        }
+        for tag in &update.add_tags {
+            let tag = normalize_tag(tag).ok_or_else(|| format!("{:?} is not a usable tag", tag))?;
+            if !after.tags.contains(&tag) {
+                after.tags.push(tag);
+            }
+        }
+        if let Some(name) = &update.project {
+            after.project_id = self.project_id(name)?;
+        }
+        Ok(after)
+    }
+
+    // Fold a new version of a task into the proposal, keeping one change
+    // per task
+    fn propose_update(&mut self, after: Task) {
+        match self.changes.iter_mut().find(|c| c.task_id() == after.id) {
+            Some(TaskChange::Create(task)) => *task = after,
+            Some(TaskChange::Update { after: current, .. }) => *current = after,
+            // find() doesn't return deleted tasks, so they can't be updated
+            Some(TaskChange::Delete(_)) => {}
+            None => {
+                if let Some(before) = self.context.tasks.iter().find(|t| t.id == after.id) {
+                    self.changes.push(TaskChange::Update { before: Box::new(before.clone()), after });
+                }
+            }
+        }
+        // An update that ends up where it started isn't a change
+        self.changes.retain(|change| match change {
+            TaskChange::Update { before, after } => !activity::changes(before, after).is_empty(),
+            _ => true,
+        });
+    }
+
+    fn create(&mut self, args: &Value) -> std::result::Result<Value, String> {
+        let parsed: ParsedTask = arguments(args)?;
+        match parsed.validate() {
+            Err(AppError::Serialization(problem)) => return Err(problem),
+            Err(e) => return Err(e.to_string()),
+            Ok(()) => {}
+        }
+        let mut task = parsed.into_task(&self.context.projects);
+        if task.project_id.is_none() {
+            task.project_id = self.context.default_project.clone();
+        }
+        let result = json!({ "proposed": "a new task, shown to the user for review", "id": short_id(&task.id) });
+        self.changes.push(TaskChange::Create(task));
+        Ok(result)
+    }
+
+    fn delete(&mut self, args: &Value) -> std::result::Result<Value, String> {
+        let args: DeleteArgs = arguments(args)?;
+        if args.ids.is_empty() {
+            return Err("\"ids\" must not be empty".to_string());
+        }
+        let saved = self.changes.clone();
+        for id in &args.ids {
+            let task = match self.find(id) {
+                Ok(task) if self.context.can_edit(&task) => Ok(task),
+                Ok(_) => Err(format!("task {} is read-only for the user", id)),
+                Err(problem) => Err(problem),
+            };
+            let task = match task {
+                Ok(task) => task,
+                Err(problem) => {
+                    self.changes = saved;
+                    return Err(problem);
+                }
+            };
+
+            let existing = self.changes.iter().position(|c| c.task_id() == task.id);
+            match existing.map(|i| self.changes.remove(i)) {
+                // Proposed and withdrawn: nothing to do
+                Some(TaskChange::Create(_)) => {}
+                Some(TaskChange::Update { before, .. }) => self.changes.push(TaskChange::Delete(*before)),
+                _ => self.changes.push(TaskChange::Delete(task)),
+            }
+        }
+        Ok(json!({ "proposed": format!("deleting {} task(s), shown to the user for review", args.ids.len()) }))
+    }
+}
+
+impl AIAssistant {
+    // One turn of the chat mode. `history` is the conversation so far,
+    // ending with the user's new message. The model looks tasks up and
+    // proposes changes through tools run against `context`; nothing is
+    // saved here, the caller shows the changes for confirmation.
+    pub async fn chat(&self, history: Vec<ChatMessage>, context: &ChatContext) -> Result<ChatReply> {
+        let tools = chat_tools();
+        let mut messages = vec![ChatMessage::system(chat_prompt(Local::now(), &context.projects))];
+        messages.extend(history);
+        let mut proposal = Proposal { context, changes: Vec::new() };
+
+        for _ in 0..MAX_TOOL_ROUNDS {
+            let reply = self.provider.complete_tools(&messages, &tools).await?;
+            let calls = reply.tool_calls.clone();
+            let text = reply.content.trim().to_string();
+            messages.push(reply);
+            if calls.is_empty() {
+                messages.remove(0);
+                return Ok(ChatReply { history: messages, text, changes: proposal.changes });
+            }
+            for call in &calls {
+                let result = proposal.run(call);
+                messages.push(ChatMessage::tool_result(call, result));
+            }
+        }
+        Err(AppError::Validation(format!(
+            "{} kept calling tools without answering; try a simpler request",
+            self.provider.name()
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::mock::MockProvider;
+    use crate::core::{Priority, Role};
+    use chrono::Utc;
+
+    fn task(title: &str, tags: &[&str]) -> Task {
+        let mut task = Task::new(title.to_string());
+        task.tags = tags.iter().map(|t| t.to_string()).collect();
+        task
+    }
+
+    fn context(tasks: Vec<Task>) -> ChatContext {
+        let mut shared = Project::new("Shared".to_string(), None, "someone".to_string());
+        shared.role = Role::Viewer;
+        ChatContext {
+            tasks,
+            projects: vec![Project::new("Work".to_string(), None, "me".to_string()), shared],
+            user_id: "me".to_string(),
+            workflow: Workflow::default(),
+            default_project: None,
+        }
+    }
+
+    fn id(task: &Task) -> String {
+        short_id(&task.id).to_string()
+    }
+
+    // The tool results sent back to the model, in order
+    fn tool_results(mock: &MockProvider) -> Vec<String> {
+        let received = mock.received();
+        let last = received.last().unwrap();
+        last.iter().filter(|m| m.tool_call_id.is_some()).map(|m| m.content.clone()).collect()
+    }
+
+    #[tokio::test]
+    async fn questions_are_answered_from_list_tasks() {
+        let mut overdue = task("Renew passport", &[]);
+        overdue.due_date = Some(Utc::now() - chrono::Duration::days(3));
+        let mut later = task("Book flights", &[]);
+        later.due_date = Some(Utc::now() + chrono::Duration::days(3));
+        let context = context(vec![overdue, later]);
+
+        let mock = MockProvider::new()
+            .call("list_tasks", json!({ "overdue": true }))
+            .reply("Renew passport is 3 days overdue.");
+        let ai = AIAssistant::new(Box::new(mock.clone()));
+        let reply = ai.chat(vec![ChatMessage::user("what's overdue?")], &context).await.unwrap();
+
+        assert_eq!(reply.text, "Renew passport is 3 days overdue.");
+        assert!(reply.changes.is_empty());
+        let results = tool_results(&mock);
+        assert!(results[0].contains("Renew passport"));
+        assert!(!results[0].contains("Book flights"));
+        // The system prompt isn't part of the history carried forward
+        assert_eq!(reply.history.len(), 4);
+        assert_eq!(reply.history[0], ChatMessage::user("what's overdue?"));
+    }
+
+    #[tokio::test]
+    async fn updates_are_proposed_not_applied() {
+        let due = dates::parse_iso("2030-01-04T17:00").unwrap();
+        let mut deploy = task("Deploy", &["infra"]);
+        deploy.due_date = Some(due);
+        let mut certs = task("Rotate certs", &["infra"]);
+        certs.due_date = Some(due);
+        let docs = task("Write docs", &[]);
+        let context = context(vec![deploy.clone(), certs.clone(), docs]);
+
+        let mock = MockProvider::new()
+            .call("list_tasks", json!({ "tag": "infra" }))
+            .call(
+                "update_tasks",
+                json!({ "updates": [
+                    { "id": id(&deploy), "shift_days": 7 },
+                    { "id": id(&certs), "due": "2030-01-10", "priority": "high" }
+                ] }),
+            )
+            .reply("I've moved both infra tasks to next week.");
+        let ai = AIAssistant::new(Box::new(mock.clone()));
+        let reply = ai
+            .chat(vec![ChatMessage::user("move everything tagged infra to next week")], &context)
+            .await
+            .unwrap();
+
+        assert_eq!(reply.changes.len(), 2);
+        let TaskChange::Update { before, after } = &reply.changes[0] else {
+            panic!("{:?}", reply.changes[0]);
+        };
+        assert_eq!(before.due_date, Some(due));
+        assert_eq!(after.due_date, dates::parse_iso("2030-01-11T17:00"));
+        let TaskChange::Update { after, .. } = &reply.changes[1] else {
+            panic!("{:?}", reply.changes[1]);
+        };
+        assert_eq!(after.due_date, dates::parse_iso("2030-01-10"));
+        assert_eq!(after.priority, Priority::High);
+        // The context is left alone
+        assert_eq!(context.tasks[0].due_date, Some(due));
+
+        let results = tool_results(&mock);
+        assert!(results[0].contains("\"count\":2"));
+        assert!(results[1].contains("proposed"));
+    }
+
+    #[tokio::test]
+    async fn invalid_calls_are_reported_to_the_model() {
+        let mut done = task("Ship it", &[]);
+        done.status = Status::Done;
+        let mut context = context(Vec::new());
+        let mut shared = task("Read only", &[]);
+        shared.project_id = Some(context.projects[1].id.clone());
+        context.tasks = vec![done.clone(), shared.clone(), task("Plain", &[])];
+
+        let mock = MockProvider::new()
+            .call("update_tasks", json!({ "updates": [{ "id": id(&shared), "title": "Mine now" }] }))
+            .call("update_tasks", json!({ "updates": [{ "id": id(&done), "status": "blocked" }] }))
+            .call("update_tasks", json!({ "updates": [{ "id": "nope1234", "title": "x" }] }))
+            .call("delete_tasks", json!({ "ids": [id(&done), id(&shared)] }))
+            .call("forget_everything", json!({}))
+            .reply("I couldn't change those.");
+        let ai = AIAssistant::new(Box::new(mock.clone()));
+        let reply = ai.chat(vec![ChatMessage::user("tidy up")], &context).await.unwrap();
+
+        let results = tool_results(&mock);
+        assert!(results[0].contains("read-only"), "{}", results[0]);
+        assert!(results[1].contains("workflow doesn't allow going from done to blocked"), "{}", results[1]);
+        assert!(results[2].contains("no task has the id"), "{}", results[2]);
+        assert!(results[3].contains("read-only"), "{}", results[3]);
+        assert!(results[4].contains("no tool named"), "{}", results[4]);
+        // The failed delete didn't keep its first, valid half
+        assert!(reply.changes.is_empty());
+    }
+
+    #[tokio::test]
+    async fn changes_to_the_same_task_are_merged() {
+        let old = task("Old idea", &[]);
+        let keep = task("Keep me", &["misc"]);
+        let context = context(vec![old.clone(), keep.clone()]);
+
+        let mock = MockProvider::new()
+            .call("create_task", json!({ "title": "Call the plumber", "project": "Work" }))
+            .call("update_tasks", json!({ "updates": [{ "id": id(&old), "title": "Renamed" }] }))
+            .call("delete_tasks", json!({ "ids": [id(&old)] }))
+            .call("update_tasks", json!({ "updates": [{ "id": id(&keep), "add_tags": ["misc"] }] }))
+            .reply("Done.");
+        let ai = AIAssistant::new(Box::new(mock.clone()));
+        let reply = ai.chat(vec![ChatMessage::user("clean up")], &context).await.unwrap();
+
+        let created = tool_results(&mock)[0].clone();
+        let new_id: Value = serde_json::from_str(&created).unwrap();
+        assert_eq!(reply.changes.len(), 2);
+        match &reply.changes[0] {
+            TaskChange::Create(task) => {
+                assert_eq!(task.title, "Call the plumber");
+                assert_eq!(task.project_id, Some(context.projects[0].id.clone()));
+                assert_eq!(new_id["id"], json!(short_id(&task.id)));
+            }
+            other => panic!("{:?}", other),
+        }
+        // Renamed and then deleted: the delete shows the original task, and
+        // the tag it already had is no change at all
+        match &reply.changes[1] {
+            TaskChange::Delete(task) => assert_eq!(task.title, "Old idea"),
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn endless_tool_calls_give_up() {
+        let mut mock = MockProvider::new();
+        for _ in 0..MAX_TOOL_ROUNDS {
+            mock = mock.call("list_tasks", json!({}));
+        }
+        let ai = AIAssistant::new(Box::new(mock));
+        let result = ai.chat(vec![ChatMessage::user("loop")], &context(Vec::new())).await;
+        assert!(matches!(result, Err(AppError::Validation(_))));
+    }
+}
@@ -0,0 +1,86 @@
+use serde_json::Value;
+
+// Shape a structured reply must have. Providers with a JSON mode or tool
+// calling pass the schema along; the rest only see it described in the prompt.
+#[derive(Debug, Clone)]
+pub struct JsonOutput {
+    // Tool name for providers that return structured output as a tool call
+    pub name: &'static str,
+    pub description: &'static str,
+    pub schema: Value,
+}
+
+// The first JSON object in a model reply. Models wrap JSON in code fences,
+// lead with "Here is the task:" or add notes afterwards; this skips all of
+// that by scanning for a balanced {...} that parses, ignoring braces inside
+// strings.
+pub fn extract_json(reply: &str) -> Option<Value> {
+    let bytes = reply.as_bytes();
+    let mut start = 0;
+    while let Some(offset) = reply[start..].find('{') {
+        let open = start + offset;
+        if let Some(close) = matching_brace(&bytes[open..]) {
+            if let Ok(value @ Value::Object(_)) = serde_json::from_str(&reply[open..=open + close]) {
+                return Some(value);
+            }
+        }
+        start = open + 1;
+    }
+    None
+}
+
+// Index of the brace closing the one at the start of `text`
+fn matching_brace(text: &[u8]) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (i, &byte) in text.iter().enumerate() {
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match byte {
+            b'"' => in_string = true,
+            b'{' => depth += 1,
+            b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn finds_the_object_in_surrounding_text() {
+        let cases = [
+            (r#"{"title": "a"}"#, Some(json!({ "title": "a" }))),
+            ("```json\n{\"title\": \"a\"}\n```", Some(json!({ "title": "a" }))),
+            ("Here is the task:\n{\"title\": \"a\"}\nLet me know!", Some(json!({ "title": "a" }))),
+            (r#"{"title": "use {braces} and \"quotes\""}"#, Some(json!({ "title": "use {braces} and \"quotes\"" }))),
+            (r#"{"outer": {"inner": 1}} and {"second": 2}"#, Some(json!({ "outer": { "inner": 1 } }))),
+            // A stray brace in the prose before the real object
+            (r#"Note: {not json} {"title": "a"}"#, Some(json!({ "title": "a" }))),
+            (r#"{"title": "unterminated""#, None),
+            ("no json here", None),
+            ("[1, 2, 3]", None),
+        ];
+        for (reply, expected) in cases {
+            assert_eq!(extract_json(reply), expected, "reply: {}", reply);
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use crate::core::error::{AppError, Result};
+use super::json::JsonOutput;
+use super::provider::{ChatMessage, LlmProvider, ToolCall};
+
+// Scripted provider for tests: replies are returned in order and every
+// conversation it receives is recorded. Running out of replies is a
+// network error, like an unreachable server.
+#[derive(Clone, Default)]
+pub struct MockProvider {
+    replies: Arc<Mutex<VecDeque<Result<ChatMessage>>>>,
+    received: Arc<Mutex<Vec<Vec<ChatMessage>>>>,
+    embeddings: Arc<Mutex<HashMap<String, Vec<f32>>>>,
+    // Texts embedded so far
+    embedded: Arc<Mutex<usize>>,
+}
+
+impl MockProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn reply(self, text: &str) -> Self {
+        self.replies.lock().unwrap().push_back(Ok(ChatMessage::assistant(text)));
+        self
+    }
+
+    // A turn that calls one tool
+    pub fn call(self, name: &str, arguments: Value) -> Self {
+        let id = format!("call_{}", self.replies.lock().unwrap().len() + 1);
+        let call = ToolCall { id, name: name.to_string(), arguments };
+        self.replies.lock().unwrap().push_back(Ok(ChatMessage::tool_calls("", vec![call])));
+        self
+    }
+
+    pub fn fail(self, error: AppError) -> Self {
+        self.replies.lock().unwrap().push_back(Err(error));
+        self
+    }
+
+    // Makes the mock support embeddings; texts without one fail
+    pub fn embedding(self, text: &str, vector: Vec<f32>) -> Self {
+        self.embeddings.lock().unwrap().insert(text.to_string(), vector);
+        self
+    }
+
+    pub fn embedded(&self) -> usize {
+        *self.embedded.lock().unwrap()
+    }
+
+    pub fn received(&self) -> Vec<Vec<ChatMessage>> {
+        self.received.lock().unwrap().clone()
+    }
+
+    fn next(&self, messages: &[ChatMessage]) -> Result<ChatMessage> {
+        self.received.lock().unwrap().push(messages.to_vec());
+        self.replies
+            .lock()
+            .unwrap()
+            .pop_front()
+            .unwrap_or_else(|| Err(AppError::Network("mock provider has no reply".to_string())))
+    }
+}
+
+#[async_trait]
+impl LlmProvider for MockProvider {
+    fn name(&self) -> &str {
+        "mock"
+    }
+
+    async fn complete(&self, messages: &[ChatMessage]) -> Result<String> {
+        self.next(messages).map(|reply| reply.content)
+    }
+
+    async fn complete_tools(&self, messages: &[ChatMessage], _tools: &[JsonOutput]) -> Result<ChatMessage> {
+        self.next(messages)
+    }
+
+    fn can_embed(&self) -> bool {
+        !self.embeddings.lock().unwrap().is_empty()
+    }
+
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let embeddings = self.embeddings.lock().unwrap();
+        let vectors = texts
+            .iter()
+            .map(|text| {
+                embeddings
+                    .get(text)
+                    .cloned()
+                    .ok_or_else(|| AppError::Network(format!("mock provider has no embedding for {:?}", text)))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        *self.embedded.lock().unwrap() += texts.len();
+        Ok(vectors)
+    }
+}
@@ -1,162 +1,291 @@
-use reqwest::Client;
-use serde::{Deserialize, Serialize};
-use crate::core::{Task, Priority};
-use chrono::{Utc, Duration};
-
-#[derive(Serialize)]
-struct GroqRequest {
-    model: String,
-    messages: Vec<Message>,
-    temperature: f32,
-    max_tokens: i32,
+use serde::Deserialize;
+use serde_json::json;
+use crate::config::{AISettings, NetworkSettings};
+use crate::core::{Task, Priority, Project};
+use crate::core::error::{AppError, Result};
+use crate::core::projects::{extract_project, resolve};
+use crate::core::tags::{extract_tags, merge_into, normalize_tag};
+use crate::core::dates;
+use crate::core::recurrence::Recurrence;
+use chrono::{DateTime, Local};
+use log::warn;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+pub mod anthropic;
+pub mod breakdown;
+pub mod chat;
+pub mod json;
+#[cfg(test)]
+pub mod mock;
+pub mod offline;
+pub mod ollama;
+pub mod openai;
+pub mod plan;
+pub mod provider;
+pub mod similar;
+#[cfg(test)]
+mod tests;
+
+use json::{extract_json, JsonOutput};
+use provider::{ChatMessage, LlmProvider};
+
+#[derive(Debug, Deserialize)]
+struct ParsedTask {
+    title: String,
+    description: Option<String>,
+    priority: Option<String>,
+    // Local date, optionally with a time of day, in ISO 8601
+    due: Option<String>,
+    estimate_minutes: Option<i64>,
+    // How often the task repeats: "daily", "every 2 weeks", ...
+    repeat: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    // Name of one of the user's projects
+    project: Option<String>,
 }
 
-#[derive(Serialize, Deserialize)]
-struct Message {
-    role: String,
-    content: String,
+// Furthest ahead a parsed due date may be, in days
+const MAX_DUE_DAYS: i64 = 3650;
+// Longest plausible estimate for a single task: a month of working days
+const MAX_ESTIMATE_MINUTES: i64 = 30 * 8 * 60;
+
+impl ParsedTask {
+    // Decode and check a model's reply. Error messages are sent back to the
+    // model when asking it to correct itself, so they name the bad field.
+    fn from_reply(reply: &str) -> Result<Self> {
+        let value = extract_json(reply)
+            .ok_or_else(|| AppError::Serialization("the reply contains no JSON object".to_string()))?;
+        let parsed: ParsedTask = serde_json::from_value(value)
+            .map_err(|e| AppError::Serialization(format!("the JSON doesn't match the expected fields: {}", e)))?;
+        parsed.validate()?;
+        Ok(parsed)
+    }
+
+    fn validate(&self) -> Result<()> {
+        let invalid = |msg: String| Err(AppError::Serialization(msg));
+
+        if self.title.trim().is_empty() {
+            return invalid("\"title\" must not be empty".to_string());
+        }
+        if let Some(priority) = &self.priority {
+            if parse_priority(priority).is_none() {
+                return invalid(format!("\"priority\" must be \"low\", \"medium\" or \"high\", not {:?}", priority));
+            }
+        }
+        if let Some(due) = &self.due {
+            let Some(date) = dates::parse_iso(due) else {
+                return invalid(format!("\"due\" must be a date like 2024-05-01 or 2024-05-01T17:30, not {:?}", due));
+            };
+            let days = (date.with_timezone(&Local).date_naive() - Local::now().date_naive()).num_days();
+            if days < 0 {
+                return invalid(format!("\"due\" is in the past ({}); use a date from today on", due));
+            }
+            if days > MAX_DUE_DAYS {
+                return invalid(format!("\"due\" is more than {} days away ({})", MAX_DUE_DAYS, due));
+            }
+        }
+        if let Some(minutes) = self.estimate_minutes {
+            if !(1..=MAX_ESTIMATE_MINUTES).contains(&minutes) {
+                return invalid(format!("\"estimate_minutes\" must be between 1 and {}, not {}", MAX_ESTIMATE_MINUTES, minutes));
+            }
+        }
+        if let Some(repeat) = &self.repeat {
+            if Recurrence::parse(repeat).is_none() {
+                return invalid(format!("\"repeat\" must be like \"daily\", \"weekly\" or \"every 3 days\", not {:?}", repeat));
+            }
+        }
+        if let Some(tag) = self.tags.iter().find(|t| normalize_tag(t).is_none()) {
+            return invalid(format!("{:?} is not a usable tag", tag));
+        }
+        Ok(())
+    }
+
+    fn into_task(self, projects: &[Project]) -> Task {
+        let priority = self.priority.as_deref().and_then(parse_priority).unwrap_or(Priority::Medium);
+
+        let mut task = Task::new(self.title.trim().to_string());
+        task.description = self.description.unwrap_or_default().trim().to_string();
+        task.priority = priority;
+        task.due_date = self.due.as_deref().and_then(dates::parse_iso);
+        task.estimate_minutes = self.estimate_minutes.and_then(|m| u32::try_from(m).ok());
+        task.recurrence = self.repeat.as_deref().and_then(Recurrence::parse);
+        merge_into(&mut task, self.tags.iter().filter_map(|t| normalize_tag(t)).collect());
+        // A project the user doesn't have is dropped rather than guessed at
+        task.project_id = self
+            .project
+            .and_then(|name| resolve(projects, &name))
+            .map(|p| p.id.clone());
+        task
+    }
 }
 
-#[derive(Deserialize)]
-struct GroqResponse {
-    choices: Vec<Choice>,
+const PARSE_INSTRUCTIONS: &str = r#"Parse the task input and return ONLY a JSON object with these fields:
+- title: the main task, short and without dates, times, tags or notes (string)
+- description: extra details or notes from the input, e.g. "remember lactose-free" (string, optional)
+- priority: "low", "medium", or "high" (string, optional)
+- due: when the task is due in the user's local time, as "YYYY-MM-DD" or "YYYY-MM-DDTHH:MM" when a time of day is given (string, optional)
+- estimate_minutes: how long the task will take, if the input says (integer, optional)
+- repeat: how often the task recurs, if the input says: "daily", "weekly", "monthly", "yearly" or "every N days/weeks/months/years" (string, optional). For "every monday" use "weekly" and the next monday as due.
+- tags: short lowercase topic tags implied by the input, including #hashtags (array of strings, optional)
+- project: one of the user's projects if the input names it (string, optional)
+
+Work out relative dates like "tomorrow" or "next friday" from the current time below.
+Return only valid JSON, no explanation."#;
+
+// The instructions plus what the model needs to resolve relative dates and
+// project names
+fn parse_prompt(now: DateTime<Local>, projects: &[Project]) -> String {
+    let mut prompt = format!(
+        "{}\n\nCurrent local time: {} (UTC{})",
+        PARSE_INSTRUCTIONS,
+        now.format("%A %Y-%m-%d %H:%M"),
+        now.format("%:z"),
+    );
+    if !projects.is_empty() {
+        let names: Vec<&str> = projects.iter().map(|p| p.name.as_str()).collect();
+        prompt.push_str(&format!("\nProjects: {}", names.join(", ")));
+    }
+    prompt
 }
 
-#[derive(Deserialize)]
-struct Choice {
-    message: Message,
+fn parse_priority(name: &str) -> Option<Priority> {
+    match name.to_lowercase().as_str() {
+        "low" => Some(Priority::Low),
+        "medium" => Some(Priority::Medium),
+        "high" => Some(Priority::High),
+        _ => None,
+    }
 }
 
-#[derive(Deserialize)]
-struct ParsedTask {
-    title: String,
-    priority: Option<String>,
-    due_days: Option<i64>,
+fn priority_name(priority: Priority) -> &'static str {
+    match priority {
+        Priority::Low => "low",
+        Priority::Medium => "medium",
+        Priority::High => "high",
+    }
+}
+
+// How often an invalid reply is sent back for correction before giving up
+const REPAIR_ATTEMPTS: usize = 1;
+
+fn task_output() -> JsonOutput {
+    JsonOutput {
+        name: "create_task",
+        description: "Create a task from the user's input",
+        schema: json!({
+            "type": "object",
+            "properties": {
+                "title": { "type": "string", "description": "The main task" },
+                "description": { "type": "string", "description": "Extra details or notes" },
+                "priority": { "type": "string", "enum": ["low", "medium", "high"] },
+                "due": { "type": "string", "description": "Local due date, YYYY-MM-DD or YYYY-MM-DDTHH:MM" },
+                "estimate_minutes": { "type": "integer", "minimum": 1, "maximum": MAX_ESTIMATE_MINUTES },
+                "repeat": { "type": "string", "description": "daily, weekly, monthly, yearly or every N days/weeks/months/years" },
+                "tags": { "type": "array", "items": { "type": "string" }, "description": "Short lowercase topic tags" },
+                "project": { "type": "string", "description": "One of the user's projects" }
+            },
+            "required": ["title"]
+        }),
+    }
 }
 
 pub struct AIAssistant {
-    client: Client,
-    api_key: String,
+    provider: Box<dyn LlmProvider>,
+    // Parse with keyword rules when the provider fails or keeps replying
+    // with invalid output, instead of reporting an error
+    offline_fallback: bool,
+    // Embeddings by title, so tasks aren't embedded again on every `:add`
+    embeddings: Mutex<HashMap<String, Vec<f32>>>,
 }
 
 impl AIAssistant {
-    pub fn new(api_key: String) -> Self {
+    pub fn new(provider: Box<dyn LlmProvider>) -> Self {
+        Self { provider, offline_fallback: true, embeddings: Mutex::default() }
+    }
+
+    // The provider chosen in the config's ai_settings
+    pub fn from_settings(settings: &AISettings, network: &NetworkSettings) -> Self {
         Self {
-            client: Client::new(),
-            api_key,
+            offline_fallback: settings.offline_fallback,
+            ..Self::new(provider::from_settings(settings, network))
         }
     }
 
-    pub async fn parse_task(&self, input: &str) -> Result<Task, Box<dyn std::error::Error>> {
-        let prompt = format!(
-            r#"Parse this task input and return ONLY a JSON object with these fields:
-- title: the main task (string)
-- priority: "low", "medium", or "high" (string, optional)
-- due_days: days from now for due date (number, optional)
-
-Input: "{}"
+    // `projects` are the user's projects, used to resolve "@name" and
+    // "for project X" references to a project_id
+    pub async fn parse_task(&self, input: &str, projects: &[Project]) -> Result<Task> {
+        // Explicit +tags and @project are taken as-is rather than left to the model
+        let (text, tags) = extract_tags(input);
+        let (text, project) = extract_project(&text);
+        let project_id = project
+            .and_then(|name| resolve(projects, &name))
+            .map(|p| p.id.clone());
 
-Return only valid JSON, no explanation."#,
-            input
-        );
+        let messages = vec![
+            ChatMessage::system(parse_prompt(Local::now(), projects)),
+            ChatMessage::user(format!("Input: \"{}\"", text)),
+        ];
 
-        match self.call_api(&prompt).await {
-            Ok(response) => {
-                let parsed = self.parse_json_response(&response)?;
-                Ok(parsed)
+        match self.complete_valid(messages, &task_output(), ParsedTask::from_reply).await {
+            Ok(parsed) => {
+                let mut task = parsed.into_task(projects);
+                merge_into(&mut task, tags);
+                if project_id.is_some() {
+                    task.project_id = project_id;
+                }
+                Ok(task)
             }
-            Err(_) => {
-                Ok(self.offline_parse(input))
+            Err(e) if self.offline_fallback => {
+                warn!("{} gave no usable task, parsing offline: {}", self.provider.name(), e);
+                Ok(self.offline_parse(input, projects))
             }
+            Err(e) => Err(e),
         }
     }
 
-    async fn call_api(&self, prompt: &str) -> Result<String, Box<dyn std::error::Error>> {
-        let request = GroqRequest {
-            model: "llama-3.3-70b-versatile".to_string(),
-            messages: vec![Message {
-                role: "user".to_string(),
-                content: prompt.to_string(),
-            }],
-            temperature: 0.7,
-            max_tokens: 500,
-        };
-
-        let response = self
-            .client
-            .post("https://api.groq.com/openai/v1/chat/completions")
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .header("Content-Type", "application/json")
-            .json(&request)
-            .send()
-            .await?;
-
-        let groq_response: GroqResponse = response.json().await?;
-        
-        if let Some(choice) = groq_response.choices.first() {
-            Ok(choice.message.content.clone())
-        } else {
-            Err("No response from AI".into())
+    // Ask for structured output and decode it with `decode`. An invalid
+    // reply is answered with the error so the model can correct itself.
+    async fn complete_valid<T>(
+        &self,
+        mut messages: Vec<ChatMessage>,
+        output: &JsonOutput,
+        decode: impl Fn(&str) -> Result<T>,
+    ) -> Result<T> {
+        let mut repairs = 0;
+        loop {
+            let reply = self.provider.complete_json(&messages, output).await?;
+            match decode(&reply) {
+                Ok(value) => return Ok(value),
+                Err(AppError::Serialization(problem)) if repairs < REPAIR_ATTEMPTS => {
+                    repairs += 1;
+                    messages.push(ChatMessage::assistant(reply));
+                    messages.push(ChatMessage::user(format!(
+                        "That reply was invalid: {}. Answer again with only the corrected JSON object.",
+                        problem
+                    )));
+                }
+                Err(e) => return Err(e),
+            }
         }
     }
 
-    fn parse_json_response(&self, response: &str) -> Result<Task, Box<dyn std::error::Error>> {
-        let cleaned = response
-            .trim()
-            .trim_start_matches("```json")
-            .trim_end_matches("```")
-            .trim();
-
-        let parsed: ParsedTask = serde_json::from_str(cleaned)?;
-        
-        let priority = match parsed.priority.as_deref() {
-            Some("high") => Priority::High,
-            Some("low") => Priority::Low,
-            _ => Priority::Medium,
-        };
-
-        let due_date = parsed.due_days.map(|days| Utc::now() + Duration::days(days));
+    // Rule-based parse for when the provider can't help; see offline.rs
+    fn offline_parse(&self, input: &str, projects: &[Project]) -> Task {
+        let (text, tags) = extract_tags(input);
+        let (text, project) = extract_project(&text);
+        let parsed = offline::parse(&text, Local::now().naive_local());
 
         let mut task = Task::new(parsed.title);
-        task.priority = priority;
-        task.due_date = due_date;
-
-        Ok(task)
-    }
-
-    fn offline_parse(&self, input: &str) -> Task {
-        let lower = input.to_lowercase();
-        
-        let priority = if lower.contains("urgent") || lower.contains("high") {
-            Priority::High
-        } else if lower.contains("low") {
-            Priority::Low
-        } else {
-            Priority::Medium
-        };
-
-        let due_date = if lower.contains("today") {
-            Some(Utc::now())
-        } else if lower.contains("tomorrow") {
-            Some(Utc::now() + Duration::days(1))
-        } else if lower.contains("week") {
-            Some(Utc::now() + Duration::days(7))
-        } else {
-            None
-        };
-
-        let title = input
-            .replace("urgent", "")
-            .replace("high", "")
-            .replace("low", "")
-            .replace("today", "")
-            .replace("tomorrow", "")
-            .trim()
-            .to_string();
-
-        let mut task = Task::new(title);
-        task.priority = priority;
-        task.due_date = due_date;
+        task.priority = parsed.priority.unwrap_or(Priority::Medium);
+        task.due_date = parsed.due.and_then(|due| dates::local_datetime(due.date(), due.time()));
+        task.recurrence = parsed.recurrence;
+        task.tags = tags;
+        merge_into(&mut task, parsed.tags);
+        task.project_id = project
+            .and_then(|name| resolve(projects, &name))
+            .map(|p| p.id.clone());
         task
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,732 @@
+use chrono::{Datelike, Duration, Months, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+use crate::core::dates::default_due_time;
+use crate::core::recurrence::{Recurrence, Unit};
+use crate::core::tags::normalize_tag;
+use crate::core::Priority;
+
+// What the rule-based parser found in a task description. Used when no AI
+// provider is configured or reachable, so it has to be predictable: every
+// phrase it understands is listed below, and words are only ever removed
+// from the title whole.
+//
+// Dates:    today, tonight, tomorrow, day after tomorrow, friday, this friday,
+//           next friday (the one in the following week), next week, next
+//           month, this weekend, in 3 days, in two weeks, in a month, 3d, 2w,
+//           2024-05-01, 1.5.2024, 5/1/2024, may 1st, 1 may 2025. Short
+//           weekday names and dates without a year need "on", "by" or "due"
+//           in front ("due fri", "on 5/1").
+// Times:    at 14:30, 14:30, 5pm, 5:30 pm, at 17, at noon, tomorrow morning,
+//           in 2 hours, in 30 min
+// Priority: !high, !medium, !low, !urgent, p1..p3 (as shown in the list),
+//           urgent, asap, high/medium/low priority
+// Repeats:  every day, every 3 days, every other week, every two months,
+//           every monday (also due on the next one); daily, weekly,
+//           fortnightly, monthly, yearly at the end or before another
+//           phrase ("review weekly", not "weekly review")
+// Tags:     #word
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parsed {
+    pub title: String,
+    pub priority: Option<Priority>,
+    // Local wall-clock time the task is due
+    pub due: Option<NaiveDateTime>,
+    pub tags: Vec<String>,
+    pub recurrence: Option<Recurrence>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Found {
+    Priority(Priority),
+    Tag(String),
+    Date(NaiveDate),
+    Time(NaiveTime),
+    DateTime(NaiveDateTime),
+    // With the first day it falls on, for "every friday"
+    Repeat(Recurrence, Option<NaiveDate>),
+}
+
+struct Token<'a> {
+    text: &'a str,
+    // Lowercased, without surrounding punctuation
+    word: String,
+}
+
+// Words that may introduce a date or time and are dropped along with it
+const CONNECTORS: [&str; 6] = ["on", "by", "due", "until", "before", "at"];
+
+pub fn parse(input: &str, now: NaiveDateTime) -> Parsed {
+    let tokens: Vec<Token> = input
+        .split_whitespace()
+        .map(|text| Token {
+            text,
+            word: text.trim_end_matches([',', '.', ';', ':', '?', '!', ')']).trim_start_matches('(').to_lowercase(),
+        })
+        .collect();
+    let parser = Parser { tokens: &tokens, now };
+
+    let mut found = Vec::new();
+    let mut kept: Vec<String> = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        match parser.match_at(i) {
+            Some((len, item)) => {
+                found.push(item);
+                // Keep a clause break like "#errands, remember ..." in the title
+                let last = tokens[i + len - 1].text;
+                if let (Some(mark @ (',' | ';')), Some(previous)) = (last.chars().last(), kept.last_mut()) {
+                    if !previous.ends_with([',', ';']) {
+                        previous.push(mark);
+                    }
+                }
+                i += len;
+            }
+            None => {
+                kept.push(tokens[i].text.to_string());
+                i += 1;
+            }
+        }
+    }
+
+    let title = kept.join(" ").trim_matches(|c: char| c == ',' || c == ';' || c.is_whitespace()).to_string();
+    Parsed {
+        // Nothing but a date or tag: keep the input rather than an empty title
+        title: if title.is_empty() { input.trim().to_string() } else { title },
+        priority: found.iter().find_map(|f| match f {
+            Found::Priority(p) => Some(*p),
+            _ => None,
+        }),
+        due: resolve_due(&found, now),
+        tags: found.iter().fold(Vec::new(), |mut tags, f| {
+            if let Found::Tag(tag) = f {
+                if !tags.contains(tag) {
+                    tags.push(tag.clone());
+                }
+            }
+            tags
+        }),
+        recurrence: found.iter().find_map(|f| match f {
+            Found::Repeat(r, _) => Some(*r),
+            _ => None,
+        }),
+    }
+}
+
+// Combine the first date and first time mentioned, falling back to the first
+// day of a repeat like "every friday". A time alone means the next time the
+// clock shows it; a date alone gets the default time of day.
+fn resolve_due(found: &[Found], now: NaiveDateTime) -> Option<NaiveDateTime> {
+    if let Some(datetime) = found.iter().find_map(|f| match f {
+        Found::DateTime(d) => Some(*d),
+        _ => None,
+    }) {
+        return Some(datetime);
+    }
+    let date = found
+        .iter()
+        .find_map(|f| match f {
+            Found::Date(d) => Some(*d),
+            _ => None,
+        })
+        .or_else(|| {
+            found.iter().find_map(|f| match f {
+                Found::Repeat(_, first) => *first,
+                _ => None,
+            })
+        });
+    let time = found.iter().find_map(|f| match f {
+        Found::Time(t) => Some(*t),
+        _ => None,
+    });
+    match (date, time) {
+        (Some(date), Some(time)) => Some(date.and_time(time)),
+        (Some(date), None) => Some(date.and_time(default_due_time())),
+        (None, Some(time)) if time > now.time() => Some(now.date().and_time(time)),
+        (None, Some(time)) => Some((now.date() + Duration::days(1)).and_time(time)),
+        (None, None) => None,
+    }
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token<'a>],
+    now: NaiveDateTime,
+}
+
+impl Parser<'_> {
+    fn word(&self, i: usize) -> Option<&str> {
+        self.tokens.get(i).map(|t| t.word.as_str())
+    }
+
+    fn today(&self) -> NaiveDate {
+        self.now.date()
+    }
+
+    // The phrase starting at token `i`: how many tokens it spans and what it means
+    fn match_at(&self, i: usize) -> Option<(usize, Found)> {
+        let word = self.word(i)?;
+        if CONNECTORS.contains(&word) {
+            // "on the 1st of june"
+            let skip = if self.word(i + 1) == Some("the") { 2 } else { 1 };
+            if let Some((len, found)) = self.match_value(i + skip, true) {
+                // "at" only introduces a time: "at home tomorrow" keeps "at home"
+                let fits = match found {
+                    Found::Time(_) | Found::DateTime(_) => true,
+                    Found::Date(_) => word != "at",
+                    _ => false,
+                };
+                if fits {
+                    return Some((len + skip, found));
+                }
+            }
+        }
+        self.match_value(i, false)
+    }
+
+    // `introduced` is set after a connector, which makes ambiguous forms like
+    // a bare hour or "5/1" safe to read as times and dates
+    fn match_value(&self, i: usize, introduced: bool) -> Option<(usize, Found)> {
+        self.priority(i)
+            .or_else(|| self.tag(i))
+            .or_else(|| self.repeat(i))
+            .or_else(|| self.relative(i, introduced))
+            .or_else(|| self.named_day(i, introduced))
+            .or_else(|| self.month_date(i))
+            .or_else(|| self.numeric_date(i, introduced))
+            .or_else(|| self.time(i, introduced))
+    }
+
+    fn priority(&self, i: usize) -> Option<(usize, Found)> {
+        let word = self.word(i)?;
+        let level = |name: &str| match name {
+            "high" | "urgent" => Some(Priority::High),
+            "medium" | "med" | "normal" => Some(Priority::Medium),
+            "low" => Some(Priority::Low),
+            _ => None,
+        };
+
+        if let Some(name) = word.strip_prefix('!') {
+            return level(name).map(|p| (1, Found::Priority(p)));
+        }
+        match word {
+            // Same numbering as the P1/P2/P3 labels in the task list
+            "p1" => return Some((1, Found::Priority(Priority::Low))),
+            "p2" => return Some((1, Found::Priority(Priority::Medium))),
+            "p3" => return Some((1, Found::Priority(Priority::High))),
+            "urgent" | "asap" => return Some((1, Found::Priority(Priority::High))),
+            _ => {}
+        }
+        // "high priority" / "priority high"
+        let next = self.word(i + 1)?;
+        match (level(word), level(next)) {
+            (Some(p), _) if next == "priority" || next == "prio" => Some((2, Found::Priority(p))),
+            (_, Some(p)) if word == "priority" || word == "prio" => Some((2, Found::Priority(p))),
+            _ => None,
+        }
+    }
+
+    fn tag(&self, i: usize) -> Option<(usize, Found)> {
+        let name = self.tokens.get(i)?.text.strip_prefix('#')?;
+        // "#123" is an issue number, not a tag
+        if !name.starts_with(char::is_alphabetic) {
+            return None;
+        }
+        normalize_tag(name.trim_end_matches([',', '.', ';', ':'])).map(|tag| (1, Found::Tag(tag)))
+    }
+
+    // "every 2 weeks", "every other day", "every friday"; a bare "weekly"
+    // only where it can't be describing the next word
+    fn repeat(&self, i: usize) -> Option<(usize, Found)> {
+        let word = self.word(i)?;
+        if word != "every" {
+            let standalone = i + 1 == self.tokens.len() || self.match_at(i + 1).is_some();
+            if !standalone || !matches!(word, "daily" | "weekly" | "fortnightly" | "monthly" | "yearly" | "annually") {
+                return None;
+            }
+            return Recurrence::parse(word).map(|r| (1, Found::Repeat(r, None)));
+        }
+
+        let next = self.word(i + 1)?;
+        if let Some(day) = weekday(next, true) {
+            let first = self.upcoming(day, true);
+            return Some((2, Found::Repeat(Recurrence::new(1, Unit::Week), Some(first))));
+        }
+        if let (Some(amount), Some(unit)) = (number(next), self.word(i + 2)) {
+            if let Some(r) = Recurrence::parse(&format!("{} {}", amount, unit)) {
+                return Some((3, Found::Repeat(r, None)));
+            }
+        }
+        if let Some(r) = self.word(i + 2).and_then(|unit| Recurrence::parse(&format!("every {} {}", next, unit))) {
+            return Some((3, Found::Repeat(r, None)));
+        }
+        Recurrence::parse(&format!("every {}", next)).map(|r| (2, Found::Repeat(r, None)))
+    }
+
+    // "in 3 days", "in two weeks", "in an hour", and compact "3d" / "2w"
+    fn relative(&self, i: usize, introduced: bool) -> Option<(usize, Found)> {
+        let word = self.word(i)?;
+        if word == "in" {
+            let amount = number(self.word(i + 1)?)?;
+            let unit = self.word(i + 2)?;
+            return self.offset(amount, unit).map(|found| (3, found));
+        }
+
+        let split = word.find(|c: char| !c.is_ascii_digit()).filter(|&at| at > 0)?;
+        let (amount, unit) = (word[..split].parse().ok()?, &word[split..]);
+        if !matches!(unit, "d" | "w") {
+            return None;
+        }
+        // "3d printer" is a thing, not a date: only read a compact offset at
+        // the end, before another recognised phrase or after "due"/"by"
+        let standalone = introduced || i + 1 == self.tokens.len() || self.match_at(i + 1).is_some();
+        if !standalone {
+            return None;
+        }
+        self.offset(amount, unit).map(|found| (1, found))
+    }
+
+    fn offset(&self, amount: u32, unit: &str) -> Option<Found> {
+        let now = self.now;
+        let today = self.today();
+        Some(match unit {
+            "min" | "mins" | "minute" | "minutes" => Found::DateTime(now + Duration::minutes(amount as i64)),
+            "h" | "hr" | "hrs" | "hour" | "hours" => Found::DateTime(now + Duration::hours(amount as i64)),
+            "d" | "day" | "days" => Found::Date(today + Duration::days(amount as i64)),
+            "w" | "week" | "weeks" => Found::Date(today + Duration::weeks(amount as i64)),
+            "month" | "months" => Found::Date(today.checked_add_months(Months::new(amount))?),
+            _ => return None,
+        })
+    }
+
+    // today, tomorrow, weekdays and weeks, optionally followed by a part of
+    // the day ("tomorrow evening")
+    fn named_day(&self, i: usize, introduced: bool) -> Option<(usize, Found)> {
+        let today = self.today();
+        let word = self.word(i)?;
+        let (len, date) = match word {
+            "today" => (1, today),
+            "tonight" => return Some((1, Found::DateTime(today.and_time(hour(20))))),
+            "tomorrow" | "tmrw" | "tmr" => (1, today + Duration::days(1)),
+            "day" if self.word(i + 1) == Some("after") && self.word(i + 2) == Some("tomorrow") => {
+                (3, today + Duration::days(2))
+            }
+            "this" | "next" => {
+                let next = self.word(i + 1)?;
+                let date = match (word, next) {
+                    ("this", "morning" | "afternoon" | "evening") => {
+                        return Some((2, Found::DateTime(today.and_time(day_part(next)?))));
+                    }
+                    ("this", "weekend") => self.upcoming(Weekday::Sat, true),
+                    ("next", "week") => self.week_after(Weekday::Mon),
+                    ("next", "weekend") => self.week_after(Weekday::Sat),
+                    ("next", "month") => {
+                        let first = today.with_day(1)?;
+                        first.checked_add_months(Months::new(1))?
+                    }
+                    ("this", _) => self.upcoming(weekday(next, true)?, true),
+                    (_, _) => self.week_after(weekday(next, true)?),
+                };
+                (2, date)
+            }
+            _ => (1, self.upcoming(weekday(word, introduced)?, false)),
+        };
+
+        match self.word(i + len).and_then(day_part) {
+            Some(time) => Some((len + 1, Found::DateTime(date.and_time(time)))),
+            None => Some((len, Found::Date(date))),
+        }
+    }
+
+    // The next `day` after today, or from today on with `inclusive`
+    fn upcoming(&self, day: Weekday, inclusive: bool) -> NaiveDate {
+        let today = self.today();
+        let ahead = (day.num_days_from_monday() as i64 - today.weekday().num_days_from_monday() as i64).rem_euclid(7);
+        let ahead = if ahead == 0 && !inclusive { 7 } else { ahead };
+        today + Duration::days(ahead)
+    }
+
+    // `day` in the week (starting Monday) after the current one
+    fn week_after(&self, day: Weekday) -> NaiveDate {
+        let today = self.today();
+        let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64) + Duration::weeks(1);
+        monday + Duration::days(day.num_days_from_monday() as i64)
+    }
+
+    // "may 1", "may 1st 2025", "1 may", "1st of may 2025"
+    fn month_date(&self, i: usize) -> Option<(usize, Found)> {
+        let (month, day, mut len) = match (month(self.word(i)?), self.word(i + 1)) {
+            (Some(month), Some(next)) => (month, ordinal(next)?, 2),
+            (None, _) => {
+                let day = ordinal(self.word(i)?)?;
+                let (skip, name) = match self.word(i + 1)? {
+                    "of" => (1, self.word(i + 2)?),
+                    name => (0, name),
+                };
+                (month(name)?, day, 2 + skip)
+            }
+            _ => return None,
+        };
+        let year = self.word(i + len).and_then(year);
+        if year.is_some() {
+            len += 1;
+        }
+        self.date(year, month, day).map(|date| (len, Found::Date(date)))
+    }
+
+    // 2024-05-01 always; 1.5.2024 and 5/1/2024 (month first) with a year, or
+    // without one after "on", "by" or "due"
+    fn numeric_date(&self, i: usize, introduced: bool) -> Option<(usize, Found)> {
+        let word = self.word(i)?;
+        if let Ok(date) = NaiveDate::parse_from_str(word, "%Y-%m-%d") {
+            return Some((1, Found::Date(date)));
+        }
+
+        let (separator, day_first) = if word.contains('.') { ('.', true) } else { ('/', false) };
+        let parts: Vec<&str> = word.trim_end_matches('.').split(separator).collect();
+        let (first, second, year) = match parts.as_slice() {
+            [a, b] if introduced => (a.parse().ok()?, b.parse().ok()?, None),
+            [a, b, y] => {
+                let y: i32 = y.parse().ok()?;
+                (a.parse().ok()?, b.parse().ok()?, Some(if y < 100 { 2000 + y } else { y }))
+            }
+            _ => return None,
+        };
+        let (month, day) = if day_first { (second, first) } else { (first, second) };
+        self.date(year, month, day).map(|date| (1, Found::Date(date)))
+    }
+
+    // A calendar date; without a year, the next time it comes round
+    fn date(&self, year: Option<i32>, month: u32, day: u32) -> Option<NaiveDate> {
+        match year {
+            Some(year) => NaiveDate::from_ymd_opt(year, month, day),
+            None => {
+                let this_year = NaiveDate::from_ymd_opt(self.today().year(), month, day)?;
+                if this_year >= self.today() {
+                    Some(this_year)
+                } else {
+                    NaiveDate::from_ymd_opt(self.today().year() + 1, month, day)
+                }
+            }
+        }
+    }
+
+    // 14:30, 5pm, 5:30pm, "5 pm"; a bare hour, noon and midnight after "at"
+    fn time(&self, i: usize, introduced: bool) -> Option<(usize, Found)> {
+        let word = self.word(i)?;
+        if let Some(time) = clock(word) {
+            return Some((1, Found::Time(time)));
+        }
+        if let Some(meridiem @ ("am" | "pm")) = self.word(i + 1) {
+            if let Some(time) = clock(&format!("{}{}", word, meridiem)) {
+                return Some((2, Found::Time(time)));
+            }
+        }
+        if !introduced {
+            return None;
+        }
+        match word {
+            "noon" | "midday" => Some((1, Found::Time(hour(12)))),
+            "midnight" => Some((1, Found::Time(NaiveTime::from_hms_opt(23, 59, 0)?))),
+            _ => {
+                let h: u32 = word.parse().ok()?;
+                (h < 24).then(|| (1, Found::Time(hour(h))))
+            }
+        }
+    }
+}
+
+fn hour(h: u32) -> NaiveTime {
+    NaiveTime::from_hms_opt(h, 0, 0).unwrap()
+}
+
+fn day_part(word: &str) -> Option<NaiveTime> {
+    match word {
+        "morning" => Some(hour(9)),
+        "afternoon" => Some(hour(15)),
+        "evening" => Some(hour(18)),
+        "night" => Some(hour(20)),
+        _ => None,
+    }
+}
+
+// "14:30", "5pm", "5:30pm", "12am"
+fn clock(word: &str) -> Option<NaiveTime> {
+    let (digits, offset) = if let Some(d) = word.strip_suffix("am") {
+        (d, Some(0))
+    } else if let Some(d) = word.strip_suffix("pm") {
+        (d, Some(12))
+    } else {
+        (word, None)
+    };
+
+    let (h, m) = match digits.split_once(':') {
+        Some((h, m)) if m.len() == 2 => (h.parse::<u32>().ok()?, m.parse::<u32>().ok()?),
+        Some(_) => return None,
+        // A bare number needs am/pm to be a time
+        None if offset.is_some() => (digits.parse::<u32>().ok()?, 0),
+        None => return None,
+    };
+    let h = match offset {
+        Some(offset) if (1..=12).contains(&h) => h % 12 + offset,
+        Some(_) => return None,
+        None => h,
+    };
+    NaiveTime::from_hms_opt(h, m, 0)
+}
+
+fn weekday(word: &str, allow_short: bool) -> Option<Weekday> {
+    let day = match word {
+        "monday" => Weekday::Mon,
+        "tuesday" => Weekday::Tue,
+        "wednesday" => Weekday::Wed,
+        "thursday" => Weekday::Thu,
+        "friday" => Weekday::Fri,
+        "saturday" => Weekday::Sat,
+        "sunday" => Weekday::Sun,
+        // Short names double as ordinary words ("sat", "wed")
+        _ if !allow_short => return None,
+        "mon" => Weekday::Mon,
+        "tue" | "tues" => Weekday::Tue,
+        "wed" => Weekday::Wed,
+        "thu" | "thur" | "thurs" => Weekday::Thu,
+        "fri" => Weekday::Fri,
+        "sat" => Weekday::Sat,
+        "sun" => Weekday::Sun,
+        _ => return None,
+    };
+    Some(day)
+}
+
+fn month(word: &str) -> Option<u32> {
+    const MONTHS: [&str; 12] = [
+        "january", "february", "march", "april", "may", "june",
+        "july", "august", "september", "october", "november", "december",
+    ];
+    // Full names and abbreviations of three letters or more ("sep", "sept")
+    MONTHS
+        .iter()
+        .position(|m| word.len() >= 3 && m.starts_with(word))
+        .map(|i| i as u32 + 1)
+}
+
+// "1", "1st", "22nd", "3rd", "4th"
+fn ordinal(word: &str) -> Option<u32> {
+    let digits = word.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let suffix = &word[digits.len()..];
+    if !matches!(suffix, "" | "st" | "nd" | "rd" | "th") {
+        return None;
+    }
+    digits.parse().ok().filter(|d| (1..=31).contains(d))
+}
+
+fn year(word: &str) -> Option<i32> {
+    if word.len() == 4 {
+        word.parse().ok()
+    } else {
+        None
+    }
+}
+
+// Digits or a small number word: "3", "a", "an", "two"
+fn number(word: &str) -> Option<u32> {
+    const WORDS: [&str; 12] = [
+        "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten", "eleven", "twelve",
+    ];
+    match word {
+        "a" | "an" => Some(1),
+        _ => word
+            .parse()
+            .ok()
+            .or_else(|| WORDS.iter().position(|w| *w == word).map(|i| i as u32 + 1)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Wednesday 2024-05-15, 10:00 local time
+    fn now() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 5, 15).unwrap().and_hms_opt(10, 0, 0).unwrap()
+    }
+
+    fn due(text: &str) -> Option<NaiveDateTime> {
+        Some(NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M").unwrap())
+    }
+
+    #[test]
+    fn dates() {
+        let cases = [
+            ("buy milk", "buy milk", None),
+            ("buy milk today", "buy milk", due("2024-05-15 09:00")),
+            ("call the bank tomorrow", "call the bank", due("2024-05-16 09:00")),
+            ("pay rent tmrw", "pay rent", due("2024-05-16 09:00")),
+            ("dentist day after tomorrow", "dentist", due("2024-05-17 09:00")),
+            ("submit report friday", "submit report", due("2024-05-17 09:00")),
+            // A bare weekday is never today
+            ("submit report on wednesday", "submit report", due("2024-05-22 09:00")),
+            ("standup this wednesday", "standup", due("2024-05-15 09:00")),
+            ("review next friday", "review", due("2024-05-24 09:00")),
+            ("report due fri", "report", due("2024-05-17 09:00")),
+            ("plan sprint next week", "plan sprint", due("2024-05-20 09:00")),
+            ("file taxes next month", "file taxes", due("2024-06-01 09:00")),
+            ("clean garage this weekend", "clean garage", due("2024-05-18 09:00")),
+            ("renew passport in 3 days", "renew passport", due("2024-05-18 09:00")),
+            ("renew passport in two weeks", "renew passport", due("2024-05-29 09:00")),
+            ("renew passport in a month", "renew passport", due("2024-06-15 09:00")),
+            ("water plants 2w", "water plants", due("2024-05-29 09:00")),
+            ("launch 2024-06-01", "launch", due("2024-06-01 09:00")),
+            ("launch on 1.6.2024", "launch", due("2024-06-01 09:00")),
+            ("launch 6/1/2024", "launch", due("2024-06-01 09:00")),
+            ("launch on 6/1", "launch", due("2024-06-01 09:00")),
+            ("pay invoice by 5/31", "pay invoice", due("2024-05-31 09:00")),
+            ("party may 20th", "party", due("2024-05-20 09:00")),
+            ("party on the 1st of june", "party", due("2024-06-01 09:00")),
+            ("conference 3 march 2025", "conference", due("2025-03-03 09:00")),
+            ("conference Sept 9, 2024", "conference", due("2024-09-09 09:00")),
+            // Already past this year, so next year's
+            ("anniversary may 1", "anniversary", due("2025-05-01 09:00")),
+            ("tomorrow", "tomorrow", due("2024-05-16 09:00")),
+        ];
+        for (input, title, expected) in cases {
+            let parsed = parse(input, now());
+            assert_eq!(parsed.title, title, "title of {:?}", input);
+            assert_eq!(parsed.due, expected, "due date of {:?}", input);
+        }
+    }
+
+    #[test]
+    fn times() {
+        let cases = [
+            ("meeting at 14:30", "meeting", due("2024-05-15 14:30")),
+            // Already past today, so tomorrow's
+            ("meeting at 9:30", "meeting", due("2024-05-16 09:30")),
+            ("meeting tomorrow at 5pm", "meeting", due("2024-05-16 17:00")),
+            ("meeting 5 pm friday", "meeting", due("2024-05-17 17:00")),
+            ("meeting friday 5:30pm", "meeting", due("2024-05-17 17:30")),
+            ("call at 17", "call", due("2024-05-15 17:00")),
+            ("lunch at noon", "lunch", due("2024-05-15 12:00")),
+            ("backup at 12am", "backup", due("2024-05-16 00:00")),
+            ("call mom tomorrow evening", "call mom", due("2024-05-16 18:00")),
+            ("movie tonight", "movie", due("2024-05-15 20:00")),
+            ("check the oven in 30 min", "check the oven", due("2024-05-15 10:30")),
+            ("deploy in 2 hours", "deploy", due("2024-05-15 12:00")),
+            ("nap this afternoon", "nap", due("2024-05-15 15:00")),
+        ];
+        for (input, title, expected) in cases {
+            let parsed = parse(input, now());
+            assert_eq!(parsed.title, title, "title of {:?}", input);
+            assert_eq!(parsed.due, expected, "due date of {:?}", input);
+        }
+    }
+
+    #[test]
+    fn priorities() {
+        let cases = [
+            ("fix login !high", "fix login", Some(Priority::High)),
+            ("fix login !LOW", "fix login", Some(Priority::Low)),
+            ("fix login !urgent", "fix login", Some(Priority::High)),
+            ("fix login p3", "fix login", Some(Priority::High)),
+            ("fix login p1", "fix login", Some(Priority::Low)),
+            ("fix login P2", "fix login", Some(Priority::Medium)),
+            ("urgent call the bank", "call the bank", Some(Priority::High)),
+            ("email the boss asap!", "email the boss", Some(Priority::High)),
+            ("write docs low priority", "write docs", Some(Priority::Low)),
+            ("priority high: fix prod", "fix prod", Some(Priority::High)),
+            ("buy milk", "buy milk", None),
+        ];
+        for (input, title, expected) in cases {
+            let parsed = parse(input, now());
+            assert_eq!(parsed.title, title, "title of {:?}", input);
+            assert_eq!(parsed.priority, expected, "priority of {:?}", input);
+        }
+    }
+
+    #[test]
+    fn tags() {
+        let cases: [(&str, &str, &[&str]); 5] = [
+            ("buy milk #errands", "buy milk", &["errands"]),
+            ("plan trip #Travel #family #travel", "plan trip", &["travel", "family"]),
+            ("follow up 3d #sales", "follow up", &["sales"]),
+            // Issue numbers are not tags
+            ("fix issue #123", "fix issue #123", &[]),
+            ("#", "#", &[]),
+        ];
+        for (input, title, expected) in cases {
+            let parsed = parse(input, now());
+            assert_eq!(parsed.title, title, "title of {:?}", input);
+            assert_eq!(parsed.tags, expected, "tags of {:?}", input);
+        }
+    }
+
+    #[test]
+    fn repeats() {
+        let every = |n, unit| Some(Recurrence::new(n, unit));
+        let cases = [
+            ("stretch every day", "stretch", every(1, Unit::Day), None),
+            ("water plants every 3 days", "water plants", every(3, Unit::Day), None),
+            ("mow the lawn every other week", "mow the lawn", every(2, Unit::Week), None),
+            ("check smoke alarms every two months", "check smoke alarms", every(2, Unit::Month), None),
+            ("team sync every monday", "team sync", every(1, Unit::Week), due("2024-05-20 09:00")),
+            ("team sync every mon at 11", "team sync", every(1, Unit::Week), due("2024-05-20 11:00")),
+            // Today is a Wednesday
+            ("retro every wednesday at 4pm", "retro", every(1, Unit::Week), due("2024-05-15 16:00")),
+            ("pay rent monthly", "pay rent", every(1, Unit::Month), None),
+            ("review budget weekly #finance", "review budget", every(1, Unit::Week), None),
+            ("call grandma fortnightly", "call grandma", every(2, Unit::Week), None),
+            ("renew domain yearly on 2024-09-01", "renew domain", every(1, Unit::Year), due("2024-09-01 09:00")),
+            ("standup every day at 9:30", "standup", every(1, Unit::Day), due("2024-05-16 09:30")),
+        ];
+        for (input, title, recurrence, expected) in cases {
+            let parsed = parse(input, now());
+            assert_eq!(parsed.title, title, "title of {:?}", input);
+            assert_eq!(parsed.recurrence, recurrence, "recurrence of {:?}", input);
+            assert_eq!(parsed.due, expected, "due date of {:?}", input);
+        }
+    }
+
+    #[test]
+    fn words_that_only_look_like_dates_stay_in_the_title() {
+        let unchanged = [
+            "follow up with the low-key client",
+            "highlight the draft",
+            "fix 3d printer",
+            "mix 1/2 cup sugar",
+            "I may call bob",
+            "sat down with the team",
+            "leave the keys at home",
+            "meet at the cafe",
+            "next steps for onboarding",
+            "todayish plans",
+            "read chapter 5",
+            "march in the parade",
+            // A connector with nothing after it
+            "decide what to work on",
+            "get this done by",
+            "ship it before",
+            "meet at",
+            "pay what is due",
+            "work until",
+            "focus on the",
+            // Describing the next word rather than how often
+            "weekly review of the roadmap",
+            "write the monthly report",
+            "every time it rains",
+            "check every",
+        ];
+        for input in unchanged {
+            let parsed = parse(input, now());
+            assert_eq!(parsed.title, input);
+            assert_eq!(parsed.due, None, "due date of {:?}", input);
+            assert_eq!(parsed.priority, None, "priority of {:?}", input);
+            assert_eq!(parsed.recurrence, None, "recurrence of {:?}", input);
+        }
+    }
+
+    #[test]
+    fn everything_together() {
+        let parsed = parse("buy milk at 5pm tomorrow #errands, remember lactose-free !low", now());
+        assert_eq!(parsed.title, "buy milk, remember lactose-free");
+        assert_eq!(parsed.due, due("2024-05-16 17:00"));
+        assert_eq!(parsed.tags, ["errands"]);
+        assert_eq!(parsed.priority, Some(Priority::Low));
+    }
+}
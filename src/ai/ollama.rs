@@ -0,0 +1,192 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use uuid::Uuid;
+use crate::config::NetworkSettings;
+use crate::core::error::{AppError, Result};
+use crate::net::HttpClient;
+use super::json::JsonOutput;
+use super::openai::function_tool;
+use super::provider::{ChatMessage, ChatRole, LlmProvider, ModelOptions, ToolCall};
+
+// A local Ollama server's native chat API. llama.cpp's server speaks the
+// OpenAI format instead and is reached through OpenAiCompatible.
+pub struct Ollama {
+    http: HttpClient,
+    options: ModelOptions,
+}
+
+#[derive(Serialize)]
+struct ChatRequest<'a> {
+    model: &'a str,
+    messages: Vec<Value>,
+    stream: bool,
+    options: Options,
+    // A JSON schema the reply is constrained to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<&'a Value>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<Value>,
+}
+
+#[derive(Serialize)]
+struct Options {
+    temperature: f32,
+    num_predict: u32,
+}
+
+#[derive(Deserialize)]
+struct ChatResponse {
+    message: ReplyMessage,
+}
+
+#[derive(Deserialize)]
+struct ReplyMessage {
+    #[serde(default)]
+    content: String,
+    #[serde(default)]
+    tool_calls: Vec<WireToolCall>,
+}
+
+#[derive(Deserialize)]
+struct WireToolCall {
+    function: WireFunction,
+}
+
+#[derive(Deserialize)]
+struct WireFunction {
+    name: String,
+    arguments: Value,
+}
+
+#[derive(Serialize)]
+struct EmbedRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+#[derive(Deserialize)]
+struct EmbedResponse {
+    embeddings: Vec<Vec<f32>>,
+}
+
+// Messages in Ollama's format. Tool calls have no ids there; results are
+// matched to calls by tool name instead.
+fn wire_messages(messages: &[ChatMessage]) -> Vec<Value> {
+    let tool_name = |id: Option<&String>| {
+        messages
+            .iter()
+            .flat_map(|m| &m.tool_calls)
+            .find(|call| Some(&call.id) == id)
+            .map(|call| call.name.clone())
+    };
+    messages
+        .iter()
+        .map(|message| match message.role {
+            ChatRole::Tool => json!({
+                "role": "tool",
+                "content": message.content,
+                "tool_name": tool_name(message.tool_call_id.as_ref()),
+            }),
+            _ if !message.tool_calls.is_empty() => {
+                let calls: Vec<Value> = message
+                    .tool_calls
+                    .iter()
+                    .map(|call| json!({ "function": { "name": call.name, "arguments": call.arguments } }))
+                    .collect();
+                json!({ "role": "assistant", "content": message.content, "tool_calls": calls })
+            }
+            _ => json!({ "role": message.role, "content": message.content }),
+        })
+        .collect()
+}
+
+impl Ollama {
+    pub fn new(options: ModelOptions, network: &NetworkSettings) -> Self {
+        Self {
+            http: HttpClient::new(network, network.ai_request_timeout_secs),
+            options,
+        }
+    }
+
+    async fn chat(&self, messages: &[ChatMessage], format: Option<&Value>, tools: &[JsonOutput]) -> Result<ReplyMessage> {
+        let body = ChatRequest {
+            model: &self.options.model,
+            messages: wire_messages(messages),
+            stream: false,
+            options: Options {
+                temperature: self.options.temperature,
+                num_predict: self.options.max_tokens,
+            },
+            format,
+            tools: tools.iter().map(function_tool).collect(),
+        };
+
+        let request = self.http.post(format!("{}/api/chat", self.options.base_url)).json(&body);
+        let response = self.http.send_idempotent(request).await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(AppError::from_response("AI request failed", status.as_u16(), body));
+        }
+
+        let reply: ChatResponse = response.json().await?;
+        Ok(reply.message)
+    }
+}
+
+#[async_trait]
+impl LlmProvider for Ollama {
+    fn name(&self) -> &str {
+        &self.options.model
+    }
+
+    async fn complete(&self, messages: &[ChatMessage]) -> Result<String> {
+        Ok(self.chat(messages, None, &[]).await?.content)
+    }
+
+    async fn complete_json(&self, messages: &[ChatMessage], output: &JsonOutput) -> Result<String> {
+        let format = self.options.json_mode.then_some(&output.schema);
+        Ok(self.chat(messages, format, &[]).await?.content)
+    }
+
+    async fn complete_tools(&self, messages: &[ChatMessage], tools: &[JsonOutput]) -> Result<ChatMessage> {
+        let reply = self.chat(messages, None, tools).await?;
+        let calls = reply
+            .tool_calls
+            .into_iter()
+            .map(|call| ToolCall {
+                id: Uuid::new_v4().to_string(),
+                name: call.function.name,
+                arguments: call.function.arguments,
+            })
+            .collect();
+        Ok(ChatMessage::tool_calls(reply.content, calls))
+    }
+
+    fn can_embed(&self) -> bool {
+        self.options.embedding_model.is_some()
+    }
+
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let Some(model) = &self.options.embedding_model else {
+            return Err(AppError::Validation("No embedding_model is configured".to_string()));
+        };
+        let request = self
+            .http
+            .post(format!("{}/api/embed", self.options.base_url))
+            .json(&EmbedRequest { model, input: texts });
+        let response = self.http.send_idempotent(request).await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(AppError::from_response("Embedding request failed", status.as_u16(), body));
+        }
+
+        let reply: EmbedResponse = response.json().await?;
+        Ok(reply.embeddings)
+    }
+}
+
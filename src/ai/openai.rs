@@ -0,0 +1,228 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use crate::config::NetworkSettings;
+use crate::core::error::{AppError, Result};
+use crate::net::HttpClient;
+use super::json::JsonOutput;
+use super::provider::{ChatMessage, ChatRole, LlmProvider, ModelOptions, ToolCall};
+
+// Chat completions API as served by OpenAI, Groq, llama.cpp's server and
+// most hosted open-model endpoints
+pub struct OpenAiCompatible {
+    http: HttpClient,
+    options: ModelOptions,
+}
+
+#[derive(Serialize)]
+struct ChatRequest<'a> {
+    model: &'a str,
+    messages: Vec<Value>,
+    temperature: f32,
+    max_tokens: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<Value>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<Value>,
+}
+
+#[derive(Deserialize)]
+struct ChatResponse {
+    choices: Vec<Choice>,
+}
+
+#[derive(Deserialize)]
+struct Choice {
+    message: ReplyMessage,
+}
+
+#[derive(Deserialize)]
+struct ReplyMessage {
+    #[serde(default)]
+    content: Option<String>,
+    #[serde(default)]
+    tool_calls: Vec<WireToolCall>,
+}
+
+#[derive(Deserialize)]
+struct WireToolCall {
+    id: String,
+    function: WireFunction,
+}
+
+#[derive(Deserialize)]
+struct WireFunction {
+    name: String,
+    // JSON encoded as a string
+    arguments: String,
+}
+
+#[derive(Serialize)]
+struct EmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+#[derive(Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<Embedding>,
+}
+
+#[derive(Deserialize)]
+struct Embedding {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+// A tool in the function-calling format, which Ollama accepts as well
+pub fn function_tool(tool: &JsonOutput) -> Value {
+    json!({
+        "type": "function",
+        "function": { "name": tool.name, "description": tool.description, "parameters": tool.schema }
+    })
+}
+
+// A message in the chat completions format, where tool calls carry their
+// arguments as a JSON string
+fn wire_message(message: &ChatMessage) -> Value {
+    match message.role {
+        ChatRole::Tool => json!({
+            "role": "tool",
+            "tool_call_id": message.tool_call_id,
+            "content": message.content,
+        }),
+        _ if !message.tool_calls.is_empty() => {
+            let calls: Vec<Value> = message
+                .tool_calls
+                .iter()
+                .map(|call| {
+                    json!({
+                        "id": call.id,
+                        "type": "function",
+                        "function": { "name": call.name, "arguments": call.arguments.to_string() },
+                    })
+                })
+                .collect();
+            json!({
+                "role": "assistant",
+                "content": (!message.content.is_empty()).then_some(&message.content),
+                "tool_calls": calls,
+            })
+        }
+        _ => json!({ "role": message.role, "content": message.content }),
+    }
+}
+
+impl OpenAiCompatible {
+    pub fn new(options: ModelOptions, network: &NetworkSettings) -> Self {
+        Self {
+            http: HttpClient::new(network, network.ai_request_timeout_secs),
+            options,
+        }
+    }
+
+    async fn chat(&self, messages: &[ChatMessage], response_format: Option<Value>, tools: &[JsonOutput]) -> Result<ReplyMessage> {
+        let body = ChatRequest {
+            model: &self.options.model,
+            messages: messages.iter().map(wire_message).collect(),
+            temperature: self.options.temperature,
+            max_tokens: self.options.max_tokens,
+            response_format,
+            tools: tools.iter().map(function_tool).collect(),
+        };
+
+        let mut request = self
+            .http
+            .post(format!("{}/chat/completions", self.options.base_url))
+            .json(&body);
+        if let Some(key) = &self.options.api_key {
+            request = request.bearer_auth(key);
+        }
+        // A completion has no side effects, so failures can be retried
+        let response = self.http.send_idempotent(request).await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(AppError::from_response("AI request failed", status.as_u16(), body));
+        }
+
+        let reply: ChatResponse = response.json().await?;
+        reply
+            .choices
+            .into_iter()
+            .next()
+            .map(|c| c.message)
+            .ok_or_else(|| AppError::Serialization("No response from AI".to_string()))
+    }
+
+    async fn text(&self, messages: &[ChatMessage], response_format: Option<Value>) -> Result<String> {
+        self.chat(messages, response_format, &[])
+            .await?
+            .content
+            .ok_or_else(|| AppError::Serialization("No response from AI".to_string()))
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OpenAiCompatible {
+    fn name(&self) -> &str {
+        &self.options.model
+    }
+
+    async fn complete(&self, messages: &[ChatMessage]) -> Result<String> {
+        self.text(messages, None).await
+    }
+
+    // JSON mode guarantees a syntactically valid object; the schema itself
+    // is only in the prompt, since few compatible servers accept json_schema
+    async fn complete_json(&self, messages: &[ChatMessage], _output: &JsonOutput) -> Result<String> {
+        let format = self.options.json_mode.then(|| json!({ "type": "json_object" }));
+        self.text(messages, format).await
+    }
+
+    async fn complete_tools(&self, messages: &[ChatMessage], tools: &[JsonOutput]) -> Result<ChatMessage> {
+        let reply = self.chat(messages, None, tools).await?;
+        let calls = reply
+            .tool_calls
+            .into_iter()
+            .map(|call| ToolCall {
+                id: call.id,
+                name: call.function.name,
+                arguments: serde_json::from_str(&call.function.arguments)
+                    .unwrap_or(Value::String(call.function.arguments)),
+            })
+            .collect();
+        Ok(ChatMessage::tool_calls(reply.content.unwrap_or_default(), calls))
+    }
+
+    fn can_embed(&self) -> bool {
+        self.options.embedding_model.is_some()
+    }
+
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let Some(model) = &self.options.embedding_model else {
+            return Err(AppError::Validation("No embedding_model is configured".to_string()));
+        };
+        let mut request = self
+            .http
+            .post(format!("{}/embeddings", self.options.base_url))
+            .json(&EmbeddingRequest { model, input: texts });
+        if let Some(key) = &self.options.api_key {
+            request = request.bearer_auth(key);
+        }
+        let response = self.http.send_idempotent(request).await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(AppError::from_response("Embedding request failed", status.as_u16(), body));
+        }
+
+        // Entries carry their input's index and needn't come back in order
+        let mut reply: EmbeddingResponse = response.json().await?;
+        reply.data.sort_by_key(|e| e.index);
+        Ok(reply.data.into_iter().map(|e| e.embedding).collect())
+    }
+}
+
@@ -0,0 +1,286 @@
+use chrono::{DateTime, Local, Utc};
+use log::warn;
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashSet;
+use crate::core::error::{AppError, Result};
+use crate::core::sort::urgency;
+use crate::core::{dates, Priority, Status, Task};
+use super::json::{extract_json, JsonOutput};
+use super::provider::ChatMessage;
+use super::{parse_priority, priority_name, AIAssistant};
+
+// Most tasks sent for planning; the most urgent ones are picked first
+const MAX_PLAN_TASKS: usize = 60;
+// Estimated work that fits into one day, in minutes
+const DAY_MINUTES: u32 = 8 * 60;
+// Assumed effort of a task without an estimate when planning offline
+const UNESTIMATED_MINUTES: u32 = 30;
+
+// One entry of a suggested plan for the day, in the order to work on them
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanStep {
+    pub task_id: String,
+    pub reason: String,
+    // A suggested new priority, when the model thinks it should change
+    pub priority: Option<Priority>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ParsedStep {
+    // 1-based number of the task in the prompt
+    task: usize,
+    reason: String,
+    priority: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ParsedPlan {
+    plan: Vec<ParsedStep>,
+}
+
+impl ParsedPlan {
+    // `count` is how many tasks were listed in the prompt
+    fn from_reply(reply: &str, count: usize) -> Result<Self> {
+        let value = extract_json(reply)
+            .ok_or_else(|| AppError::Serialization("the reply contains no JSON object".to_string()))?;
+        let parsed: ParsedPlan = serde_json::from_value(value)
+            .map_err(|e| AppError::Serialization(format!("the JSON doesn't match the expected fields: {}", e)))?;
+        parsed.validate(count)?;
+        Ok(parsed)
+    }
+
+    fn validate(&self, count: usize) -> Result<()> {
+        let invalid = |msg: String| Err(AppError::Serialization(msg));
+
+        if self.plan.is_empty() {
+            return invalid("\"plan\" must not be empty".to_string());
+        }
+        let mut seen = HashSet::new();
+        for step in &self.plan {
+            if !(1..=count).contains(&step.task) {
+                return invalid(format!("\"task\" must be a number from 1 to {}, not {}", count, step.task));
+            }
+            if !seen.insert(step.task) {
+                return invalid(format!("task {} is planned more than once", step.task));
+            }
+            if step.reason.trim().is_empty() {
+                return invalid(format!("task {} has an empty \"reason\"", step.task));
+            }
+            if let Some(priority) = &step.priority {
+                if parse_priority(priority).is_none() {
+                    return invalid(format!("\"priority\" must be \"low\", \"medium\" or \"high\", not {:?}", priority));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn into_steps(self, tasks: &[&Task]) -> Vec<PlanStep> {
+        self.plan
+            .into_iter()
+            .map(|step| PlanStep {
+                task_id: tasks[step.task - 1].id.clone(),
+                reason: step.reason.trim().to_string(),
+                priority: step.priority.as_deref().and_then(parse_priority),
+            })
+            .collect()
+    }
+}
+
+const PLAN_INSTRUCTIONS: &str = r#"You plan the user's working day. Given their open tasks, pick what they should work on today and in which order, and return ONLY a JSON object with one field:
+- plan: the chosen tasks in the order to do them (array), each with
+  - task: the task's number from the list (integer)
+  - reason: why it is scheduled there, in at most 12 words (string)
+  - priority: "low", "medium" or "high", only if the task's priority should change (string, optional)
+
+Consider due dates, priorities and status; skip blocked and waiting tasks unless they are overdue.
+Keep the total estimated effort within about 8 hours. Each task may appear only once.
+Return only valid JSON, no explanation."#;
+
+fn plan_output() -> JsonOutput {
+    JsonOutput {
+        name: "plan_day",
+        description: "Suggest an ordered plan for today",
+        schema: json!({
+            "type": "object",
+            "properties": {
+                "plan": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "task": { "type": "integer", "minimum": 1, "description": "The task's number from the list" },
+                            "reason": { "type": "string", "description": "Why it is scheduled there" },
+                            "priority": { "type": "string", "enum": ["low", "medium", "high"] }
+                        },
+                        "required": ["task", "reason"]
+                    }
+                }
+            },
+            "required": ["plan"]
+        }),
+    }
+}
+
+// The numbered task list sent to the model, with the current time so it
+// can tell what is overdue
+fn plan_prompt(tasks: &[&Task], now: DateTime<Local>) -> String {
+    let mut prompt = format!("Current local time: {}\n\nOpen tasks:", now.format("%A %Y-%m-%d %H:%M"));
+    for (i, task) in tasks.iter().enumerate() {
+        prompt.push_str(&format!("\n{}. {} | priority: {}", i + 1, task.title, priority_name(task.priority)));
+        if task.status != Status::Todo {
+            prompt.push_str(&format!(" | status: {}", task.status.label().to_lowercase()));
+        }
+        if let Some(due) = task.due_date {
+            prompt.push_str(&format!(" | due: {}", due.with_timezone(&Local).format("%a %Y-%m-%d %H:%M")));
+        }
+        if let Some(minutes) = task.estimate_minutes {
+            prompt.push_str(&format!(" | estimate: {}", dates::format_duration(minutes)));
+        }
+    }
+    prompt
+}
+
+impl AIAssistant {
+    // An ordered plan for today drawn from `tasks`, which should be the
+    // user's open tasks
+    pub async fn plan_day(&self, tasks: &[Task]) -> Result<Vec<PlanStep>> {
+        let now = Utc::now();
+        let mut candidates: Vec<&Task> = tasks.iter().filter(|t| !t.status.is_closed()).collect();
+        if candidates.is_empty() {
+            return Err(AppError::Validation("No open tasks to plan".to_string()));
+        }
+        candidates.sort_by(|a, b| urgency(b, now).total_cmp(&urgency(a, now)));
+        candidates.truncate(MAX_PLAN_TASKS);
+
+        let messages = vec![
+            ChatMessage::system(PLAN_INSTRUCTIONS),
+            ChatMessage::user(plan_prompt(&candidates, Local::now())),
+        ];
+        let count = candidates.len();
+        match self.complete_valid(messages, &plan_output(), |reply| ParsedPlan::from_reply(reply, count)).await {
+            Ok(plan) => Ok(plan.into_steps(&candidates)),
+            Err(e) if self.offline_fallback => {
+                warn!("{} gave no usable plan, planning offline: {}", self.provider.name(), e);
+                Ok(offline_plan(&candidates, now))
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+// Most urgent first until the day is full, skipping blocked and waiting
+// tasks. `tasks` must already be sorted by urgency.
+fn offline_plan(tasks: &[&Task], now: DateTime<Utc>) -> Vec<PlanStep> {
+    let today = now.with_timezone(&Local).date_naive();
+    let mut planned = 0;
+    let mut steps = Vec::new();
+    for task in tasks {
+        if matches!(task.status, Status::Blocked | Status::Waiting) {
+            continue;
+        }
+        let minutes = task.estimate_minutes.unwrap_or(UNESTIMATED_MINUTES);
+        if !steps.is_empty() && planned + minutes > DAY_MINUTES {
+            break;
+        }
+        planned += minutes;
+
+        let due = task.due_date.map(|d| d.with_timezone(&Local).date_naive());
+        let reason = match due {
+            Some(date) if date < today => "Overdue",
+            Some(date) if date == today => "Due today",
+            _ if task.status == Status::InProgress => "Already in progress",
+            _ if task.priority == Priority::High => "High priority",
+            Some(date) if (date - today).num_days() <= 3 => "Due soon",
+            _ => "Next most urgent",
+        };
+        steps.push(PlanStep { task_id: task.id.clone(), reason: reason.to_string(), priority: None });
+    }
+    steps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::mock::MockProvider;
+    use chrono::Duration;
+
+    fn task(title: &str, priority: Priority) -> Task {
+        let mut task = Task::new(title.to_string());
+        task.priority = priority;
+        task
+    }
+
+    #[test]
+    fn replies_are_validated() {
+        let cases = [
+            (r#"{"plan": [{"task": 2, "reason": "Due today"}, {"task": 1, "reason": "Quick win", "priority": "high"}]}"#, None),
+            (r#"{"plan": []}"#, Some("must not be empty")),
+            (r#"{"plan": [{"task": 4, "reason": "Due today"}]}"#, Some("from 1 to 3, not 4")),
+            (r#"{"plan": [{"task": 0, "reason": "Due today"}]}"#, Some("from 1 to 3, not 0")),
+            (r#"{"plan": [{"task": 1, "reason": "a"}, {"task": 1, "reason": "b"}]}"#, Some("more than once")),
+            (r#"{"plan": [{"task": 1, "reason": ""}]}"#, Some("empty \"reason\"")),
+            (r#"{"plan": [{"task": 1, "reason": "a", "priority": "p1"}]}"#, Some("\"priority\" must be")),
+            (r#"{"tasks": [1, 2]}"#, Some("expected fields")),
+        ];
+        for (reply, problem) in cases {
+            match (ParsedPlan::from_reply(reply, 3), problem) {
+                (Ok(_), None) => {}
+                (Err(AppError::Serialization(message)), Some(problem)) => {
+                    assert!(message.contains(problem), "{:?}: {}", reply, message)
+                }
+                (result, _) => panic!("{:?} gave {:?}", reply, result),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn plan_refers_to_tasks_by_number() {
+        let mut report = task("Write report", Priority::Medium);
+        report.estimate_minutes = Some(90);
+        let mut done = task("Old chore", Priority::High);
+        done.status = Status::Done;
+        let tasks = vec![report, task("Call the bank", Priority::High), done];
+
+        // The most urgent open task is listed first
+        let mock = MockProvider::new()
+            .reply(r#"{"plan": [{"task": 2, "reason": "Takes focus", "priority": "high"}, {"task": 1, "reason": "Before noon"}]}"#);
+        let steps = AIAssistant::new(Box::new(mock.clone())).plan_day(&tasks).await.unwrap();
+        assert_eq!(
+            steps,
+            [
+                PlanStep { task_id: tasks[0].id.clone(), reason: "Takes focus".to_string(), priority: Some(Priority::High) },
+                PlanStep { task_id: tasks[1].id.clone(), reason: "Before noon".to_string(), priority: None },
+            ]
+        );
+
+        let prompt = &mock.received()[0][1].content;
+        assert!(prompt.contains("1. Call the bank | priority: high"));
+        assert!(prompt.contains("2. Write report | priority: medium | estimate: 1h30m"));
+        assert!(!prompt.contains("Old chore"));
+    }
+
+    #[tokio::test]
+    async fn without_a_provider_the_most_urgent_tasks_fill_the_day() {
+        let now = Utc::now();
+        let mut overdue = task("Renew passport", Priority::Low);
+        overdue.due_date = Some(now - Duration::days(2));
+        let mut blocked = task("Deploy", Priority::High);
+        blocked.status = Status::Blocked;
+        let mut long = task("Rewrite parser", Priority::Medium);
+        long.estimate_minutes = Some(8 * 60);
+        let tasks = vec![long, blocked, task("Fix login", Priority::High), overdue];
+
+        let steps = AIAssistant::new(Box::new(MockProvider::new())).plan_day(&tasks).await.unwrap();
+        let planned: Vec<(&str, &str)> = steps
+            .iter()
+            .map(|s| {
+                let task = tasks.iter().find(|t| t.id == s.task_id).unwrap();
+                (task.title.as_str(), s.reason.as_str())
+            })
+            .collect();
+        // The 8 hour rewrite no longer fits after the first two
+        assert_eq!(planned, [("Renew passport", "Overdue"), ("Fix login", "High priority")]);
+    }
+}
@@ -0,0 +1,140 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::config::{AISettings, NetworkSettings, ProviderKind};
+use crate::core::error::{AppError, Result};
+use super::anthropic::Anthropic;
+use super::json::JsonOutput;
+use super::ollama::Ollama;
+use super::openai::OpenAiCompatible;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChatRole {
+    System,
+    User,
+    Assistant,
+    // The result of a tool call, sent back to the model
+    Tool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: ChatRole,
+    pub content: String,
+    // Tools an assistant message asks to have called
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
+    // On a Tool message, the id of the call it answers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+}
+
+// A function call requested by the model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolCall {
+    pub id: String,
+    pub name: String,
+    // Usually an object; whatever the model sent when it isn't valid JSON
+    pub arguments: Value,
+}
+
+impl ChatMessage {
+    fn new(role: ChatRole, content: String) -> Self {
+        Self { role, content, tool_calls: Vec::new(), tool_call_id: None }
+    }
+
+    pub fn system(content: impl Into<String>) -> Self {
+        Self::new(ChatRole::System, content.into())
+    }
+
+    pub fn user(content: impl Into<String>) -> Self {
+        Self::new(ChatRole::User, content.into())
+    }
+
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Self::new(ChatRole::Assistant, content.into())
+    }
+
+    pub fn tool_calls(content: impl Into<String>, calls: Vec<ToolCall>) -> Self {
+        Self { tool_calls: calls, ..Self::assistant(content) }
+    }
+
+    pub fn tool_result(call: &ToolCall, content: impl Into<String>) -> Self {
+        Self { tool_call_id: Some(call.id.clone()), ..Self::new(ChatRole::Tool, content.into()) }
+    }
+}
+
+// A chat model the assistant can send prompts to. Implementations translate
+// the conversation into their API's wire format and return the reply text.
+#[async_trait]
+pub trait LlmProvider: Send + Sync {
+    // Shown in logs and the status bar
+    fn name(&self) -> &str;
+
+    async fn complete(&self, messages: &[ChatMessage]) -> Result<String>;
+
+    // A reply that should be a JSON object shaped like `output`. Providers
+    // with a JSON mode or tool calling override this to constrain the
+    // model; the reply is validated by the caller either way.
+    async fn complete_json(&self, messages: &[ChatMessage], output: &JsonOutput) -> Result<String> {
+        let _ = output;
+        self.complete(messages).await
+    }
+
+    // The model's next turn when it may call `tools`, each described like a
+    // structured output. The reply either answers the user or carries
+    // tool_calls, whose results go back as ChatMessage::tool_result.
+    async fn complete_tools(&self, messages: &[ChatMessage], tools: &[JsonOutput]) -> Result<ChatMessage> {
+        let _ = (messages, tools);
+        Err(AppError::Validation(format!("{} doesn't support tool calling", self.name())))
+    }
+
+    // Whether `embed` is available, i.e. an embedding model is configured
+    fn can_embed(&self) -> bool {
+        false
+    }
+
+    // One embedding vector per text, in order
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let _ = texts;
+        Err(AppError::Validation(format!("{} doesn't support embeddings", self.name())))
+    }
+}
+
+// Model parameters shared by every provider
+#[derive(Debug, Clone)]
+pub struct ModelOptions {
+    pub base_url: String,
+    pub model: String,
+    pub api_key: Option<String>,
+    pub temperature: f32,
+    pub max_tokens: u32,
+    // Ask for structured output when the API supports it
+    pub json_mode: bool,
+    pub embedding_model: Option<String>,
+}
+
+impl ModelOptions {
+    pub fn from_settings(settings: &AISettings) -> Self {
+        Self {
+            base_url: settings.base_url().trim_end_matches('/').to_string(),
+            model: settings.model(),
+            api_key: settings.api_key(),
+            temperature: settings.temperature,
+            max_tokens: settings.max_tokens,
+            json_mode: settings.json_mode,
+            embedding_model: settings.embedding_model.clone(),
+        }
+    }
+}
+
+// The provider selected in the config
+pub fn from_settings(settings: &AISettings, network: &NetworkSettings) -> Box<dyn LlmProvider> {
+    let options = ModelOptions::from_settings(settings);
+    match settings.provider {
+        ProviderKind::OpenAi => Box::new(OpenAiCompatible::new(options, network)),
+        ProviderKind::Anthropic => Box::new(Anthropic::new(options, network)),
+        ProviderKind::Ollama => Box::new(Ollama::new(options, network)),
+    }
+}
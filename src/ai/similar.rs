@@ -0,0 +1,303 @@
+use log::warn;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use crate::core::error::{AppError, Result};
+use crate::core::Task;
+use super::AIAssistant;
+
+// Titles at least this alike as text are taken for the same task. Word
+// overlap catches reordered or padded titles, letter pairs catch small
+// spelling differences ("electric" / "electricity").
+const TEXT_THRESHOLD: f32 = 0.8;
+// Cosine similarity of embeddings above which two titles mean the same
+const EMBEDDING_THRESHOLD: f32 = 0.85;
+// Titles sent per embedding request, and at most in total; longer lists are
+// compared as text only
+const EMBED_BATCH: usize = 100;
+const MAX_EMBEDDED: usize = 1000;
+
+// Words that don't tell tasks apart
+const FILLER: [&str; 14] = ["a", "an", "the", "to", "for", "of", "and", "my", "our", "on", "in", "at", "with", "some"];
+
+// An open task that looks like the same task as another one
+#[derive(Debug, Clone, PartialEq)]
+pub struct Similar {
+    pub task_id: String,
+    // How alike the two are, from 0 to 1
+    pub score: f32,
+}
+
+// A title reduced to what matters for comparing it: lowercase words
+// without punctuation, filler or plural "s", and the letter pairs of those
+// words
+struct Fingerprint {
+    words: HashSet<String>,
+    pairs: HashSet<(char, char)>,
+}
+
+impl Fingerprint {
+    fn new(title: &str) -> Self {
+        let words: Vec<String> = title
+            .split_whitespace()
+            .map(|w| w.chars().filter(|c| c.is_alphanumeric()).collect::<String>().to_lowercase())
+            .filter(|w| !w.is_empty() && !FILLER.contains(&w.as_str()))
+            .map(|w| match w.strip_suffix('s') {
+                Some(stem) if stem.chars().count() >= 3 && !stem.ends_with('s') => stem.to_string(),
+                _ => w,
+            })
+            .collect();
+        let joined: Vec<char> = words.join(" ").chars().collect();
+        let pairs = joined.windows(2).map(|w| (w[0], w[1])).collect();
+        Self { words: words.into_iter().collect(), pairs }
+    }
+
+    fn similarity(&self, other: &Fingerprint) -> f32 {
+        dice(&self.words, &other.words).max(dice(&self.pairs, &other.pairs))
+    }
+}
+
+// Sørensen–Dice coefficient of two sets
+fn dice<T: Eq + Hash>(a: &HashSet<T>, b: &HashSet<T>) -> f32 {
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    2.0 * a.intersection(b).count() as f32 / (a.len() + b.len()) as f32
+}
+
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norms = norm(a) * norm(b);
+    if norms == 0.0 {
+        0.0
+    } else {
+        dot / norms
+    }
+}
+
+// Pairwise comparison of a list of titles
+struct Comparison {
+    prints: Vec<Fingerprint>,
+    // One per title, when the provider could embed them
+    vectors: Option<Vec<Vec<f32>>>,
+}
+
+impl Comparison {
+    // How alike titles `i` and `j` are, if enough to count as duplicates
+    fn score(&self, i: usize, j: usize) -> Option<f32> {
+        let text = self.prints[i].similarity(&self.prints[j]);
+        let meaning = self.vectors.as_ref().map_or(0.0, |v| cosine(&v[i], &v[j]));
+        (text >= TEXT_THRESHOLD || meaning >= EMBEDDING_THRESHOLD).then_some(text.max(meaning))
+    }
+}
+
+impl AIAssistant {
+    async fn compare(&self, titles: Vec<String>) -> Comparison {
+        let prints = titles.iter().map(|t| Fingerprint::new(t)).collect();
+        let vectors = if self.provider.can_embed() && titles.len() <= MAX_EMBEDDED {
+            match self.embed_titles(&titles).await {
+                Ok(vectors) => Some(vectors),
+                Err(e) => {
+                    warn!("{} gave no embeddings, comparing titles as text: {}", self.provider.name(), e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        Comparison { prints, vectors }
+    }
+
+    // Embeddings of `titles`, asking the provider only for ones it hasn't
+    // embedded before
+    async fn embed_titles(&self, titles: &[String]) -> Result<Vec<Vec<f32>>> {
+        let missing: Vec<String> = {
+            let cache = self.embeddings.lock().unwrap();
+            let unique: HashSet<&String> = titles.iter().filter(|t| !cache.contains_key(*t)).collect();
+            unique.into_iter().cloned().collect()
+        };
+        for batch in missing.chunks(EMBED_BATCH) {
+            let vectors = self.provider.embed(batch).await?;
+            if vectors.len() != batch.len() {
+                return Err(AppError::Serialization(format!(
+                    "{} embeddings for {} titles",
+                    vectors.len(),
+                    batch.len()
+                )));
+            }
+            let mut cache = self.embeddings.lock().unwrap();
+            cache.extend(batch.iter().cloned().zip(vectors));
+        }
+        let cache = self.embeddings.lock().unwrap();
+        Ok(titles.iter().map(|t| cache.get(t).cloned().unwrap_or_default()).collect())
+    }
+
+    // Open tasks among `tasks` that look like the same task as `task`,
+    // most alike first
+    pub async fn find_similar(&self, task: &Task, tasks: &[Task]) -> Vec<Similar> {
+        let candidates: Vec<&Task> = tasks.iter().filter(|t| !t.status.is_closed() && t.id != task.id).collect();
+        if candidates.is_empty() {
+            return Vec::new();
+        }
+        let mut titles = vec![task.title.clone()];
+        titles.extend(candidates.iter().map(|t| t.title.clone()));
+        let comparison = self.compare(titles).await;
+
+        let mut similar: Vec<Similar> = candidates
+            .iter()
+            .enumerate()
+            .filter_map(|(i, t)| comparison.score(0, i + 1).map(|score| Similar { task_id: t.id.clone(), score }))
+            .collect();
+        similar.sort_by(|a, b| b.score.total_cmp(&a.score));
+        similar
+    }
+
+    // Groups of open tasks that look like duplicates of each other, largest
+    // first. A task alike to any task of a group joins it; each group keeps
+    // the order of `tasks`.
+    pub async fn duplicate_groups(&self, tasks: &[Task]) -> Vec<Vec<String>> {
+        let open: Vec<&Task> = tasks.iter().filter(|t| !t.status.is_closed()).collect();
+        let comparison = self.compare(open.iter().map(|t| t.title.clone()).collect()).await;
+
+        // Union-find over the alike pairs
+        let mut parent: Vec<usize> = (0..open.len()).collect();
+        fn root(parent: &mut [usize], mut i: usize) -> usize {
+            while parent[i] != i {
+                parent[i] = parent[parent[i]];
+                i = parent[i];
+            }
+            i
+        }
+        for i in 0..open.len() {
+            for j in i + 1..open.len() {
+                if comparison.score(i, j).is_some() {
+                    let (a, b) = (root(&mut parent, i), root(&mut parent, j));
+                    parent[b] = a;
+                }
+            }
+        }
+
+        let mut index: HashMap<usize, usize> = HashMap::new();
+        let mut groups: Vec<Vec<String>> = Vec::new();
+        for (i, task) in open.iter().enumerate() {
+            let group = *index.entry(root(&mut parent, i)).or_insert_with(|| {
+                groups.push(Vec::new());
+                groups.len() - 1
+            });
+            groups[group].push(task.id.clone());
+        }
+        groups.retain(|g| g.len() > 1);
+        groups.sort_by_key(|g| std::cmp::Reverse(g.len()));
+        groups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::mock::MockProvider;
+    use crate::core::Status;
+
+    fn similarity(a: &str, b: &str) -> f32 {
+        Fingerprint::new(a).similarity(&Fingerprint::new(b))
+    }
+
+    fn titles(tasks: &[Task], ids: &[String]) -> Vec<String> {
+        ids.iter().map(|id| tasks.iter().find(|t| &t.id == id).unwrap().title.clone()).collect()
+    }
+
+    #[test]
+    fn titles_are_compared_without_filler_case_or_punctuation() {
+        let alike = [
+            ("Fix login bug", "fix the login bug"),
+            ("Renew passport", "Renew my passport!"),
+            ("Buy milk", "buy oat milk"),
+            ("Pay electricity bill", "Pay the electric bill"),
+            ("Update dependencies", "update dependency"),
+            ("Call the bank", "Bank: call"),
+        ];
+        for (a, b) in alike {
+            assert!(similarity(a, b) >= TEXT_THRESHOLD, "{:?} / {:?}: {}", a, b, similarity(a, b));
+        }
+        let different = [
+            ("Call mom", "Call mum"),
+            ("Buy milk", "Buy bread"),
+            ("Write report", "Review report draft"),
+            ("Deploy to staging", "Deploy to production"),
+            ("!!!", "???"),
+        ];
+        for (a, b) in different {
+            assert!(similarity(a, b) < TEXT_THRESHOLD, "{:?} / {:?}: {}", a, b, similarity(a, b));
+        }
+    }
+
+    #[tokio::test]
+    async fn similar_open_tasks_are_found_most_alike_first() {
+        let mut closed = Task::new("Fix the login bug".to_string());
+        closed.status = Status::Done;
+        let tasks = vec![
+            Task::new("Fix login bugs on mobile".to_string()),
+            Task::new("Fix login bug".to_string()),
+            Task::new("Water the plants".to_string()),
+            closed,
+        ];
+        let ai = AIAssistant::new(Box::new(MockProvider::new()));
+
+        let new = Task::new("fix the login bug".to_string());
+        let similar = ai.find_similar(&new, &tasks).await;
+        let ids: Vec<String> = similar.iter().map(|s| s.task_id.clone()).collect();
+        assert_eq!(titles(&tasks, &ids), ["Fix login bug", "Fix login bugs on mobile"]);
+        assert_eq!(similar[0].score, 1.0);
+    }
+
+    #[tokio::test]
+    async fn embeddings_catch_the_same_task_in_other_words() {
+        let tasks = vec![Task::new("Schedule a visit to the dentist".to_string()), Task::new("Buy stamps".to_string())];
+        let mock = MockProvider::new()
+            .embedding("Book dentist appointment", vec![1.0, 0.1, 0.0])
+            .embedding("Schedule a visit to the dentist", vec![0.9, 0.2, 0.0])
+            .embedding("Buy stamps", vec![0.0, 0.2, 1.0]);
+        let ai = AIAssistant::new(Box::new(mock.clone()));
+
+        let new = Task::new("Book dentist appointment".to_string());
+        let similar = ai.find_similar(&new, &tasks).await;
+        assert_eq!(similar.len(), 1);
+        assert_eq!(similar[0].task_id, tasks[0].id);
+
+        // Titles embedded once aren't sent again
+        ai.find_similar(&new, &tasks).await;
+        assert_eq!(mock.embedded(), 3);
+    }
+
+    #[tokio::test]
+    async fn failing_embeddings_fall_back_to_comparing_text() {
+        let tasks = vec![Task::new("Renew passport".to_string())];
+        // The mock fails for titles it has no embedding for
+        let mock = MockProvider::new().embedding("unused", vec![1.0]);
+        let ai = AIAssistant::new(Box::new(mock));
+
+        let similar = ai.find_similar(&Task::new("Renew my passport".to_string()), &tasks).await;
+        assert_eq!(similar.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn duplicates_are_grouped_across_the_list() {
+        let tasks: Vec<Task> = [
+            "Buy milk",
+            "Write report",
+            "buy oat milk",
+            "Water the plants",
+            "Buy milk!",
+            "Write the report",
+            "Call the bank",
+        ]
+        .iter()
+        .map(|t| Task::new(t.to_string()))
+        .collect();
+        let ai = AIAssistant::new(Box::new(MockProvider::new()));
+
+        let groups: Vec<Vec<String>> =
+            ai.duplicate_groups(&tasks).await.iter().map(|g| titles(&tasks, g)).collect();
+        assert_eq!(groups, [vec!["Buy milk", "buy oat milk", "Buy milk!"], vec!["Write report", "Write the report"]]);
+    }
+}
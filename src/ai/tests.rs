@@ -0,0 +1,406 @@
+use super::mock::MockProvider;
+use super::provider::{self, ChatRole, ToolCall};
+use super::*;
+use chrono::Local;
+use crate::config::ProviderKind;
+use axum::http::HeaderMap;
+use axum::{Json, Router};
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+
+fn assistant(mock: &MockProvider) -> AIAssistant {
+    AIAssistant::new(Box::new(mock.clone()))
+}
+
+fn project(name: &str) -> Project {
+    Project::new(name.to_string(), None, "u1".to_string())
+}
+
+// What a provider sent: path, headers and JSON body
+type Received = Arc<Mutex<Vec<(String, HeaderMap, Value)>>>;
+
+// Answer every POST with `reply`, recording the requests
+async fn serve(reply: Value) -> (String, Received) {
+    let received: Received = Arc::default();
+    let log = received.clone();
+    let app = Router::new().fallback(move |uri: axum::http::Uri, headers: HeaderMap, Json(body): Json<Value>| {
+        let reply = reply.clone();
+        log.lock().unwrap().push((uri.path().to_string(), headers, body));
+        async move { Json(reply) }
+    });
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    (base_url, received)
+}
+
+fn settings(kind: ProviderKind, base_url: &str) -> AISettings {
+    AISettings {
+        provider: kind,
+        base_url: Some(base_url.to_string()),
+        model: Some("test-model".to_string()),
+        api_key_env: Some("TODO_TUI_TEST_AI_KEY".to_string()),
+        temperature: 0.2,
+        max_tokens: 64,
+        ..AISettings::default()
+    }
+}
+
+fn conversation() -> Vec<ChatMessage> {
+    vec![ChatMessage::system("Be brief"), ChatMessage::user("Hello")]
+}
+
+#[tokio::test]
+async fn parse_task_uses_the_provider_reply() {
+    let mock = MockProvider::new().reply(r#"{"title": "Fix login bug", "priority": "high", "tags": ["Auth"]}"#);
+    let projects = vec![project("Website")];
+
+    let task = assistant(&mock).parse_task("fix the login bug asap +bugs @website", &projects).await.unwrap();
+    assert_eq!(task.title, "Fix login bug");
+    assert_eq!(task.priority, Priority::High);
+    assert_eq!(task.tags, ["auth", "bugs"]);
+    assert_eq!(task.project_id, Some(projects[0].id.clone()));
+
+    // Explicit +tags and @project are stripped before prompting
+    let conversation = &mock.received()[0];
+    assert_eq!(conversation[0].role, ChatRole::System);
+    let prompt = &conversation[1];
+    assert_eq!(prompt.role, ChatRole::User);
+    assert!(prompt.content.contains("fix the login bug asap"));
+    assert!(!prompt.content.contains("@website"));
+}
+
+#[tokio::test]
+async fn every_field_is_taken_from_the_reply() {
+    let mock = MockProvider::new().reply(
+        r#"{"title": "Buy milk", "description": "remember lactose-free", "due": "2030-06-14T17:00",
+            "estimate_minutes": 15, "repeat": "every 2 weeks", "tags": ["errands"], "project": "home"}"#,
+    );
+    let projects = vec![project("Website"), project("Home")];
+
+    let task = assistant(&mock)
+        .parse_task("buy milk at 5pm tomorrow #errands, remember lactose-free", &projects)
+        .await
+        .unwrap();
+    assert_eq!(task.title, "Buy milk");
+    assert_eq!(task.description, "remember lactose-free");
+    assert_eq!(task.tags, ["errands"]);
+    assert_eq!(task.estimate_minutes, Some(15));
+    assert_eq!(task.recurrence.map(|r| r.to_string()).as_deref(), Some("every 2 weeks"));
+    assert_eq!(task.project_id, Some(projects[1].id.clone()));
+    // The due time is the user's local wall-clock time
+    let due = task.due_date.unwrap().with_timezone(&Local);
+    assert_eq!(due.format("%Y-%m-%d %H:%M").to_string(), "2030-06-14 17:00");
+
+    // The prompt gives the model today's date and the project names
+    let system = &mock.received()[0][0].content;
+    assert!(system.contains(&Local::now().format("%Y-%m-%d").to_string()));
+    assert!(system.contains("Projects: Website, Home"));
+}
+
+#[tokio::test]
+async fn unknown_projects_from_the_model_are_ignored() {
+    let mock = MockProvider::new().reply(r#"{"title": "Call mum", "project": "Family"}"#);
+    let task = assistant(&mock).parse_task("call mum", &[project("Work")]).await.unwrap();
+    assert_eq!(task.project_id, None);
+}
+
+#[tokio::test]
+async fn provider_failure_falls_back_to_offline_parsing() {
+    let mock = MockProvider::new().fail(AppError::Network("connection refused".to_string()));
+    let task = assistant(&mock).parse_task("urgent call the bank every friday", &[]).await.unwrap();
+    assert_eq!(task.priority, Priority::High);
+    assert!(task.due_date.is_some());
+    assert_eq!(task.recurrence.map(|r| r.to_string()).as_deref(), Some("weekly"));
+    assert_eq!(mock.received().len(), 1);
+}
+
+#[tokio::test]
+async fn openai_compatible_request_and_reply() {
+    let (base_url, received) = serve(json!({ "choices": [{ "message": { "role": "assistant", "content": "Hi" } }] })).await;
+    std::env::set_var("TODO_TUI_TEST_AI_KEY", "secret");
+    let llm = provider::from_settings(&settings(ProviderKind::OpenAi, &base_url), &NetworkSettings::default());
+
+    assert_eq!(llm.complete(&conversation()).await.unwrap(), "Hi");
+    let (path, headers, body) = received.lock().unwrap()[0].clone();
+    assert_eq!(path, "/chat/completions");
+    assert_eq!(headers["authorization"], "Bearer secret");
+    assert_eq!(body["model"], "test-model");
+    assert_eq!(body["max_tokens"], 64);
+    assert_eq!(body["messages"][0], json!({ "role": "system", "content": "Be brief" }));
+}
+
+#[tokio::test]
+async fn anthropic_request_and_reply() {
+    let (base_url, received) = serve(json!({ "content": [{ "type": "text", "text": "Hi" }] })).await;
+    std::env::set_var("TODO_TUI_TEST_AI_KEY", "secret");
+    let llm = provider::from_settings(&settings(ProviderKind::Anthropic, &base_url), &NetworkSettings::default());
+
+    assert_eq!(llm.complete(&conversation()).await.unwrap(), "Hi");
+    let (path, headers, body) = received.lock().unwrap()[0].clone();
+    assert_eq!(path, "/v1/messages");
+    assert_eq!(headers["x-api-key"], "secret");
+    assert!(headers.contains_key("anthropic-version"));
+    // The system prompt moves out of the message list
+    assert_eq!(body["system"], "Be brief");
+    assert_eq!(body["messages"], json!([{ "role": "user", "content": "Hello" }]));
+}
+
+#[tokio::test]
+async fn ollama_request_and_reply() {
+    let (base_url, received) = serve(json!({ "message": { "role": "assistant", "content": "Hi" }, "done": true })).await;
+    let llm = provider::from_settings(&settings(ProviderKind::Ollama, &base_url), &NetworkSettings::default());
+
+    assert_eq!(llm.complete(&conversation()).await.unwrap(), "Hi");
+    let (path, _, body) = received.lock().unwrap()[0].clone();
+    assert_eq!(path, "/api/chat");
+    assert_eq!(body["stream"], false);
+    assert_eq!(body["options"]["num_predict"], 64);
+}
+
+#[test]
+fn older_configs_get_provider_defaults() {
+    let settings: AISettings = serde_json::from_str(r#"{ "auto_parse": true, "offline_fallback": true }"#).unwrap();
+    assert_eq!(settings.provider, ProviderKind::OpenAi);
+    assert_eq!(settings.base_url(), "https://api.groq.com/openai/v1");
+    assert_eq!(settings.model(), "llama-3.3-70b-versatile");
+
+    let settings: AISettings = serde_json::from_str(r#"{ "provider": "ollama", "model": "qwen2.5" }"#).unwrap();
+    assert_eq!(settings.base_url(), "http://localhost:11434");
+    assert_eq!(settings.model(), "qwen2.5");
+    assert_eq!(settings.api_key(), None);
+}
+
+#[test]
+fn replies_are_validated_field_by_field() {
+    let cases = [
+        (r#"{"title": "Pay rent", "priority": "HIGH", "due": "2030-03-01T17:30", "estimate_minutes": 20}"#, None),
+        (r#"Sure! {"title": "Pay rent"} Anything else?"#, None),
+        (r#"{"title": "  "}"#, Some("\"title\" must not be empty")),
+        (r#"{"title": "Pay rent", "priority": "asap"}"#, Some("\"priority\" must be")),
+        (r#"{"title": "Pay rent", "due": "2020-01-01"}"#, Some("\"due\" is in the past")),
+        (r#"{"title": "Pay rent", "due": "2999-01-01"}"#, Some("more than 3650 days away")),
+        (r#"{"title": "Pay rent", "due": "next friday"}"#, Some("\"due\" must be a date")),
+        (r#"{"title": "Pay rent", "estimate_minutes": 0}"#, Some("\"estimate_minutes\" must be between 1")),
+        (r#"{"title": "Pay rent", "estimate_minutes": "an hour"}"#, Some("doesn't match the expected fields")),
+        (r#"{"title": "Pay rent", "repeat": "monthly"}"#, None),
+        (r#"{"title": "Pay rent", "repeat": "now and then"}"#, Some("\"repeat\" must be like")),
+        (r#"{"title": "Pay rent", "tags": ["   "]}"#, Some("not a usable tag")),
+        (r#"{"priority": "low"}"#, Some("missing field `title`")),
+        ("I can't help with that", Some("no JSON object")),
+    ];
+    for (reply, expected) in cases {
+        match (ParsedTask::from_reply(reply), expected) {
+            (Ok(_), None) => {}
+            (Err(AppError::Serialization(msg)), Some(expected)) => {
+                assert!(msg.contains(expected), "{:?}: got {:?}", reply, msg)
+            }
+            (result, _) => panic!("{:?}: unexpected {:?}", reply, result),
+        }
+    }
+}
+
+#[tokio::test]
+async fn invalid_reply_is_sent_back_for_correction() {
+    let mock = MockProvider::new()
+        .reply(r#"{"title": "Water plants", "priority": "whenever"}"#)
+        .reply(r#"{"title": "Water plants", "priority": "low"}"#);
+
+    let task = assistant(&mock).parse_task("water the plants sometime", &[]).await.unwrap();
+    assert_eq!(task.title, "Water plants");
+    assert_eq!(task.priority, Priority::Low);
+
+    // The retry carries the bad reply and what was wrong with it
+    let received = mock.received();
+    assert_eq!(received.len(), 2);
+    let retry = &received[1];
+    assert_eq!(retry.len(), 4);
+    assert_eq!(retry[2], ChatMessage::assistant(r#"{"title": "Water plants", "priority": "whenever"}"#));
+    assert_eq!(retry[3].role, ChatRole::User);
+    assert!(retry[3].content.contains("\"priority\" must be"));
+}
+
+#[tokio::test]
+async fn repeated_invalid_replies_fall_back_to_offline_parsing() {
+    let mock = MockProvider::new().reply("not json").reply(r#"{"title": ""}"#);
+    let task = assistant(&mock).parse_task("urgent renew passport", &[]).await.unwrap();
+    assert_eq!(task.title, "renew passport");
+    assert_eq!(task.priority, Priority::High);
+    assert_eq!(mock.received().len(), 2);
+}
+
+#[tokio::test]
+async fn without_offline_fallback_errors_are_reported() {
+    let mock = MockProvider::new().reply("not json").reply("still not json");
+    let ai = AIAssistant { offline_fallback: false, ..assistant(&mock) };
+    let err = ai.parse_task("renew passport", &[]).await.unwrap_err();
+    assert!(matches!(err, AppError::Serialization(_)));
+
+    let mock = MockProvider::new().fail(AppError::Network("connection refused".to_string()));
+    let ai = AIAssistant { offline_fallback: false, ..assistant(&mock) };
+    assert!(matches!(ai.parse_task("renew passport", &[]).await, Err(AppError::Network(_))));
+}
+
+fn output() -> JsonOutput {
+    JsonOutput {
+        name: "create_task",
+        description: "Create a task",
+        schema: json!({ "type": "object", "properties": { "title": { "type": "string" } } }),
+    }
+}
+
+#[tokio::test]
+async fn openai_compatible_json_mode() {
+    let (base_url, received) = serve(json!({ "choices": [{ "message": { "content": "{\"title\": \"a\"}" } }] })).await;
+    let llm = provider::from_settings(&settings(ProviderKind::OpenAi, &base_url), &NetworkSettings::default());
+    assert_eq!(llm.complete_json(&conversation(), &output()).await.unwrap(), r#"{"title": "a"}"#);
+
+    let plain = AISettings { json_mode: false, ..settings(ProviderKind::OpenAi, &base_url) };
+    let llm = provider::from_settings(&plain, &NetworkSettings::default());
+    llm.complete_json(&conversation(), &output()).await.unwrap();
+
+    let received = received.lock().unwrap();
+    assert_eq!(received[0].2["response_format"], json!({ "type": "json_object" }));
+    assert!(received[1].2.get("response_format").is_none());
+}
+
+#[tokio::test]
+async fn anthropic_structured_output_uses_a_forced_tool() {
+    let (base_url, received) = serve(json!({
+        "content": [{ "type": "tool_use", "id": "t1", "name": "create_task", "input": { "title": "a" } }]
+    }))
+    .await;
+    let llm = provider::from_settings(&settings(ProviderKind::Anthropic, &base_url), &NetworkSettings::default());
+
+    let reply = llm.complete_json(&conversation(), &output()).await.unwrap();
+    assert_eq!(serde_json::from_str::<Value>(&reply).unwrap(), json!({ "title": "a" }));
+    let (_, _, body) = received.lock().unwrap()[0].clone();
+    assert_eq!(body["tools"][0]["name"], "create_task");
+    assert_eq!(body["tools"][0]["input_schema"], output().schema);
+    assert_eq!(body["tool_choice"], json!({ "type": "tool", "name": "create_task" }));
+}
+
+#[tokio::test]
+async fn ollama_structured_output_sends_the_schema() {
+    let (base_url, received) = serve(json!({ "message": { "content": "{\"title\": \"a\"}" }, "done": true })).await;
+    let llm = provider::from_settings(&settings(ProviderKind::Ollama, &base_url), &NetworkSettings::default());
+
+    llm.complete_json(&conversation(), &output()).await.unwrap();
+    let (_, _, body) = received.lock().unwrap()[0].clone();
+    assert_eq!(body["format"], output().schema);
+}
+
+// A conversation in the middle of a tool round: one call and its result
+fn tool_round() -> Vec<ChatMessage> {
+    let call = ToolCall { id: "c1".to_string(), name: "list_tasks".to_string(), arguments: json!({ "tag": "infra" }) };
+    let mut messages = conversation();
+    messages.push(ChatMessage::tool_calls("", vec![call.clone()]));
+    messages.push(ChatMessage::tool_result(&call, "[]"));
+    messages
+}
+
+#[tokio::test]
+async fn openai_compatible_tool_calls() {
+    let (base_url, received) = serve(json!({ "choices": [{ "message": { "content": null, "tool_calls": [
+        { "id": "c2", "type": "function", "function": { "name": "update_tasks", "arguments": "{\"updates\": []}" } }
+    ] } }] }))
+    .await;
+    let llm = provider::from_settings(&settings(ProviderKind::OpenAi, &base_url), &NetworkSettings::default());
+
+    let reply = llm.complete_tools(&tool_round(), &[output()]).await.unwrap();
+    assert_eq!(
+        reply.tool_calls,
+        [ToolCall { id: "c2".to_string(), name: "update_tasks".to_string(), arguments: json!({ "updates": [] }) }]
+    );
+    let (_, _, body) = received.lock().unwrap()[0].clone();
+    assert_eq!(body["tools"][0]["function"]["name"], "create_task");
+    assert_eq!(body["messages"][2]["tool_calls"][0]["function"]["arguments"], r#"{"tag":"infra"}"#);
+    assert_eq!(body["messages"][3], json!({ "role": "tool", "tool_call_id": "c1", "content": "[]" }));
+}
+
+#[tokio::test]
+async fn anthropic_tool_calls() {
+    let (base_url, received) = serve(json!({ "content": [
+        { "type": "text", "text": "Looking" },
+        { "type": "tool_use", "id": "c2", "name": "update_tasks", "input": { "updates": [] } }
+    ] }))
+    .await;
+    let llm = provider::from_settings(&settings(ProviderKind::Anthropic, &base_url), &NetworkSettings::default());
+
+    let reply = llm.complete_tools(&tool_round(), &[output()]).await.unwrap();
+    assert_eq!(reply.content, "Looking");
+    assert_eq!(reply.tool_calls[0].arguments, json!({ "updates": [] }));
+    let (_, _, body) = received.lock().unwrap()[0].clone();
+    assert_eq!(body["tools"][0]["name"], "create_task");
+    assert!(body.get("tool_choice").is_none());
+    assert_eq!(
+        body["messages"][1]["content"],
+        json!([{ "type": "tool_use", "id": "c1", "name": "list_tasks", "input": { "tag": "infra" } }])
+    );
+    // Tool results go back as a user turn
+    assert_eq!(
+        body["messages"][2],
+        json!({ "role": "user", "content": [{ "type": "tool_result", "tool_use_id": "c1", "content": "[]" }] })
+    );
+}
+
+#[tokio::test]
+async fn ollama_tool_calls() {
+    let (base_url, received) = serve(json!({ "message": { "content": "", "tool_calls": [
+        { "function": { "name": "update_tasks", "arguments": { "updates": [] } } }
+    ] }, "done": true }))
+    .await;
+    let llm = provider::from_settings(&settings(ProviderKind::Ollama, &base_url), &NetworkSettings::default());
+
+    let reply = llm.complete_tools(&tool_round(), &[output()]).await.unwrap();
+    assert_eq!(reply.tool_calls[0].name, "update_tasks");
+    // Ollama has no call ids, so one is made up for matching the result
+    assert!(!reply.tool_calls[0].id.is_empty());
+    let (_, _, body) = received.lock().unwrap()[0].clone();
+    assert_eq!(body["tools"][0]["function"]["name"], "create_task");
+    assert_eq!(body["messages"][3], json!({ "role": "tool", "content": "[]", "tool_name": "list_tasks" }));
+}
+
+#[tokio::test]
+async fn embeddings_need_an_embedding_model() {
+    let (base_url, received) = serve(json!({ "data": [
+        { "index": 1, "embedding": [0.0, 1.0] },
+        { "index": 0, "embedding": [1.0, 0.0] }
+    ] }))
+    .await;
+    let texts = vec!["Buy milk".to_string(), "Call the bank".to_string()];
+
+    let llm = provider::from_settings(&settings(ProviderKind::OpenAi, &base_url), &NetworkSettings::default());
+    assert!(!llm.can_embed());
+    assert!(llm.embed(&texts).await.is_err());
+
+    let embedding = AISettings {
+        embedding_model: Some("text-embedding-3-small".to_string()),
+        ..settings(ProviderKind::OpenAi, &base_url)
+    };
+    let llm = provider::from_settings(&embedding, &NetworkSettings::default());
+    assert!(llm.can_embed());
+    // Returned in input order, whatever order the server used
+    assert_eq!(llm.embed(&texts).await.unwrap(), [vec![1.0, 0.0], vec![0.0, 1.0]]);
+    let (path, _, body) = received.lock().unwrap()[0].clone();
+    assert_eq!(path, "/embeddings");
+    assert_eq!(body, json!({ "model": "text-embedding-3-small", "input": ["Buy milk", "Call the bank"] }));
+}
+
+#[tokio::test]
+async fn ollama_embeddings() {
+    let (base_url, received) = serve(json!({ "embeddings": [[0.5, 0.5]] })).await;
+    let embedding = AISettings {
+        embedding_model: Some("nomic-embed-text".to_string()),
+        ..settings(ProviderKind::Ollama, &base_url)
+    };
+    let llm = provider::from_settings(&embedding, &NetworkSettings::default());
+
+    assert_eq!(llm.embed(&["Buy milk".to_string()]).await.unwrap(), [vec![0.5, 0.5]]);
+    let (path, _, body) = received.lock().unwrap()[0].clone();
+    assert_eq!(path, "/api/embed");
+    assert_eq!(body["model"], "nomic-embed-text");
+}
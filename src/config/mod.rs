@@ -2,12 +2,18 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
+use crate::core::Status;
+use crate::core::error::AppError;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub keybindings: HashMap<String, String>,
     pub theme: Theme,
     pub ai_settings: AISettings,
+    #[serde(default)]
+    pub workflow: Workflow,
+    #[serde(default)]
+    pub network: NetworkSettings,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,9 +24,172 @@ pub struct Theme {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AISettings {
     pub auto_parse: bool,
     pub offline_fallback: bool,
+    pub provider: ProviderKind,
+    // Unset fields take the provider's defaults (see ProviderKind)
+    pub base_url: Option<String>,
+    pub model: Option<String>,
+    // Environment variable holding the API key
+    pub api_key_env: Option<String>,
+    pub temperature: f32,
+    pub max_tokens: u32,
+    // Use the provider's JSON mode or tool calling for structured replies;
+    // turn off for OpenAI-compatible servers that reject `response_format`
+    pub json_mode: bool,
+    // Model for comparing tasks by meaning when looking for duplicates, e.g.
+    // "text-embedding-3-small"; without one titles are compared as text
+    pub embedding_model: Option<String>,
+}
+
+impl Default for AISettings {
+    fn default() -> Self {
+        Self {
+            auto_parse: true,
+            offline_fallback: true,
+            provider: ProviderKind::default(),
+            base_url: None,
+            model: None,
+            api_key_env: None,
+            temperature: 0.7,
+            max_tokens: 500,
+            json_mode: true,
+            embedding_model: None,
+        }
+    }
+}
+
+impl AISettings {
+    pub fn base_url(&self) -> String {
+        self.base_url.clone().unwrap_or_else(|| self.provider.default_base_url().to_string())
+    }
+
+    pub fn model(&self) -> String {
+        self.model.clone().unwrap_or_else(|| self.provider.default_model().to_string())
+    }
+
+    // The key from the configured (or the provider's usual) environment
+    // variable; local servers need none
+    pub fn api_key(&self) -> Option<String> {
+        let var = self.api_key_env.as_deref().or(self.provider.default_api_key_env())?;
+        std::env::var(var).ok().filter(|key| !key.is_empty())
+    }
+}
+
+// Which LLM API the assistant talks to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderKind {
+    // Any OpenAI-compatible chat completions endpoint: Groq (the default),
+    // OpenAI, or a llama.cpp server
+    #[default]
+    OpenAi,
+    Anthropic,
+    // A local Ollama server
+    Ollama,
+}
+
+impl ProviderKind {
+    pub fn default_base_url(&self) -> &'static str {
+        match self {
+            ProviderKind::OpenAi => "https://api.groq.com/openai/v1",
+            ProviderKind::Anthropic => "https://api.anthropic.com",
+            ProviderKind::Ollama => "http://localhost:11434",
+        }
+    }
+
+    pub fn default_model(&self) -> &'static str {
+        match self {
+            ProviderKind::OpenAi => "llama-3.3-70b-versatile",
+            ProviderKind::Anthropic => "claude-3-5-haiku-latest",
+            ProviderKind::Ollama => "llama3.1",
+        }
+    }
+
+    pub fn default_api_key_env(&self) -> Option<&'static str> {
+        match self {
+            ProviderKind::OpenAi => Some("GROQ_API_KEY"),
+            ProviderKind::Anthropic => Some("ANTHROPIC_API_KEY"),
+            ProviderKind::Ollama => None,
+        }
+    }
+}
+
+// Timeouts and retry behaviour for requests to Supabase and the AI provider
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkSettings {
+    pub connect_timeout_secs: u64,
+    pub request_timeout_secs: u64,
+    // AI completions take longer than database calls
+    pub ai_request_timeout_secs: u64,
+    // Extra attempts after the first, for requests that are safe to repeat
+    pub max_retries: u32,
+    // Backoff doubles from retry_base_ms up to retry_max_ms, with jitter
+    pub retry_base_ms: u64,
+    pub retry_max_ms: u64,
+    // Consecutive failed requests before switching to offline mode
+    pub failure_threshold: u32,
+    // How long to stay offline before trying the server again
+    pub offline_cooldown_secs: u64,
+}
+
+impl Default for NetworkSettings {
+    fn default() -> Self {
+        Self {
+            connect_timeout_secs: 5,
+            request_timeout_secs: 15,
+            ai_request_timeout_secs: 30,
+            max_retries: 3,
+            retry_base_ms: 250,
+            retry_max_ms: 8000,
+            failure_threshold: 5,
+            offline_cooldown_secs: 30,
+        }
+    }
+}
+
+// Allowed status transitions, keyed by the current status
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Workflow {
+    pub transitions: HashMap<Status, Vec<Status>>,
+}
+
+impl Default for Workflow {
+    fn default() -> Self {
+        use Status::*;
+
+        let mut transitions = HashMap::new();
+        transitions.insert(Todo, vec![InProgress, Blocked, Waiting, Done, Cancelled]);
+        transitions.insert(InProgress, vec![Todo, Blocked, Waiting, Done, Cancelled]);
+        transitions.insert(Blocked, vec![Todo, InProgress, Cancelled]);
+        transitions.insert(Waiting, vec![Todo, InProgress, Done, Cancelled]);
+        transitions.insert(Done, vec![Todo]);
+        transitions.insert(Cancelled, vec![Todo]);
+
+        Self { transitions }
+    }
+}
+
+impl Workflow {
+    pub fn allows(&self, from: Status, to: Status) -> bool {
+        from == to
+            || self
+                .transitions
+                .get(&from)
+                .is_some_and(|next| next.contains(&to))
+    }
+
+    // The status after `from` in declaration order that the workflow allows,
+    // wrapping around; used to cycle a task's status with one key
+    pub fn cycle(&self, from: Status) -> Option<Status> {
+        let start = Status::ALL.iter().position(|s| *s == from)?;
+        (1..Status::ALL.len())
+            .map(|i| Status::ALL[(start + i) % Status::ALL.len()])
+            .find(|to| self.allows(from, *to))
+    }
 }
 
 impl Default for Config {
@@ -35,6 +204,7 @@ impl Default for Config {
         
         // Actions
         keybindings.insert("toggle_done".to_string(), "space".to_string());
+        keybindings.insert("cycle_status".to_string(), "s".to_string());
         keybindings.insert("delete_task".to_string(), "d".to_string());
         keybindings.insert("edit_task".to_string(), "e".to_string());
         keybindings.insert("priority_low".to_string(), "1".to_string());
@@ -53,10 +223,9 @@ impl Default for Config {
                 secondary_color: "Yellow".to_string(),
                 accent_color: "Green".to_string(),
             },
-            ai_settings: AISettings {
-                auto_parse: true,
-                offline_fallback: true,
-            },
+            ai_settings: AISettings::default(),
+            workflow: Workflow::default(),
+            network: NetworkSettings::default(),
         }
     }
 }
@@ -74,7 +243,7 @@ impl Config {
         }
     }
 
-    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn save(&self) -> Result<(), AppError> {
         let config_path = Self::config_path();
         
         if let Some(parent) = config_path.parent() {
@@ -99,9 +268,13 @@ impl Config {
     }
 }
 
+const MAX_HISTORY: usize = 500;
+
 pub struct CommandHistory {
     history: Vec<String>,
     current_index: Option<usize>,
+    prefix: String,
+    path: Option<PathBuf>,
 }
 
 impl CommandHistory {
@@ -109,43 +282,141 @@ impl CommandHistory {
         Self {
             history: Vec::new(),
             current_index: None,
+            prefix: String::new(),
+            path: None,
+        }
+    }
+
+    // Load history from ~/.config/todo-ai/history, one command per line
+    pub fn load() -> Self {
+        Self::load_from(Self::history_path())
+    }
+
+    // Load history from `path`, saving back to it as commands are added
+    fn load_from(path: PathBuf) -> Self {
+        let mut history = Self::new();
+
+        if let Ok(contents) = fs::read_to_string(&path) {
+            for line in contents.lines() {
+                history.push_entry(line.to_string());
+            }
         }
+
+        history.path = Some(path);
+        history
     }
 
     pub fn add(&mut self, command: String) {
-        if !command.is_empty() && (self.history.is_empty() || self.history.last() != Some(&command)) {
-            self.history.push(command);
+        if !command.trim().is_empty() {
+            self.push_entry(command);
+            let _ = self.save();
         }
-        self.current_index = None;
+        self.reset();
     }
 
-    pub fn previous(&mut self) -> Option<String> {
-        if self.history.is_empty() {
-            return None;
+    // Move a command to the end of the history, dropping older duplicates
+    fn push_entry(&mut self, command: String) {
+        if command.trim().is_empty() {
+            return;
+        }
+        self.history.retain(|c| c != &command);
+        self.history.push(command);
+
+        if self.history.len() > MAX_HISTORY {
+            let excess = self.history.len() - MAX_HISTORY;
+            self.history.drain(..excess);
         }
+    }
 
-        let index = match self.current_index {
-            Some(i) if i > 0 => i - 1,
-            None => self.history.len() - 1,
-            _ => return None,
+    fn save(&self) -> Result<(), AppError> {
+        let Some(path) = &self.path else {
+            return Ok(());
         };
 
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut contents = self.history.join("\n");
+        contents.push('\n');
+        fs::write(path, contents)?;
+        Ok(())
+    }
+
+    fn history_path() -> PathBuf {
+        let mut path = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+        path.push(".config");
+        path.push("todo-ai");
+        path.push("history");
+        path
+    }
+
+    // Step back to the previous command starting with `input`. The prefix is
+    // captured on the first step so later steps keep filtering by it.
+    pub fn previous(&mut self, input: &str) -> Option<String> {
+        if self.current_index.is_none() {
+            self.prefix = input.to_string();
+        }
+
+        let end = self.current_index.unwrap_or(self.history.len());
+        let index = self.history[..end]
+            .iter()
+            .rposition(|c| c.starts_with(&self.prefix))?;
+
         self.current_index = Some(index);
         self.history.get(index).cloned()
     }
 
+    // Step forward; returns the original prefix once past the newest match
     pub fn next(&mut self) -> Option<String> {
-        let index = match self.current_index {
-            Some(i) if i < self.history.len() - 1 => i + 1,
-            _ => return None,
-        };
+        let current = self.current_index?;
 
-        self.current_index = Some(index);
-        self.history.get(index).cloned()
+        match self.history[current + 1..]
+            .iter()
+            .position(|c| c.starts_with(&self.prefix))
+        {
+            Some(offset) => {
+                let index = current + 1 + offset;
+                self.current_index = Some(index);
+                self.history.get(index).cloned()
+            }
+            None => {
+                self.current_index = None;
+                Some(self.prefix.clone())
+            }
+        }
+    }
+
+    // Find the newest command containing `query` strictly before `before`
+    pub fn search(&self, query: &str, before: Option<usize>) -> Option<usize> {
+        let end = before.unwrap_or(self.history.len()).min(self.history.len());
+        self.history[..end].iter().rposition(|c| c.contains(query))
+    }
+
+    pub fn get(&self, index: usize) -> Option<&str> {
+        self.history.get(index).map(|c| c.as_str())
     }
 
     pub fn reset(&mut self) {
         self.current_index = None;
+        self.prefix.clear();
+    }
+}
+
+// State for an in-progress Ctrl-r reverse incremental search
+pub struct HistorySearch {
+    pub query: String,
+    pub matched: Option<usize>,
+    pub original_input: String,
+}
+
+impl HistorySearch {
+    pub fn new(original_input: String) -> Self {
+        Self {
+            query: String::new(),
+            matched: None,
+            original_input,
+        }
     }
 }
 #[allow(dead_code)]
@@ -204,4 +475,80 @@ impl CommandPalette {
     pub fn get_all(&self) -> Vec<Command> {
         self.commands.clone()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(commands: &[&str]) -> CommandHistory {
+        let mut history = CommandHistory::new();
+        for command in commands {
+            history.add(command.to_string());
+        }
+        history
+    }
+
+    fn entries(history: &CommandHistory) -> Vec<&str> {
+        (0..).map_while(|i| history.get(i)).collect()
+    }
+
+    #[test]
+    fn repeated_commands_move_to_the_end() {
+        let history = history(&["add milk", "sort due", "add milk", "  ", "view board"]);
+        assert_eq!(entries(&history), ["sort due", "add milk", "view board"]);
+    }
+
+    #[test]
+    fn only_the_newest_commands_are_kept() {
+        let commands: Vec<String> = (0..MAX_HISTORY + 20).map(|i| format!("add task {}", i)).collect();
+        let history = history(&commands.iter().map(String::as_str).collect::<Vec<_>>());
+        let kept = entries(&history);
+        assert_eq!(kept.len(), MAX_HISTORY);
+        assert_eq!(kept[0], "add task 20");
+        assert_eq!(kept[MAX_HISTORY - 1], format!("add task {}", MAX_HISTORY + 19));
+    }
+
+    #[test]
+    fn previous_and_next_filter_by_the_typed_prefix() {
+        let mut history = history(&["add milk", "sort due", "add bread", "view board"]);
+
+        assert_eq!(history.previous("add").as_deref(), Some("add bread"));
+        // The prefix typed first keeps applying, whatever the input shows now
+        assert_eq!(history.previous("add bread").as_deref(), Some("add milk"));
+        // Stays on the oldest match
+        assert_eq!(history.previous("add milk"), None);
+        assert_eq!(history.next().as_deref(), Some("add bread"));
+        // Past the newest match the typed prefix comes back, then nothing
+        assert_eq!(history.next().as_deref(), Some("add"));
+        assert_eq!(history.next(), None);
+
+        // Without a prefix every command matches
+        assert_eq!(history.previous("").as_deref(), Some("view board"));
+        history.reset();
+        assert_eq!(history.previous("x"), None);
+    }
+
+    #[test]
+    fn search_finds_older_matches_step_by_step() {
+        let history = history(&["add milk", "sort due", "add bread"]);
+        assert_eq!(history.search("add", None), Some(2));
+        assert_eq!(history.search("add", Some(2)), Some(0));
+        assert_eq!(history.search("add", Some(0)), None);
+        assert_eq!(history.search("due", Some(99)), Some(1));
+    }
+
+    #[test]
+    fn history_survives_a_restart() {
+        let path = std::env::temp_dir().join(format!("todo-ai-history-{}", uuid::Uuid::new_v4())).join("history");
+
+        let mut history = CommandHistory::load_from(path.clone());
+        history.add("add milk".to_string());
+        history.add("sort due".to_string());
+        history.add("add milk".to_string());
+
+        let reloaded = CommandHistory::load_from(path.clone());
+        assert_eq!(entries(&reloaded), ["sort due", "add milk"]);
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
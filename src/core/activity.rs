@@ -0,0 +1,106 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::core::{Priority, Status, Task};
+
+// Kinds of entries in a task's timeline. Stored by name in task_activity
+// (migration 0006); comments live in the same append-only table.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Created,
+    Renamed,
+    PriorityChanged,
+    StatusChanged,
+    Completed,
+    Reassigned,
+    Rescheduled,
+    Moved,
+    Retagged,
+    Commented,
+    Deleted,
+}
+
+impl Action {
+    // Past-tense phrase shown after the actor's name
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::Created => "created the task",
+            Action::Renamed => "renamed it",
+            Action::PriorityChanged => "changed priority",
+            Action::StatusChanged => "changed status",
+            Action::Completed => "completed it",
+            Action::Reassigned => "reassigned it",
+            Action::Rescheduled => "changed the due date",
+            Action::Moved => "moved it",
+            Action::Retagged => "changed tags",
+            Action::Commented => "commented",
+            Action::Deleted => "deleted it",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Activity {
+    pub id: String,
+    pub task_id: String,
+    pub user_id: Option<String>,
+    pub action: Action,
+    // Human-readable summary of the change, or the comment text. For
+    // Reassigned and Moved it holds the new user or project id (empty when
+    // cleared) so the UI can show current names; for Deleted the title.
+    pub detail: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+// Timeline entries describing how `after` differs from `before`. Position
+// changes are not recorded; reordering would flood the log.
+pub fn changes(before: &Task, after: &Task) -> Vec<(Action, Option<String>)> {
+    let mut entries = Vec::new();
+
+    if before.title != after.title {
+        entries.push((Action::Renamed, Some(format!("\"{}\" → \"{}\"", before.title, after.title))));
+    }
+    if before.priority != after.priority {
+        entries.push((
+            Action::PriorityChanged,
+            Some(format!("{} → {}", priority_name(before.priority), priority_name(after.priority))),
+        ));
+    }
+    if before.status != after.status {
+        entries.push(status_change(before.status, after.status));
+    }
+    if before.assignee_id != after.assignee_id {
+        entries.push((Action::Reassigned, Some(after.assignee_id.clone().unwrap_or_default())));
+    }
+    if before.due_date != after.due_date {
+        let detail = after
+            .due_date
+            .map_or("no due date".to_string(), |d| d.format("%Y-%m-%d %H:%M UTC").to_string());
+        entries.push((Action::Rescheduled, Some(detail)));
+    }
+    if before.project_id != after.project_id {
+        entries.push((Action::Moved, Some(after.project_id.clone().unwrap_or_default())));
+    }
+    if before.tags != after.tags {
+        let tags: Vec<String> = after.tags.iter().map(|t| format!("+{}", t)).collect();
+        entries.push((Action::Retagged, Some(tags.join(" "))));
+    }
+
+    entries
+}
+
+pub fn status_change(from: Status, to: Status) -> (Action, Option<String>) {
+    if to == Status::Done {
+        (Action::Completed, None)
+    } else {
+        (Action::StatusChanged, Some(format!("{} → {}", from.label(), to.label())))
+    }
+}
+
+fn priority_name(priority: Priority) -> &'static str {
+    match priority {
+        Priority::Low => "Low",
+        Priority::Medium => "Medium",
+        Priority::High => "High",
+    }
+}
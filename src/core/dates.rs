@@ -0,0 +1,76 @@
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+
+// Time of day given to due dates entered without one
+pub fn default_due_time() -> NaiveTime {
+    NaiveTime::from_hms_opt(9, 0, 0).unwrap()
+}
+
+// A wall-clock time in the user's timezone. Times skipped by a DST change
+// resolve to None; repeated ones take the earlier instant.
+pub fn local_datetime(date: NaiveDate, time: NaiveTime) -> Option<DateTime<Utc>> {
+    Local
+        .from_local_datetime(&date.and_time(time))
+        .earliest()
+        .map(|d| d.with_timezone(&Utc))
+}
+
+// "2024-05-01", "2024-05-01 17:30" or "2024-05-01T17:30[:00]" in local
+// time, or a full RFC 3339 timestamp with its own offset
+pub fn parse_iso(text: &str) -> Option<DateTime<Utc>> {
+    let text = text.trim();
+    if let Ok(date) = DateTime::parse_from_rfc3339(text) {
+        return Some(date.with_timezone(&Utc));
+    }
+    for format in ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"] {
+        if let Ok(datetime) = NaiveDateTime::parse_from_str(text, format) {
+            return local_datetime(datetime.date(), datetime.time());
+        }
+    }
+    let date = NaiveDate::parse_from_str(text, "%Y-%m-%d").ok()?;
+    local_datetime(date, default_due_time())
+}
+
+// The inverse of parse_iso for editing: local date and time of day
+pub fn format_local(due: DateTime<Utc>) -> String {
+    due.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string()
+}
+
+// "45m", "2h", "1h30m", "1.5h", "90" (minutes) or "1d" (a working day of
+// eight hours)
+pub fn parse_duration(text: &str) -> Option<u32> {
+    let text = text.trim().to_lowercase().replace(' ', "");
+    if text.is_empty() {
+        return None;
+    }
+    if let Ok(minutes) = text.parse::<u32>() {
+        return (minutes > 0).then_some(minutes);
+    }
+
+    let mut total = 0.0;
+    let mut rest = text.as_str();
+    while !rest.is_empty() {
+        let number_end = rest.find(|c: char| !(c.is_ascii_digit() || c == '.'))?;
+        let value: f64 = rest[..number_end].parse().ok()?;
+        rest = &rest[number_end..];
+        let unit_end = rest.find(|c: char| c.is_ascii_digit() || c == '.').unwrap_or(rest.len());
+        let minutes_per = match &rest[..unit_end] {
+            "m" | "min" | "mins" | "minute" | "minutes" => 1.0,
+            "h" | "hr" | "hrs" | "hour" | "hours" => 60.0,
+            "d" | "day" | "days" => 480.0,
+            _ => return None,
+        };
+        total += value * minutes_per;
+        rest = &rest[unit_end..];
+    }
+    let minutes = total.round() as u32;
+    (minutes > 0).then_some(minutes)
+}
+
+// Compact effort label: "45m", "2h", "1h30m"
+pub fn format_duration(minutes: u32) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("{}m", m),
+        (h, 0) => format!("{}h", h),
+        (h, m) => format!("{}h{}m", h, m),
+    }
+}
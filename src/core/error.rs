@@ -0,0 +1,134 @@
+use std::fmt;
+
+// Errors surfaced by the database, AI and storage layers. Each variant keeps
+// enough detail for the TUI to tell the user what went wrong and whether
+// trying again could help.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AppError {
+    // The request never got a response: DNS, connect, TLS or timeout
+    Network(String),
+    // The server answered with a non-success status
+    Http { context: String, status: u16, body: String },
+    // Bad credentials, expired session or a permission check failed
+    Auth(String),
+    // Input rejected before anything was sent
+    Validation(String),
+    NotFound(String),
+    // A response or file could not be decoded
+    Serialization(String),
+    // Local files (config, history) or direct Postgres access
+    Storage(String),
+    // Refused without sending: too many recent requests failed
+    Offline(String),
+}
+
+pub type Result<T> = std::result::Result<T, AppError>;
+
+impl AppError {
+    // Build the error for a non-success response. PostgREST and GoTrue send
+    // JSON bodies with a human-readable "message" (or "msg"); use it when present.
+    pub fn from_response(context: &str, status: u16, body: String) -> AppError {
+        let message = serde_json::from_str::<serde_json::Value>(&body)
+            .ok()
+            .and_then(|v| {
+                ["message", "msg", "error_description", "error"]
+                    .iter()
+                    .find_map(|k| v.get(k).and_then(|m| m.as_str()).map(str::to_string))
+            })
+            .unwrap_or(body);
+
+        match status {
+            401 | 403 => AppError::Auth(format!("{}: {}", context, message)),
+            _ => AppError::Http { context: context.to_string(), status, body: message },
+        }
+    }
+
+    // Whether the same request might succeed if sent again
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            AppError::Network(_) => true,
+            AppError::Http { status, .. } => *status == 408 || *status == 429 || *status >= 500,
+            _ => false,
+        }
+    }
+
+    // Short text for the status bar, saying what the user can do about it
+    pub fn user_message(&self) -> String {
+        match self {
+            AppError::Network(msg) => format!("Can't reach the server ({}); check your connection and try again", msg),
+            AppError::Http { context, status, body } if self.is_retryable() => {
+                format!("{}: server error {} ({}); try again shortly", context, status, body)
+            }
+            AppError::Http { context, status, body } => format!("{}: rejected with {} ({})", context, status, body),
+            AppError::Auth(msg) => format!("{} — check your login or permissions", msg),
+            AppError::Validation(msg) => msg.clone(),
+            AppError::NotFound(msg) => format!("Not found: {}", msg),
+            AppError::Serialization(msg) => format!("Unexpected response: {}", msg),
+            AppError::Storage(msg) => format!("Storage error: {}", msg),
+            AppError::Offline(msg) => format!("Offline: {}", msg),
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AppError::Network(msg) => write!(f, "Network error: {}", msg),
+            AppError::Http { context, status, body } => write!(f, "{}: HTTP {}: {}", context, status, body),
+            AppError::Auth(msg) => write!(f, "Authentication error: {}", msg),
+            AppError::Validation(msg) => write!(f, "Validation error: {}", msg),
+            AppError::NotFound(msg) => write!(f, "Not found: {}", msg),
+            AppError::Serialization(msg) => write!(f, "Serialization error: {}", msg),
+            AppError::Storage(msg) => write!(f, "Storage error: {}", msg),
+            AppError::Offline(msg) => write!(f, "Offline: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<reqwest::Error> for AppError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_decode() {
+            AppError::Serialization(e.to_string())
+        } else if let Some(status) = e.status() {
+            AppError::Http { context: "Request failed".to_string(), status: status.as_u16(), body: e.to_string() }
+        } else {
+            AppError::Network(e.to_string())
+        }
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(e: serde_json::Error) -> Self {
+        AppError::Serialization(e.to_string())
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(e: std::io::Error) -> Self {
+        AppError::Storage(e.to_string())
+    }
+}
+
+impl From<tokio_postgres::Error> for AppError {
+    fn from(e: tokio_postgres::Error) -> Self {
+        // The server's error text lives in the DbError, not the outer Display
+        match e.as_db_error() {
+            Some(db) => AppError::Storage(db.to_string()),
+            None => AppError::Storage(e.to_string()),
+        }
+    }
+}
+
+impl From<native_tls::Error> for AppError {
+    fn from(e: native_tls::Error) -> Self {
+        AppError::Network(e.to_string())
+    }
+}
+
+impl From<bcrypt::BcryptError> for AppError {
+    fn from(e: bcrypt::BcryptError) -> Self {
+        AppError::Auth(format!("Password hashing failed: {}", e))
+    }
+}
@@ -1,39 +1,73 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
-use std::fmt;
-
-#[derive(Debug)]
-pub enum TaskError {
-    DatabaseError(String),
-    NotFound(String),
-    ValidationError(String),
-}
-
-impl fmt::Display for TaskError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            TaskError::DatabaseError(msg) => write!(f, "Database error: {}", msg),
-            TaskError::NotFound(msg) => write!(f, "Not found: {}", msg),
-            TaskError::ValidationError(msg) => write!(f, "Validation error: {}", msg),
-        }
-    }
-}
-
-impl std::error::Error for TaskError {}
+use crate::core::recurrence::Recurrence;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Task {
     pub id: String,
     pub title: String,
     pub description: String,
-    pub done: bool,
+    pub status: Status,
     pub priority: Priority,
     pub due_date: Option<DateTime<Utc>>,
     pub tags: Vec<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub position: i32,
+    pub project_id: Option<String>,
+    pub assignee_id: Option<String>,
+    // Expected effort in minutes
+    #[serde(default)]
+    pub estimate_minutes: Option<u32>,
+    // Task this one is a subtask of
+    #[serde(default)]
+    pub parent_id: Option<String>,
+    #[serde(default)]
+    pub recurrence: Option<Recurrence>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Project {
+    pub id: String,
+    pub name: String,
+    pub color: Option<String>,
+    pub position: i32,
+    pub created_at: DateTime<Utc>,
+    pub owner_id: String,
+    // The current user's role in this project
+    pub role: Role,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Owner,
+    Editor,
+    Viewer,
+}
+
+impl Role {
+    pub fn parse(name: &str) -> Option<Role> {
+        match name.to_lowercase().as_str() {
+            "owner" => Some(Role::Owner),
+            "editor" => Some(Role::Editor),
+            "viewer" => Some(Role::Viewer),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Owner => "owner",
+            Role::Editor => "editor",
+            Role::Viewer => "viewer",
+        }
+    }
+
+    pub fn can_edit(&self) -> bool {
+        !matches!(self, Role::Viewer)
+    }
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
@@ -43,6 +77,81 @@ pub enum Priority {
     High = 3,
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Todo,
+    InProgress,
+    Blocked,
+    Waiting,
+    Done,
+    Cancelled,
+}
+
+impl Status {
+    pub const ALL: [Status; 6] = [
+        Status::Todo,
+        Status::InProgress,
+        Status::Blocked,
+        Status::Waiting,
+        Status::Done,
+        Status::Cancelled,
+    ];
+
+    // Name used in the database and config
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Status::Todo => "todo",
+            Status::InProgress => "in_progress",
+            Status::Blocked => "blocked",
+            Status::Waiting => "waiting",
+            Status::Done => "done",
+            Status::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Status> {
+        let name = name.to_lowercase().replace(['-', ' '], "_");
+        match name.as_str() {
+            "todo" => Some(Status::Todo),
+            "in_progress" | "doing" | "wip" => Some(Status::InProgress),
+            "blocked" => Some(Status::Blocked),
+            "waiting" => Some(Status::Waiting),
+            "done" => Some(Status::Done),
+            "cancelled" | "canceled" => Some(Status::Cancelled),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Status::Todo => "Todo",
+            Status::InProgress => "In Progress",
+            Status::Blocked => "Blocked",
+            Status::Waiting => "Waiting",
+            Status::Done => "Done",
+            Status::Cancelled => "Cancelled",
+        }
+    }
+
+    // Three-cell marker shown in place of a checkbox
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Status::Todo => "[ ]",
+            Status::InProgress => "[~]",
+            Status::Blocked => "[!]",
+            Status::Waiting => "[…]",
+            Status::Done => "[✓]",
+            Status::Cancelled => "[-]",
+        }
+    }
+
+    // Done and cancelled tasks need no further work
+    pub fn is_closed(&self) -> bool {
+        matches!(self, Status::Done | Status::Cancelled)
+    }
+}
+
 impl Task {
     pub fn new(title: String) -> Self {
         let now = Utc::now();
@@ -50,15 +159,45 @@ impl Task {
             id: Uuid::new_v4().to_string(),
             title,
             description: String::new(),
-            done: false,
+            status: Status::Todo,
             priority: Priority::Medium,
             due_date: None,
             tags: Vec::new(),
             created_at: now,
             updated_at: now,
             position: 0,
+            project_id: None,
+            assignee_id: None,
+            estimate_minutes: None,
+            parent_id: None,
+            recurrence: None,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.status == Status::Done
+    }
+}
+
+impl Project {
+    pub fn new(name: String, color: Option<String>, owner_id: String) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            name,
+            color,
+            position: 0,
+            created_at: Utc::now(),
+            owner_id,
+            role: Role::Owner,
         }
     }
 }
 
+pub mod activity;
+pub mod error;
 pub mod cache;
+pub mod dates;
+pub mod projects;
+pub mod recurrence;
+pub mod sort;
+pub mod tags;
@@ -0,0 +1,154 @@
+use crate::core::Project;
+
+// Pull a project reference out of free text: an `@name` word, or the phrase
+// "for project <name>" / "in project <name>". Returns the remaining text and
+// the referenced name as written.
+pub fn extract_project(input: &str) -> (String, Option<String>) {
+    let words: Vec<&str> = input.split_whitespace().collect();
+    let mut kept = Vec::new();
+    let mut name = None;
+    let mut i = 0;
+
+    while i < words.len() {
+        let word = words[i];
+        if name.is_none() {
+            if let Some(at) = word.strip_prefix('@').map(trim_trailing_punctuation) {
+                if !at.is_empty() {
+                    name = Some(at.to_string());
+                    i += 1;
+                    continue;
+                }
+            }
+        }
+
+        let lower = word.to_lowercase();
+        if name.is_none()
+            && (lower == "for" || lower == "in")
+            && i + 2 < words.len()
+            && words[i + 1].eq_ignore_ascii_case("project")
+        {
+            name = Some(trim_trailing_punctuation(words[i + 2]).to_string());
+            i += 3;
+            continue;
+        }
+
+        kept.push(word);
+        i += 1;
+    }
+
+    (kept.join(" "), name)
+}
+
+// Find a project by name, ignoring case and treating '-' and '_' as spaces
+// so that `@website-redesign` matches "Website Redesign". Falls back to a
+// unique prefix match.
+pub fn resolve<'a>(projects: &'a [Project], name: &str) -> Option<&'a Project> {
+    let wanted = normalize(name);
+    if wanted.is_empty() {
+        return None;
+    }
+
+    if let Some(exact) = projects.iter().find(|p| normalize(&p.name) == wanted) {
+        return Some(exact);
+    }
+
+    let mut prefixed = projects.iter().filter(|p| normalize(&p.name).starts_with(&wanted));
+    match (prefixed.next(), prefixed.next()) {
+        (Some(only), None) => Some(only),
+        _ => None,
+    }
+}
+
+// "@work," at the end of a clause refers to "work"
+fn trim_trailing_punctuation(word: &str) -> &str {
+    word.trim_end_matches(|c: char| c.is_ascii_punctuation())
+}
+
+fn normalize(name: &str) -> String {
+    name.to_lowercase()
+        .replace(['-', '_'], " ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn projects(names: &[&str]) -> Vec<Project> {
+        names.iter().map(|n| Project::new(n.to_string(), None, "u1".to_string())).collect()
+    }
+
+    #[test]
+    fn at_names_come_out_anywhere_in_the_text() {
+        let cases = [
+            ("@work call mom", "call mom", "work"),
+            ("call @Work mom", "call mom", "Work"),
+            ("call mom @website-redesign", "call mom", "website-redesign"),
+            ("call mom @work, then rest", "call mom then rest", "work"),
+            ("call mom @work.", "call mom", "work"),
+        ];
+        for (input, text, name) in cases {
+            assert_eq!(extract_project(input), (text.to_string(), Some(name.to_string())), "{input}");
+        }
+    }
+
+    #[test]
+    fn project_phrases_are_recognised() {
+        assert_eq!(
+            extract_project("Draft copy for project Website."),
+            ("Draft copy".to_string(), Some("Website".to_string()))
+        );
+        assert_eq!(
+            extract_project("notes IN Project home today"),
+            ("notes today".to_string(), Some("home".to_string()))
+        );
+        // No name after the phrase
+        assert_eq!(extract_project("work in project"), ("work in project".to_string(), None));
+    }
+
+    #[test]
+    fn only_the_first_reference_counts() {
+        assert_eq!(
+            extract_project("@home tidy up @work for project garden"),
+            ("tidy up @work for project garden".to_string(), Some("home".to_string()))
+        );
+    }
+
+    #[test]
+    fn bare_markers_and_emails_are_not_references() {
+        assert_eq!(extract_project("meet @ noon"), ("meet @ noon".to_string(), None));
+        assert_eq!(extract_project("ping @!"), ("ping @!".to_string(), None));
+        assert_eq!(extract_project("mail bob@example.com"), ("mail bob@example.com".to_string(), None));
+        assert_eq!(extract_project(""), (String::new(), None));
+    }
+
+    #[test]
+    fn resolving_ignores_case_and_separators() {
+        let all = projects(&["Website Redesign", "Home", "Work"]);
+        assert_eq!(resolve(&all, "website-redesign").unwrap().name, "Website Redesign");
+        assert_eq!(resolve(&all, "WEBSITE_redesign").unwrap().name, "Website Redesign");
+        assert_eq!(resolve(&all, "home").unwrap().name, "Home");
+    }
+
+    #[test]
+    fn unique_prefixes_resolve_and_ambiguous_ones_dont() {
+        let all = projects(&["Website Redesign", "Web Shop", "Home", "Homework"]);
+        assert_eq!(resolve(&all, "websi").unwrap().name, "Website Redesign");
+        assert!(resolve(&all, "web").is_none());
+        // An exact match wins over a longer name sharing its prefix
+        assert_eq!(resolve(&all, "home").unwrap().name, "Home");
+        assert_eq!(resolve(&all, "homew").unwrap().name, "Homework");
+    }
+
+    #[test]
+    fn missing_projects_dont_resolve() {
+        let all = projects(&["Home", "Work"]);
+        assert!(resolve(&all, "garden").is_none());
+        assert!(resolve(&all, "redesign").is_none());
+        assert!(resolve(&all, "").is_none());
+        assert!(resolve(&all, " - ").is_none());
+        assert!(resolve(&[], "home").is_none());
+    }
+}
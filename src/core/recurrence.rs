@@ -0,0 +1,156 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// Longest accepted interval count, matching the tasks_recurrence_check
+// constraint
+const MAX_EVERY: u32 = 999;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl Unit {
+    fn parse(word: &str) -> Option<Unit> {
+        match word {
+            "day" | "days" => Some(Unit::Day),
+            "week" | "weeks" => Some(Unit::Week),
+            "month" | "months" => Some(Unit::Month),
+            "year" | "years" => Some(Unit::Year),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Unit::Day => "day",
+            Unit::Week => "week",
+            Unit::Month => "month",
+            Unit::Year => "year",
+        }
+    }
+}
+
+// How often a task repeats: every `every` days, weeks, months or years,
+// counted from its due date. Stored in tasks.recurrence as a Postgres
+// interval ("1 day", "2 weeks") so the database can create the next
+// occurrence when the task is completed (migration 0014); shown as "daily"
+// or "every 2 weeks".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Recurrence {
+    pub every: u32,
+    pub unit: Unit,
+}
+
+impl Recurrence {
+    pub fn new(every: u32, unit: Unit) -> Self {
+        Self { every: every.clamp(1, MAX_EVERY), unit }
+    }
+
+    // "daily", "weekly", "monthly", "yearly"/"annually", "every day", "every
+    // 3 days", "every other week", or the stored form "2 weeks"
+    pub fn parse(text: &str) -> Option<Recurrence> {
+        let text = text.trim().to_lowercase();
+        let words: Vec<&str> = text.split_whitespace().collect();
+        let (prefixed, words) = match words.split_first() {
+            Some((&"every", rest)) => (true, rest),
+            _ => (false, &words[..]),
+        };
+
+        match *words {
+            ["daily"] => Some(Recurrence::new(1, Unit::Day)),
+            ["weekly"] => Some(Recurrence::new(1, Unit::Week)),
+            ["fortnightly" | "biweekly"] => Some(Recurrence::new(2, Unit::Week)),
+            ["monthly"] => Some(Recurrence::new(1, Unit::Month)),
+            ["yearly" | "annually"] => Some(Recurrence::new(1, Unit::Year)),
+            [unit] if prefixed => Some(Recurrence::new(1, Unit::parse(unit)?)),
+            ["other", unit] if prefixed => Some(Recurrence::new(2, Unit::parse(unit)?)),
+            [count, unit] => {
+                let every: u32 = count.parse().ok().filter(|n| (1..=MAX_EVERY).contains(n))?;
+                Some(Recurrence::new(every, Unit::parse(unit)?))
+            }
+            _ => None,
+        }
+    }
+
+    // The form stored in tasks.recurrence
+    pub fn interval(&self) -> String {
+        let plural = if self.every == 1 { "" } else { "s" };
+        format!("{} {}{}", self.every, self.unit.name(), plural)
+    }
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.every, self.unit) {
+            (1, Unit::Day) => write!(f, "daily"),
+            (1, Unit::Week) => write!(f, "weekly"),
+            (1, Unit::Month) => write!(f, "monthly"),
+            (1, Unit::Year) => write!(f, "yearly"),
+            (every, unit) => write!(f, "every {} {}s", every, unit.name()),
+        }
+    }
+}
+
+impl TryFrom<String> for Recurrence {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, String> {
+        Recurrence::parse(&text).ok_or_else(|| format!("invalid recurrence {:?}", text))
+    }
+}
+
+impl From<Recurrence> for String {
+    fn from(recurrence: Recurrence) -> String {
+        recurrence.interval()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn phrases_and_stored_intervals_parse() {
+        let cases = [
+            ("daily", Recurrence::new(1, Unit::Day)),
+            ("Every day", Recurrence::new(1, Unit::Day)),
+            ("every 3 days", Recurrence::new(3, Unit::Day)),
+            ("weekly", Recurrence::new(1, Unit::Week)),
+            ("every other week", Recurrence::new(2, Unit::Week)),
+            ("fortnightly", Recurrence::new(2, Unit::Week)),
+            ("every month", Recurrence::new(1, Unit::Month)),
+            ("annually", Recurrence::new(1, Unit::Year)),
+            ("2 weeks", Recurrence::new(2, Unit::Week)),
+            ("1 year", Recurrence::new(1, Unit::Year)),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(Recurrence::parse(text), Some(expected), "{:?}", text);
+        }
+        for text in ["", "every", "every 0 days", "every -1 weeks", "sometimes", "every 2 fortnights", "every 1000 days", "hourly", "week"] {
+            assert_eq!(Recurrence::parse(text), None, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn stored_and_shown_forms_round_trip() {
+        for (recurrence, interval, shown) in [
+            (Recurrence::new(1, Unit::Day), "1 day", "daily"),
+            (Recurrence::new(2, Unit::Week), "2 weeks", "every 2 weeks"),
+            (Recurrence::new(1, Unit::Month), "1 month", "monthly"),
+            (Recurrence::new(3, Unit::Year), "3 years", "every 3 years"),
+        ] {
+            assert_eq!(recurrence.interval(), interval);
+            assert_eq!(recurrence.to_string(), shown);
+            assert_eq!(Recurrence::parse(interval), Some(recurrence));
+            assert_eq!(Recurrence::parse(shown), Some(recurrence));
+        }
+        let json = serde_json::to_string(&Recurrence::new(2, Unit::Week)).unwrap();
+        assert_eq!(json, "\"2 weeks\"");
+        assert!(serde_json::from_str::<Recurrence>("\"now and then\"").is_err());
+    }
+}
@@ -0,0 +1,310 @@
+use chrono::{DateTime, Utc};
+use std::cmp::Ordering;
+use std::fmt;
+use crate::core::{Priority, Status, Task};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortKey {
+    Manual,
+    Priority,
+    Due,
+    Created,
+    Updated,
+    Title,
+    Urgency,
+}
+
+impl SortKey {
+    pub const ALL: [SortKey; 7] = [
+        SortKey::Manual,
+        SortKey::Priority,
+        SortKey::Due,
+        SortKey::Created,
+        SortKey::Updated,
+        SortKey::Title,
+        SortKey::Urgency,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SortKey::Manual => "manual",
+            SortKey::Priority => "priority",
+            SortKey::Due => "due",
+            SortKey::Created => "created",
+            SortKey::Updated => "updated",
+            SortKey::Title => "title",
+            SortKey::Urgency => "urgency",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<SortKey> {
+        match name {
+            "due_date" => Some(SortKey::Due),
+            "position" => Some(SortKey::Manual),
+            _ => Self::ALL.iter().copied().find(|k| k.name() == name),
+        }
+    }
+
+    // Direction that puts the most relevant tasks first
+    fn default_descending(&self) -> bool {
+        matches!(
+            self,
+            SortKey::Priority | SortKey::Created | SortKey::Updated | SortKey::Urgency
+        )
+    }
+
+    fn compare(&self, a: &Task, b: &Task, now: DateTime<Utc>) -> Ordering {
+        match self {
+            SortKey::Manual => a.position.cmp(&b.position),
+            SortKey::Priority => priority_rank(a.priority).cmp(&priority_rank(b.priority)),
+            SortKey::Due => match (a.due_date, b.due_date) {
+                (Some(x), Some(y)) => x.cmp(&y),
+                _ => Ordering::Equal,
+            },
+            SortKey::Created => a.created_at.cmp(&b.created_at),
+            SortKey::Updated => a.updated_at.cmp(&b.updated_at),
+            SortKey::Title => a.title.to_lowercase().cmp(&b.title.to_lowercase()),
+            SortKey::Urgency => urgency(a, now).total_cmp(&urgency(b, now)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SortField {
+    pub key: SortKey,
+    pub descending: bool,
+}
+
+// Ordered list of sort keys; later keys break ties in earlier ones and the
+// manual position breaks any remaining ties
+#[derive(Debug, Clone, PartialEq)]
+pub struct SortSpec {
+    pub fields: Vec<SortField>,
+}
+
+impl Default for SortSpec {
+    fn default() -> Self {
+        Self {
+            fields: vec![SortField { key: SortKey::Manual, descending: false }],
+        }
+    }
+}
+
+impl SortSpec {
+    // Parse "priority,-due": keys are comma separated and a leading '-'
+    // reverses that key's default direction
+    pub fn parse(spec: &str) -> Result<SortSpec, String> {
+        let mut fields = Vec::new();
+        for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (reversed, name) = match part.strip_prefix('-') {
+                Some(name) => (true, name),
+                None => (false, part),
+            };
+            let key = SortKey::parse(&name.to_lowercase())
+                .ok_or_else(|| format!("Unknown sort key: {}", name))?;
+            fields.push(SortField {
+                key,
+                descending: key.default_descending() != reversed,
+            });
+        }
+
+        if fields.is_empty() {
+            return Ok(SortSpec::default());
+        }
+        Ok(SortSpec { fields })
+    }
+
+    pub fn primary(&self) -> SortKey {
+        self.fields.first().map(|f| f.key).unwrap_or(SortKey::Manual)
+    }
+
+    pub fn is_manual(&self) -> bool {
+        self.primary() == SortKey::Manual
+    }
+
+    pub fn compare(&self, a: &Task, b: &Task, now: DateTime<Utc>) -> Ordering {
+        for field in &self.fields {
+            // Undated tasks sort after dated ones in either direction
+            if field.key == SortKey::Due {
+                match (a.due_date.is_some(), b.due_date.is_some()) {
+                    (true, false) => return Ordering::Less,
+                    (false, true) => return Ordering::Greater,
+                    _ => {}
+                }
+            }
+
+            let ord = field.key.compare(a, b, now);
+            let ord = if field.descending { ord.reverse() } else { ord };
+            if ord != Ordering::Equal {
+                return ord;
+            }
+        }
+        a.position.cmp(&b.position)
+    }
+}
+
+impl fmt::Display for SortSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let parts: Vec<String> = self
+            .fields
+            .iter()
+            .map(|field| {
+                if field.key == SortKey::Manual {
+                    field.key.name().to_string()
+                } else {
+                    let arrow = if field.descending { "↓" } else { "↑" };
+                    format!("{}{}", field.key.name(), arrow)
+                }
+            })
+            .collect();
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+fn priority_rank(priority: Priority) -> i32 {
+    match priority {
+        Priority::Low => 1,
+        Priority::Medium => 2,
+        Priority::High => 3,
+    }
+}
+
+// Higher means more pressing. Combines priority, how close (or how far past)
+// the due date is, how long the task has been open, and its status.
+pub fn urgency(task: &Task, now: DateTime<Utc>) -> f64 {
+    if task.status.is_closed() {
+        return 0.0;
+    }
+
+    let priority = match task.priority {
+        Priority::High => 6.0,
+        Priority::Medium => 3.9,
+        Priority::Low => 1.8,
+    };
+
+    // Ramps from 0.2 two weeks out to 1.0 at the due date, capped once overdue
+    let due = task.due_date.map_or(0.0, |due| {
+        let days = (due - now).num_hours() as f64 / 24.0;
+        let factor = if days <= 0.0 {
+            1.0
+        } else if days >= 14.0 {
+            0.2
+        } else {
+            1.0 - 0.8 * days / 14.0
+        };
+        12.0 * factor
+    });
+
+    let age_days = (now - task.created_at).num_days().max(0) as f64;
+    let age = 2.0 * (age_days / 365.0).min(1.0);
+
+    let status = match task.status {
+        Status::InProgress => 4.0,
+        Status::Blocked => -5.0,
+        Status::Waiting => -3.0,
+        _ => 0.0,
+    };
+
+    priority + due + age + status
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn task(title: &str, priority: Priority, due_in_days: Option<i64>, now: DateTime<Utc>) -> Task {
+        let mut task = Task::new(title.to_string());
+        task.priority = priority;
+        task.due_date = due_in_days.map(|days| now + Duration::days(days));
+        task.created_at = now;
+        task
+    }
+
+    fn sorted<'a>(spec: &str, tasks: &'a [Task], now: DateTime<Utc>) -> Vec<&'a str> {
+        let spec = SortSpec::parse(spec).unwrap();
+        let mut refs: Vec<&Task> = tasks.iter().collect();
+        refs.sort_by(|a, b| spec.compare(a, b, now));
+        refs.iter().map(|t| t.title.as_str()).collect()
+    }
+
+    #[test]
+    fn specs_parse_with_default_directions() {
+        let spec = SortSpec::parse("Priority, -due,title").unwrap();
+        let fields: Vec<(SortKey, bool)> = spec.fields.iter().map(|f| (f.key, f.descending)).collect();
+        assert_eq!(fields, [(SortKey::Priority, true), (SortKey::Due, true), (SortKey::Title, false)]);
+        assert_eq!(spec.to_string(), "priority↓, due↓, title↑");
+
+        // Legacy names and an empty spec
+        assert_eq!(SortSpec::parse("due_date").unwrap().primary(), SortKey::Due);
+        assert!(SortSpec::parse("position").unwrap().is_manual());
+        assert_eq!(SortSpec::parse(" , ").unwrap(), SortSpec::default());
+
+        assert_eq!(SortSpec::parse("priority,size"), Err("Unknown sort key: size".to_string()));
+        assert!(SortSpec::parse("-").is_err());
+    }
+
+    #[test]
+    fn later_keys_break_ties() {
+        let now = Utc::now();
+        let tasks = [
+            task("b low", Priority::Low, None, now),
+            task("c high", Priority::High, None, now),
+            task("a high", Priority::High, None, now),
+            task("a low", Priority::Low, None, now),
+        ];
+        assert_eq!(sorted("priority,title", &tasks, now), ["a high", "c high", "a low", "b low"]);
+        assert_eq!(sorted("-priority,-title", &tasks, now), ["b low", "a low", "c high", "a high"]);
+    }
+
+    #[test]
+    fn manual_position_breaks_remaining_ties() {
+        let now = Utc::now();
+        let mut tasks = [task("second", Priority::High, None, now), task("first", Priority::High, None, now)];
+        tasks[0].position = 2;
+        tasks[1].position = 1;
+        assert_eq!(sorted("priority", &tasks, now), ["first", "second"]);
+    }
+
+    #[test]
+    fn undated_tasks_come_last_in_either_direction() {
+        let now = Utc::now();
+        let tasks = [
+            task("none", Priority::Medium, None, now),
+            task("later", Priority::Medium, Some(5), now),
+            task("sooner", Priority::Medium, Some(1), now),
+        ];
+        assert_eq!(sorted("due", &tasks, now), ["sooner", "later", "none"]);
+        assert_eq!(sorted("-due", &tasks, now), ["later", "sooner", "none"]);
+    }
+
+    #[test]
+    fn urgency_weighs_due_dates_priority_and_status() {
+        let now = Utc::now();
+        let urgency_of = |priority, due| urgency(&task("t", priority, due, now), now);
+
+        // Overdue counts as due now; the ramp flattens two weeks out
+        assert_eq!(urgency_of(Priority::Medium, Some(-3)), urgency_of(Priority::Medium, Some(0)));
+        assert!(urgency_of(Priority::Medium, Some(0)) > urgency_of(Priority::Medium, Some(7)));
+        assert!(urgency_of(Priority::Medium, Some(7)) > urgency_of(Priority::Medium, Some(14)));
+        assert_eq!(urgency_of(Priority::Medium, Some(14)), urgency_of(Priority::Medium, Some(60)));
+        // A far-off due date still beats none
+        assert!(urgency_of(Priority::Medium, Some(60)) > urgency_of(Priority::Medium, None));
+        // Being due soon outweighs priority
+        assert!(urgency_of(Priority::Low, Some(0)) > urgency_of(Priority::High, None));
+        assert!(urgency_of(Priority::High, None) > urgency_of(Priority::Medium, None));
+
+        let mut blocked = task("t", Priority::High, None, now);
+        blocked.status = Status::Blocked;
+        assert!(urgency(&blocked, now) < urgency_of(Priority::Low, None));
+        blocked.status = Status::Done;
+        assert_eq!(urgency(&blocked, now), 0.0);
+
+        let tasks = [
+            task("someday", Priority::High, None, now),
+            task("overdue", Priority::Low, Some(-2), now),
+            task("next week", Priority::Medium, Some(7), now),
+        ];
+        assert_eq!(sorted("urgency", &tasks, now), ["overdue", "next week", "someday"]);
+    }
+}
@@ -0,0 +1,134 @@
+use std::collections::BTreeMap;
+use crate::core::Task;
+
+// Lowercase a tag and drop its marker; returns None if nothing usable is left
+pub fn normalize_tag(raw: &str) -> Option<String> {
+    let tag: String = raw
+        .trim_start_matches('+')
+        .chars()
+        .filter(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '/'))
+        .collect::<String>()
+        .to_lowercase();
+
+    if tag.is_empty() {
+        None
+    } else {
+        Some(tag)
+    }
+}
+
+// Split `+tag` words out of free text. Returns the remaining text with
+// whitespace collapsed and the tags in first-seen order without duplicates.
+pub fn extract_tags(input: &str) -> (String, Vec<String>) {
+    let mut words = Vec::new();
+    let mut tags: Vec<String> = Vec::new();
+
+    for word in input.split_whitespace() {
+        if word.len() > 1 && word.starts_with('+') {
+            if let Some(tag) = normalize_tag(word) {
+                if !tags.contains(&tag) {
+                    tags.push(tag);
+                }
+                continue;
+            }
+        }
+        words.push(word);
+    }
+
+    (words.join(" "), tags)
+}
+
+// Add tags to a task, skipping ones it already has
+pub fn merge_into(task: &mut Task, tags: Vec<String>) {
+    for tag in tags {
+        if !task.tags.contains(&tag) {
+            task.tags.push(tag);
+        }
+    }
+}
+
+// Every tag in use with the number of tasks carrying it, sorted by name
+pub fn tag_counts(tasks: &[Task]) -> Vec<(String, usize)> {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for task in tasks {
+        for tag in &task.tags {
+            *counts.entry(tag.as_str()).or_default() += 1;
+        }
+    }
+    counts.into_iter().map(|(t, n)| (t.to_string(), n)).collect()
+}
+
+// Text for editing a task: the title followed by its tags as `+tag` words
+pub fn with_tags(title: &str, tags: &[String]) -> String {
+    let mut text = title.to_string();
+    for tag in tags {
+        text.push_str(" +");
+        text.push_str(tag);
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tags_are_lowercased_and_stripped_of_punctuation() {
+        assert_eq!(normalize_tag("+Work"), Some("work".to_string()));
+        assert_eq!(normalize_tag("+home/Garden"), Some("home/garden".to_string()));
+        assert_eq!(normalize_tag("+follow-up,"), Some("follow-up".to_string()));
+        assert_eq!(normalize_tag("+q3_goals!"), Some("q3_goals".to_string()));
+        assert_eq!(normalize_tag("++ÉTÉ"), Some("été".to_string()));
+        assert_eq!(normalize_tag("+2024"), Some("2024".to_string()));
+        assert_eq!(normalize_tag("+!?"), None);
+        assert_eq!(normalize_tag("+"), None);
+    }
+
+    #[test]
+    fn tags_come_out_of_text_once_in_first_seen_order() {
+        let (text, tags) = extract_tags("  Call  +Work mom +home +work +HOME ");
+        assert_eq!(text, "Call mom");
+        assert_eq!(tags, ["work", "home"]);
+    }
+
+    #[test]
+    fn only_plus_words_with_something_usable_are_tags() {
+        // `#` isn't the tag marker, numbers are fine, and lone or
+        // punctuation-only markers and inner pluses stay in the text
+        let (text, tags) = extract_tags("Fix #123 and #ui +123 c++ a+b + +!! +Ops.");
+        assert_eq!(text, "Fix #123 and #ui c++ a+b + +!!");
+        assert_eq!(tags, ["123", "ops"]);
+
+        let (text, tags) = extract_tags("");
+        assert_eq!(text, "");
+        assert!(tags.is_empty());
+    }
+
+    #[test]
+    fn merging_keeps_existing_tags_and_skips_duplicates() {
+        let mut task = Task::new("Call mom".to_string());
+        task.tags = vec!["home".to_string()];
+        merge_into(&mut task, vec!["work".to_string(), "home".to_string(), "work".to_string()]);
+        assert_eq!(task.tags, ["home", "work"]);
+
+        let (_, tags) = extract_tags("+Home +Errands");
+        merge_into(&mut task, tags);
+        assert_eq!(task.tags, ["home", "work", "errands"]);
+    }
+
+    #[test]
+    fn counts_and_edit_text_round_trip() {
+        let mut a = Task::new("a".to_string());
+        a.tags = vec!["work".to_string(), "home".to_string()];
+        let mut b = Task::new("b".to_string());
+        b.tags = vec!["work".to_string()];
+        assert_eq!(
+            tag_counts(&[a.clone(), b]),
+            [("home".to_string(), 1), ("work".to_string(), 2)]
+        );
+
+        let text = with_tags(&a.title, &a.tags);
+        assert_eq!(text, "a +work +home");
+        assert_eq!(extract_tags(&text), ("a".to_string(), a.tags));
+    }
+}
@@ -0,0 +1,121 @@
+use native_tls::TlsConnector;
+use postgres_native_tls::MakeTlsConnector;
+use tokio_postgres::{Client, NoTls};
+use log::{error, info};
+use crate::core::error::{AppError, Result};
+
+// SQL migrations compiled into the binary, in the order they must run. Each
+// one is applied in its own transaction and recorded in schema_migrations.
+pub struct Migration {
+    pub version: i32,
+    pub name: &'static str,
+    pub sql: &'static str,
+}
+
+macro_rules! migration {
+    ($version:expr, $name:literal) => {
+        Migration {
+            version: $version,
+            name: $name,
+            sql: include_str!(concat!("../../migrations/", $name, ".sql")),
+        }
+    };
+}
+
+pub const MIGRATIONS: &[Migration] = &[
+    migration!(1, "0001_initial_schema"),
+    migration!(2, "0002_task_status"),
+    migration!(3, "0003_tag_functions"),
+    migration!(4, "0004_projects"),
+    migration!(5, "0005_sharing"),
+    migration!(6, "0006_activity"),
+    migration!(7, "0007_task_estimate"),
+    migration!(8, "0008_subtasks"),
+    migration!(9, "0009_update_checks"),
+    migration!(10, "0010_activity_deletes"),
+    migration!(11, "0011_users_access"),
+    migration!(12, "0012_table_grants"),
+    migration!(13, "0013_deletion_trigger"),
+    migration!(14, "0014_task_recurrence"),
+];
+
+// Schema version this build reads and writes
+pub fn latest() -> i32 {
+    MIGRATIONS.last().map_or(0, |m| m.version)
+}
+
+// Connect with TLS, falling back to plain TCP for `sslmode=disable` URLs
+// such as a local Postgres
+async fn connect(database_url: &str) -> Result<Client> {
+    let client = if database_url.contains("sslmode=disable") {
+        let (client, connection) = tokio_postgres::connect(database_url, NoTls).await?;
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                error!("Postgres connection error: {}", e);
+            }
+        });
+        client
+    } else {
+        let tls = MakeTlsConnector::new(TlsConnector::new()?);
+        let (client, connection) = tokio_postgres::connect(database_url, tls).await?;
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                error!("Postgres connection error: {}", e);
+            }
+        });
+        client
+    };
+    Ok(client)
+}
+
+async fn applied_versions(client: &Client) -> Result<Vec<i32>> {
+    client
+        .batch_execute(
+            "CREATE TABLE IF NOT EXISTS schema_migrations (
+                version INTEGER PRIMARY KEY,
+                name TEXT NOT NULL,
+                applied_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            )",
+        )
+        .await?;
+
+    let rows = client.query("SELECT version FROM schema_migrations ORDER BY version", &[]).await?;
+    Ok(rows.iter().map(|row| row.get(0)).collect())
+}
+
+// Migrations not yet recorded in the database
+pub async fn pending(database_url: &str) -> Result<Vec<&'static Migration>> {
+    let client = connect(database_url).await?;
+    let applied = applied_versions(&client).await?;
+    Ok(MIGRATIONS.iter().filter(|m| !applied.contains(&m.version)).collect())
+}
+
+// Apply every pending migration and return the ones that ran. Stops at the
+// first failure; migrations applied before it stay applied.
+pub async fn migrate(database_url: &str) -> Result<Vec<&'static Migration>> {
+    let mut client = connect(database_url).await?;
+    let applied = applied_versions(&client).await?;
+
+    let mut ran = Vec::new();
+    for migration in MIGRATIONS.iter().filter(|m| !applied.contains(&m.version)) {
+        info!("Applying migration {}", migration.name);
+        let tx = client.transaction().await?;
+        if let Err(e) = tx.batch_execute(migration.sql).await {
+            let detail = e.as_db_error().map_or_else(|| e.to_string(), |db| db.to_string());
+            return Err(AppError::Storage(format!("Migration {} failed: {}", migration.name, detail)));
+        }
+        tx.execute(
+            "INSERT INTO schema_migrations (version, name) VALUES ($1, $2)",
+            &[&migration.version, &migration.name],
+        )
+        .await?;
+        tx.commit().await?;
+        ran.push(migration);
+    }
+
+    if !ran.is_empty() {
+        // Let PostgREST pick up new tables, columns and functions
+        client.batch_execute("NOTIFY pgrst, 'reload schema'").await?;
+    }
+    Ok(ran)
+}
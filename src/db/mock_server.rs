@@ -0,0 +1,527 @@
+// In-process stand-in for the Supabase REST (PostgREST) and Auth (GoTrue)
+// endpoints, implementing only what SupabaseClient sends: eq/in/or filters,
+// order, select, `Prefer: return=representation` and
+// `resolution=merge-duplicates`, the RPC functions from migrations/ and
+// password sign-in/sign-up. Rows are plain JSON objects kept in memory.
+
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::{HeaderMap, Method, StatusCode, Uri};
+use axum::response::{IntoResponse, Response};
+use axum::{Json, Router};
+use serde_json::{json, Map, Value};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use chrono::{DateTime, Duration, Months, Utc};
+use uuid::Uuid;
+use crate::config::NetworkSettings;
+use crate::core::recurrence::{Recurrence, Unit};
+
+pub const API_KEY: &str = "test-anon-key";
+
+// Columns that identify a row for upserts, per table
+fn primary_key(table: &str) -> &'static [&'static str] {
+    match table {
+        "project_members" => &["project_id", "user_id"],
+        _ => &["id"],
+    }
+}
+
+// Quick retries so failure tests don't wait on real backoff, and no circuit
+// breaker unless a test asks for one
+pub fn settings() -> NetworkSettings {
+    NetworkSettings {
+        retry_base_ms: 1,
+        retry_max_ms: 20,
+        max_retries: 2,
+        failure_threshold: 0,
+        ..NetworkSettings::default()
+    }
+}
+
+#[derive(Default)]
+struct Store {
+    tables: HashMap<String, Vec<Value>>,
+    // GoTrue accounts: email -> (password, auth user id)
+    accounts: HashMap<String, (String, String)>,
+    schema_version: Option<i64>,
+    // Responses to return instead of handling the next requests, with an
+    // optional Retry-After in seconds
+    failures: Vec<(StatusCode, String, Option<u64>)>,
+    requests: Vec<String>,
+}
+
+#[derive(Clone)]
+pub struct MockServer {
+    store: Arc<Mutex<Store>>,
+    pub base_url: String,
+}
+
+impl MockServer {
+    pub async fn start() -> MockServer {
+        let store = Arc::new(Mutex::new(Store {
+            schema_version: Some(crate::db::migrations::latest() as i64),
+            ..Store::default()
+        }));
+        let app = Router::new().fallback(handle).with_state(store.clone());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        MockServer { store, base_url }
+    }
+
+    pub fn client(&self) -> crate::db::SupabaseClient {
+        crate::db::SupabaseClient::new(self.base_url.clone(), API_KEY.to_string(), &settings())
+    }
+
+    pub fn rows(&self, table: &str) -> Vec<Value> {
+        self.store.lock().unwrap().tables.get(table).cloned().unwrap_or_default()
+    }
+
+    pub fn insert(&self, table: &str, row: Value) {
+        let mut store = self.store.lock().unwrap();
+        store.tables.entry(table.to_string()).or_default().push(with_defaults(row));
+    }
+
+    // Make the next request fail with `status` and `body`
+    pub fn fail_next(&self, status: StatusCode, body: &str) {
+        self.store.lock().unwrap().failures.push((status, body.to_string(), None));
+    }
+
+    pub fn fail_next_with_retry_after(&self, status: StatusCode, seconds: u64) {
+        self.store.lock().unwrap().failures.push((status, "slow down".to_string(), Some(seconds)));
+    }
+
+    // None makes rpc/schema_version 404, like a database without migration 0001
+    pub fn set_schema_version(&self, version: Option<i64>) {
+        self.store.lock().unwrap().schema_version = version;
+    }
+
+    // "METHOD /path" of every request received, in order
+    pub fn requests(&self) -> Vec<String> {
+        self.store.lock().unwrap().requests.clone()
+    }
+}
+
+async fn handle(
+    State(store): State<Arc<Mutex<Store>>>,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let mut store = store.lock().unwrap();
+    store.requests.push(format!("{} {}", method, uri.path()));
+
+    if headers.get("apikey").and_then(|v| v.to_str().ok()) != Some(API_KEY) {
+        return error(StatusCode::UNAUTHORIZED, "Invalid API key");
+    }
+    if !store.failures.is_empty() {
+        let (status, message, retry_after) = store.failures.remove(0);
+        let mut response = error(status, &message);
+        if let Some(seconds) = retry_after {
+            response.headers_mut().insert("retry-after", seconds.into());
+        }
+        return response;
+    }
+
+    let query: Vec<(String, String)> = serde_urlencoded::from_str(uri.query().unwrap_or("")).unwrap_or_default();
+    let body: Value = if body.is_empty() {
+        Value::Null
+    } else {
+        match serde_json::from_slice(&body) {
+            Ok(value) => value,
+            Err(e) => return error(StatusCode::BAD_REQUEST, &e.to_string()),
+        }
+    };
+    let prefer = headers.get("prefer").and_then(|v| v.to_str().ok()).unwrap_or("").to_string();
+
+    // The signed-in user, as auth.uid() sees them
+    let caller = headers
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer token-"))
+        .map(str::to_string);
+
+    let path = uri.path();
+    if let Some(action) = path.strip_prefix("/auth/v1/") {
+        return auth(&mut store, action, &query, &body);
+    }
+    if let Some(function) = path.strip_prefix("/rest/v1/rpc/") {
+        return rpc(&mut store, function, &body, caller);
+    }
+    let Some(table) = path.strip_prefix("/rest/v1/") else {
+        return error(StatusCode::NOT_FOUND, "Not found");
+    };
+    if table == "users" {
+        if let Some(denied) = users_access(&method, &query, &body, caller.as_deref()) {
+            return denied;
+        }
+    }
+    if table == "task_activity" && method == Method::POST && body["action"] == "deleted" {
+        return error(StatusCode::UNAUTHORIZED, "new row violates row-level security policy for table \"task_activity\"");
+    }
+
+    let filters: Vec<(String, String)> = query
+        .iter()
+        .filter(|(k, _)| k != "order" && k != "select")
+        .cloned()
+        .collect();
+    let rows = store.tables.entry(table.to_string()).or_default();
+
+    match method {
+        Method::GET => {
+            let mut found: Vec<Value> = rows.iter().filter(|r| matches_all(r, &filters)).cloned().collect();
+            if let Some((_, order)) = query.iter().find(|(k, _)| k == "order") {
+                sort_rows(&mut found, order);
+            }
+            if let Some((_, select)) = query.iter().find(|(k, _)| k == "select") {
+                found = found.into_iter().map(|r| project(r, select)).collect();
+            }
+            Json(found).into_response()
+        }
+        Method::POST => {
+            let new_rows = match body {
+                Value::Array(items) => items,
+                other => vec![other],
+            };
+            let merge = prefer.contains("resolution=merge-duplicates");
+            let key = primary_key(table);
+            let mut written = Vec::new();
+            for row in new_rows {
+                let row = with_defaults(row);
+                let existing = rows.iter().position(|r| key.iter().all(|k| r.get(k) == row.get(k)));
+                match existing {
+                    Some(i) if merge => {
+                        merge_into(&mut rows[i], &row);
+                        written.push(rows[i].clone());
+                    }
+                    Some(_) => return error(StatusCode::CONFLICT, "duplicate key value violates unique constraint"),
+                    None => {
+                        rows.push(row.clone());
+                        written.push(row);
+                    }
+                }
+            }
+            created(&prefer, written)
+        }
+        Method::PATCH => {
+            let mut written = Vec::new();
+            let mut repeats = Vec::new();
+            for row in rows.iter_mut().filter(|r| matches_all(r, &filters)) {
+                let was_done = row["status"] == "done";
+                merge_into(row, &body);
+                // The tasks_repeat_completed trigger from
+                // migrations/0014_task_recurrence.sql
+                if table == "tasks" && !was_done && row["status"] == "done" && !row["recurrence"].is_null() {
+                    repeats.push(next_occurrence(row));
+                    row["recurrence"] = Value::Null;
+                }
+                written.push(row.clone());
+            }
+            let mut created_ids = Vec::new();
+            for mut next in repeats {
+                let last = rows
+                    .iter()
+                    .filter(|r| r["user_id"] == next["user_id"])
+                    .filter_map(|r| r["position"].as_i64())
+                    .max();
+                next["position"] = json!(last.map_or(0, |p| p + 1));
+                created_ids.push(next["id"].clone());
+                rows.push(next);
+            }
+            let log = store.tables.entry("task_activity".to_string()).or_default();
+            for id in created_ids {
+                log.push(with_defaults(json!({
+                    "task_id": id,
+                    "user_id": caller,
+                    "action": "created",
+                    "detail": null,
+                })));
+            }
+            returned(&prefer, written)
+        }
+        Method::DELETE => {
+            let (removed, kept): (Vec<Value>, Vec<Value>) =
+                rows.drain(..).partition(|r| matches_all(r, &filters));
+            *rows = kept;
+            // The tasks_log_deletion trigger from migrations/0013_deletion_trigger.sql
+            if table == "tasks" {
+                let log = store.tables.entry("task_activity".to_string()).or_default();
+                for task in &removed {
+                    log.push(with_defaults(json!({
+                        "task_id": task["id"],
+                        "user_id": caller,
+                        "action": "deleted",
+                        "detail": task["title"],
+                    })));
+                }
+            }
+            returned(&prefer, removed)
+        }
+        _ => error(StatusCode::METHOD_NOT_ALLOWED, "Method not allowed"),
+    }
+}
+
+fn auth(store: &mut Store, action: &str, query: &[(String, String)], body: &Value) -> Response {
+    let email = body["email"].as_str().unwrap_or_default().to_string();
+    let password = body["password"].as_str().unwrap_or_default().to_string();
+
+    match action {
+        "signup" => {
+            if store.accounts.contains_key(&email) {
+                return error(StatusCode::UNPROCESSABLE_ENTITY, "User already registered");
+            }
+            let id = Uuid::new_v4().to_string();
+            store.accounts.insert(email.clone(), (password, id.clone()));
+            Json(json!({ "id": id, "email": email })).into_response()
+        }
+        "token" if query.iter().any(|(k, v)| k == "grant_type" && v == "password") => {
+            match store.accounts.get(&email) {
+                Some((expected, id)) if *expected == password => Json(json!({
+                    "access_token": format!("token-{}", id),
+                    "token_type": "bearer",
+                    "user": { "id": id, "email": email },
+                }))
+                .into_response(),
+                _ => error(StatusCode::BAD_REQUEST, "Invalid login credentials"),
+            }
+        }
+        _ => error(StatusCode::NOT_FOUND, "Not found"),
+    }
+}
+
+// The column grants and policies of migrations/0011_users_access.sql: only
+// id and username are readable, and a user row is created by its own user
+fn users_access(method: &Method, query: &[(String, String)], body: &Value, caller: Option<&str>) -> Option<Response> {
+    let readable = ["id", "username"];
+    match *method {
+        Method::GET => {
+            let select = query.iter().find(|(k, _)| k == "select").map_or("*", |(_, v)| v.as_str());
+            if select.split(',').any(|column| !readable.contains(&column.trim())) {
+                return Some(error(StatusCode::UNAUTHORIZED, "permission denied for table users"));
+            }
+            None
+        }
+        Method::POST if caller.is_some() && body["id"].as_str() == caller => None,
+        _ => Some(error(
+            StatusCode::UNAUTHORIZED,
+            "new row violates row-level security policy for table \"users\"",
+        )),
+    }
+}
+
+fn rpc(store: &mut Store, function: &str, body: &Value, caller: Option<String>) -> Response {
+    match function {
+        "schema_version" => match store.schema_version {
+            Some(version) => Json(json!(version)).into_response(),
+            None => error(StatusCode::NOT_FOUND, "Could not find the function public.schema_version"),
+        },
+        // Same semantics as migrations/0009_update_checks.sql
+        "rename_tag" => {
+            let Some(user_id) = caller.map(Value::String) else {
+                return Json(json!(0)).into_response();
+            };
+            let (old, new) = (body["p_old"].clone(), body["p_new"].clone());
+            let mut changed = 0;
+            for task in store.tables.entry("tasks".to_string()).or_default() {
+                let Some(tags) = task["tags"].as_array().cloned() else {
+                    continue;
+                };
+                if task["user_id"] != user_id || !tags.contains(&old) {
+                    continue;
+                }
+                let mut renamed: Vec<Value> = Vec::new();
+                for tag in tags {
+                    let tag = if tag == old { new.clone() } else { tag };
+                    if !renamed.contains(&tag) {
+                        renamed.push(tag);
+                    }
+                }
+                task["tags"] = Value::Array(renamed);
+                changed += 1;
+            }
+            Json(json!(changed)).into_response()
+        }
+        // Same semantics as migrations/0011_users_access.sql
+        "verify_login" => {
+            let password = body["p_password"].as_str().unwrap_or_default();
+            let users = store.tables.get("users").map(Vec::as_slice).unwrap_or_default();
+            let found: Vec<Value> = users
+                .iter()
+                .filter(|u| u["username"] == body["p_username"])
+                .filter(|u| bcrypt::verify(password, u["password_hash"].as_str().unwrap_or_default()).unwrap_or(false))
+                .map(|u| project(u.clone(), "id,username,email,created_at"))
+                .collect();
+            Json(found).into_response()
+        }
+        _ => error(StatusCode::NOT_FOUND, "Could not find the function"),
+    }
+}
+
+fn error(status: StatusCode, message: &str) -> Response {
+    (status, Json(json!({ "message": message }))).into_response()
+}
+
+fn created(prefer: &str, rows: Vec<Value>) -> Response {
+    if prefer.contains("return=representation") {
+        (StatusCode::CREATED, Json(rows)).into_response()
+    } else {
+        StatusCode::CREATED.into_response()
+    }
+}
+
+fn returned(prefer: &str, rows: Vec<Value>) -> Response {
+    if prefer.contains("return=representation") {
+        Json(rows).into_response()
+    } else {
+        StatusCode::NO_CONTENT.into_response()
+    }
+}
+
+// Column defaults the real schema fills in on insert
+fn with_defaults(mut row: Value) -> Value {
+    if let Some(object) = row.as_object_mut() {
+        object.entry("id").or_insert_with(|| json!(Uuid::new_v4().to_string()));
+        object.entry("created_at").or_insert_with(|| json!(chrono::Utc::now().to_rfc3339()));
+    }
+    row
+}
+
+// A todo copy of a completed repeating task, due one interval later
+fn next_occurrence(task: &Value) -> Value {
+    let recurrence = Recurrence::parse(task["recurrence"].as_str().unwrap_or_default()).expect("valid recurrence");
+    let due = task["due_date"]
+        .as_str()
+        .and_then(|d| DateTime::parse_from_rfc3339(d).ok())
+        .map_or_else(Utc::now, |d| d.with_timezone(&Utc));
+    let next_due = match recurrence.unit {
+        Unit::Day => due + Duration::days(recurrence.every as i64),
+        Unit::Week => due + Duration::weeks(recurrence.every as i64),
+        Unit::Month => due + Months::new(recurrence.every),
+        Unit::Year => due + Months::new(recurrence.every * 12),
+    };
+
+    let mut next = task.clone();
+    next["id"] = json!(Uuid::new_v4().to_string());
+    next["status"] = json!("todo");
+    next["done"] = json!(false);
+    next["due_date"] = json!(next_due.to_rfc3339());
+    next["created_at"] = json!(Utc::now().to_rfc3339());
+    next["updated_at"] = next["created_at"].clone();
+    next
+}
+
+fn merge_into(row: &mut Value, update: &Value) {
+    if let (Some(row), Some(update)) = (row.as_object_mut(), update.as_object()) {
+        for (k, v) in update {
+            row.insert(k.clone(), v.clone());
+        }
+    }
+}
+
+fn project(row: Value, select: &str) -> Value {
+    if select == "*" {
+        return row;
+    }
+    let mut out = Map::new();
+    for column in select.split(',') {
+        if let Some(v) = row.get(column) {
+            out.insert(column.to_string(), v.clone());
+        }
+    }
+    Value::Object(out)
+}
+
+fn matches_all(row: &Value, filters: &[(String, String)]) -> bool {
+    filters.iter().all(|(column, expr)| {
+        if column == "or" {
+            let inner = expr.trim_start_matches('(').trim_end_matches(')');
+            split_top_level(inner).iter().any(|cond| {
+                // column.op.value
+                let (column, expr) = cond.split_once('.').unwrap_or((cond, ""));
+                matches(row, column, expr)
+            })
+        } else {
+            matches(row, column, expr)
+        }
+    })
+}
+
+// `expr` is "op.value", e.g. "eq.abc" or "in.(a,b)"
+fn matches(row: &Value, column: &str, expr: &str) -> bool {
+    let value = row.get(column).map(as_text);
+    match expr.split_once('.') {
+        Some(("eq", wanted)) => value.as_deref() == Some(wanted),
+        Some(("neq", wanted)) => value.as_deref() != Some(wanted),
+        Some(("is", "null")) => value.is_none() || row[column].is_null(),
+        Some(("in", list)) => {
+            let list = list.trim_start_matches('(').trim_end_matches(')');
+            value.is_some_and(|v| list.split(',').any(|item| item == v))
+        }
+        _ => false,
+    }
+}
+
+fn as_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => "null".to_string(),
+        other => other.to_string(),
+    }
+}
+
+// Split on commas that are not inside parentheses
+fn split_top_level(s: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut depth, mut start) = (0, 0);
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(&s[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+fn sort_rows(rows: &mut [Value], order: &str) {
+    let keys: Vec<(&str, bool)> = order
+        .split(',')
+        .map(|part| match part.rsplit_once('.') {
+            Some((column, "desc")) => (column, true),
+            Some((column, "asc")) => (column, false),
+            _ => (part, false),
+        })
+        .collect();
+
+    rows.sort_by(|a, b| {
+        for (column, descending) in &keys {
+            let ord = compare_values(&a[*column], &b[*column]);
+            let ord = if *descending { ord.reverse() } else { ord };
+            if ord != Ordering::Equal {
+                return ord;
+            }
+        }
+        Ordering::Equal
+    });
+}
+
+fn compare_values(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => {
+            x.as_f64().unwrap_or(0.0).total_cmp(&y.as_f64().unwrap_or(0.0))
+        }
+        _ => as_text(a).cmp(&as_text(b)),
+    }
+}
@@ -1,15 +1,28 @@
-use reqwest::Client;
+use reqwest::{Method, RequestBuilder, Response};
+use std::collections::HashMap;
+use std::sync::RwLock;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use crate::core::{Task, Priority};
+use crate::core::{Task, Priority, Project, Role, Status};
+use crate::core::activity::{self, Action, Activity};
+use crate::core::recurrence::Recurrence;
+use crate::config::NetworkSettings;
+use crate::core::error::{AppError, Result};
+use crate::net::HttpClient;
 use chrono::{DateTime, Utc};
+use log::warn;
+
+pub mod migrations;
+#[cfg(test)]
+mod mock_server;
+#[cfg(test)]
+mod tests;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SupabaseUser {
     pub id: String,
     pub username: String,
     pub email: String,
-    pub password_hash: String,
     pub created_at: String,
 }
 
@@ -20,88 +33,310 @@ pub struct SupabaseTask {
     pub title: String,
     pub description: String,
     pub done: bool,
+    // Added in migration 0002; rows written by older clients may lack it
+    #[serde(default)]
+    pub status: Option<String>,
     pub priority: i32,
     pub due_date: Option<String>,
     pub tags: Vec<String>,
     pub created_at: String,
     pub updated_at: String,
     pub position: i32,
+    #[serde(default)]
+    pub project_id: Option<String>,
+    #[serde(default)]
+    pub assignee_id: Option<String>,
+    #[serde(default)]
+    pub estimate_minutes: Option<i32>,
+    #[serde(default)]
+    pub parent_id: Option<String>,
+    #[serde(default)]
+    pub recurrence: Option<String>,
+}
+
+// A timestamp column as PostgREST returns it
+fn parse_timestamp(column: &str, value: &str) -> Result<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&Utc))
+        .map_err(|e| AppError::Serialization(format!("invalid {} {:?}: {}", column, value, e)))
+}
+
+impl TryFrom<SupabaseTask> for Task {
+    type Error = AppError;
+
+    fn try_from(st: SupabaseTask) -> Result<Self> {
+        Ok(Task {
+            id: st.id,
+            title: st.title,
+            description: st.description,
+            status: st
+                .status
+                .as_deref()
+                .and_then(Status::parse)
+                .unwrap_or(if st.done { Status::Done } else { Status::Todo }),
+            priority: match st.priority {
+                1 => Priority::Low,
+                3 => Priority::High,
+                _ => Priority::Medium,
+            },
+            due_date: st.due_date.map(|d| parse_timestamp("due_date", &d)).transpose()?,
+            tags: st.tags,
+            created_at: parse_timestamp("created_at", &st.created_at)?,
+            updated_at: parse_timestamp("updated_at", &st.updated_at)?,
+            position: st.position,  // Add this
+            project_id: st.project_id,
+            assignee_id: st.assignee_id,
+            estimate_minutes: st.estimate_minutes.and_then(|m| u32::try_from(m).ok()),
+            parent_id: st.parent_id,
+            recurrence: st
+                .recurrence
+                .map(|r| {
+                    Recurrence::parse(&r)
+                        .ok_or_else(|| AppError::Serialization(format!("invalid recurrence {:?}", r)))
+                })
+                .transpose()?,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AuthSession {
+    access_token: String,
+    user: AuthUser,
+}
+
+#[derive(Debug, Deserialize)]
+struct AuthUser {
+    id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectMember {
+    pub project_id: String,
+    pub user_id: String,
+    pub role: Role,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UserSummary {
+    pub id: String,
+    pub username: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SupabaseProject {
+    pub id: String,
+    pub user_id: String,
+    pub name: String,
+    pub color: Option<String>,
+    pub position: i32,
+    pub created_at: String,
+}
+
+// Pass through a success response; turn anything else into an AppError
+// carrying the status and the server's message
+async fn check(response: Response, context: &str) -> Result<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+    Err(AppError::from_response(context, status.as_u16(), body))
+}
+
+// PATCH responses requested with `return=representation`. Row-level security
+// hides rows the user can't change, so an update that matched nothing means
+// the task is gone or read-only.
+async fn expect_rows(response: Response) -> Result<()> {
+    let updated: Vec<serde_json::Value> = response.json().await?;
+    if updated.is_empty() {
+        return Err(AppError::NotFound("Task was deleted or you can't edit it".to_string()));
+    }
+    Ok(())
+}
+
+// GoTrue answers bad credentials and unconfirmed emails with a 4xx other
+// than 401; those are login errors, not server trouble
+async fn auth_response(response: Response, context: &str) -> Result<Response> {
+    match check(response, context).await {
+        Err(AppError::Http { context, status, body }) if status < 500 && status != 408 && status != 429 => {
+            Err(AppError::Auth(format!("{}: {}", context, body)))
+        }
+        result => result,
+    }
 }
 
 pub struct SupabaseClient {
-    client: Client,
+    http: HttpClient,
     base_url: String,
     api_key: String,
+    // Supabase Auth session for the logged-in user. Row-level security
+    // policies identify the user from this token; without one requests run
+    // as the anonymous role.
+    access_token: RwLock<Option<String>>,
+    // users.id of the logged-in user, recorded as the actor in task_activity
+    actor: RwLock<Option<String>>,
 }
 
 impl SupabaseClient {
-    pub fn new(base_url: String, api_key: String) -> Self {
+    pub fn new(base_url: String, api_key: String, settings: &NetworkSettings) -> Self {
         Self {
-            client: Client::new(),
+            http: HttpClient::new(settings, settings.request_timeout_secs),
             base_url,
             api_key,
+            access_token: RwLock::new(None),
+            actor: RwLock::new(None),
         }
     }
 
+    // PostgREST request with the api key and, once logged in, the user's token.
+    // Our PATCHes set absolute values and upserts merge duplicates, so those
+    // go through send_idempotent and are retried like GETs.
+    fn rest(&self, method: Method, path: &str) -> RequestBuilder {
+        let token = self.access_token.read().ok().and_then(|t| t.clone());
+        self.http
+            .request(method, format!("{}/rest/v1/{}", self.base_url, path))
+            .header("apikey", &self.api_key)
+            .bearer_auth(token.as_deref().unwrap_or(&self.api_key))
+    }
+
+    fn auth(&self, path: &str) -> RequestBuilder {
+        self.http
+            .post(format!("{}/auth/v1/{}", self.base_url, path))
+            .header("apikey", &self.api_key)
+            .header("Content-Type", "application/json")
+    }
+
+    // Sign in to Supabase Auth so later requests carry the user's identity,
+    // returning the Auth user id. Row-level security (migration 0005) hides
+    // every task from requests without a session, so deployments with only
+    // the anon key and no Supabase Auth are not supported.
+    async fn start_session(&self, email: &str, password: &str) -> Result<String> {
+        let request = self
+            .auth("token?grant_type=password")
+            .json(&json!({ "email": email, "password": password }));
+        let response = self.http.send_idempotent(request).await?;
+
+        let response = auth_response(response, "Supabase Auth sign-in failed").await?;
+
+        let session: AuthSession = response.json().await?;
+        if let Ok(mut token) = self.access_token.write() {
+            *token = Some(session.access_token);
+        }
+        Ok(session.user.id)
+    }
+
+    fn set_actor(&self, user_id: &str) {
+        if let Ok(mut actor) = self.actor.write() {
+            *actor = Some(user_id.to_string());
+        }
+    }
+
+    // Set after repeated failures; requests fail fast until the server is tried again
+    pub fn is_offline(&self) -> bool {
+        self.http.is_offline()
+    }
+
+    // Highest migration applied to the database (see migrations::MIGRATIONS),
+    // or None when it predates versioned migrations
+    pub async fn schema_version(&self) -> Result<Option<i32>> {
+        let request = self
+            .rest(Method::POST, "rpc/schema_version")
+            .header("Content-Type", "application/json")
+            .json(&json!({}));
+        let response = self.http.send_idempotent(request).await?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let response = check(response, "Failed to read schema version").await?;
+
+        Ok(Some(response.json().await?))
+    }
+
     // Register new user
-    pub async fn register(&self, username: &str, email: &str, password: &str) -> Result<SupabaseUser, Box<dyn std::error::Error>> {
+    pub async fn register(&self, username: &str, email: &str, password: &str) -> Result<SupabaseUser> {
         let password_hash = bcrypt::hash(password, bcrypt::DEFAULT_COST)?;
-        
+
+        // Reuse the Supabase Auth id so RLS policies (auth.uid()) match
+        // users.id; the users policies only let the new Auth user create
+        // their own row
+        self.sign_up(email, password).await?;
+        let auth_id = self.start_session(email, password).await?;
+
         let user = json!({
+            "id": auth_id,
             "username": username,
             "email": email,
             "password_hash": password_hash,
         });
 
-        let response = self.client
-            .post(format!("{}/rest/v1/users", self.base_url))
-            .header("apikey", &self.api_key)
+        let request = self
+            .rest(Method::POST, "users")
             .header("Content-Type", "application/json")
-            .header("Prefer", "return=representation")
-            .json(&user)
-            .send()
-            .await?;
+            .json(&user);
+        let response = self.http.send(request).await?;
 
-        if !response.status().is_success() {
-            let error_text = response.text().await?;
-            return Err(format!("Registration failed: {}", error_text).into());
-        }
+        check(response, "Registration failed").await?;
 
-        let users: Vec<SupabaseUser> = response.json().await?;
-        users.into_iter().next().ok_or("No user returned".into())
+        // Only id and username can be read back directly
+        self.login(username, password).await
     }
 
-    // Login user
-    pub async fn login(&self, username: &str, password: &str) -> Result<SupabaseUser, Box<dyn std::error::Error>> {
-        let response = self.client
-            .get(format!("{}/rest/v1/users", self.base_url))
-            .header("apikey", &self.api_key)
-            .query(&[("username", format!("eq.{}", username))])
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            return Err("User not found".into());
+    // An Auth account left behind by a registration that couldn't sign in
+    // (say, before the email address was confirmed) is reused; signing in
+    // afterwards checks it belongs to this password
+    async fn sign_up(&self, email: &str, password: &str) -> Result<()> {
+        let request = self
+            .auth("signup")
+            .json(&json!({ "email": email, "password": password }));
+        let response = self.http.send(request).await?;
+
+        if response.status() == reqwest::StatusCode::UNPROCESSABLE_ENTITY {
+            return Ok(());
         }
+        auth_response(response, "Supabase Auth sign-up failed").await?;
+        Ok(())
+    }
 
-        let users: Vec<SupabaseUser> = response.json().await?;
-        let user = users.into_iter().next().ok_or("User not found")?;
+    // Login user. The database checks the password (migration 0011), so the
+    // hash is never sent to clients.
+    pub async fn login(&self, username: &str, password: &str) -> Result<SupabaseUser> {
+        let request = self
+            .rest(Method::POST, "rpc/verify_login")
+            .header("Content-Type", "application/json")
+            .json(&json!({ "p_username": username, "p_password": password }));
+        let response = self.http.send_idempotent(request).await?;
+
+        let response = check(response, "Failed to log in").await?;
 
-        if bcrypt::verify(password, &user.password_hash)? {
-            Ok(user)
-        } else {
-            Err("Invalid password".into())
+        let users: Vec<SupabaseUser> = response.json().await?;
+        let user = users
+            .into_iter()
+            .next()
+            .ok_or_else(|| AppError::Auth("Invalid username or password".to_string()))?;
+
+        let auth_id = self.start_session(&user.email, password).await?;
+        if auth_id != user.id {
+            // Row-level security would hide all of the user's tasks
+            return Err(AppError::Auth(format!(
+                "The Supabase Auth account of {} doesn't match users.id",
+                user.username
+            )));
         }
+        self.set_actor(&user.id);
+        Ok(user)
     }
 
     // Create task
-    pub async fn create_task(&self, task: &Task, user_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+    pub async fn create_task(&self, task: &Task, user_id: &str) -> Result<()> {
         let supabase_task = json!({
             "id": task.id,
             "user_id": user_id,
             "title": task.title,
             "description": task.description,
-            "done": task.done,
+            "status": task.status.as_str(),
+            "done": task.is_done(),
             "priority": match task.priority {
                 Priority::Low => 1,
                 Priority::Medium => 2,
@@ -112,68 +347,57 @@ impl SupabaseClient {
             "created_at": task.created_at.to_rfc3339(),
             "updated_at": task.updated_at.to_rfc3339(),
             "position": task.position,  // Add this
+            "project_id": task.project_id,
+            "assignee_id": task.assignee_id,
+            "estimate_minutes": task.estimate_minutes,
+            "parent_id": task.parent_id,
+            "recurrence": task.recurrence.map(|r| r.interval()),
         });
 
-        let response = self.client
-            .post(format!("{}/rest/v1/tasks", self.base_url))
-            .header("apikey", &self.api_key)
+        let request = self
+            .rest(Method::POST, "tasks")
             .header("Content-Type", "application/json")
-            .json(&supabase_task)
-            .send()
-            .await?;
+            .json(&supabase_task);
+        let response = self.http.send(request).await?;
 
-        if !response.status().is_success() {
-            let error_text = response.text().await?;
-            return Err(format!("Failed to create task: {}", error_text).into());
-        }
+        check(response, "Failed to create task").await?;
 
+        self.record(&task.id, Action::Created, None).await;
         Ok(())
     }
 
     // Get all tasks for user
-    pub async fn get_tasks(&self, user_id: &str) -> Result<Vec<Task>, Box<dyn std::error::Error>> {
-        let response = self.client
-            .get(format!("{}/rest/v1/tasks", self.base_url))
-            .header("apikey", &self.api_key)
-            .query(&[("user_id", format!("eq.{}", user_id))])
-            .query(&[("order", "position.asc")])  // Changed from created_at.desc
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            return Err("Failed to fetch tasks".into());
+    // Own tasks, tasks assigned to the user and tasks in projects shared with them
+    pub async fn get_tasks(&self, user_id: &str) -> Result<Vec<Task>> {
+        let mut visible = vec![
+            format!("user_id.eq.{}", user_id),
+            format!("assignee_id.eq.{}", user_id),
+        ];
+        let shared: Vec<String> = self.get_memberships(user_id).await?.into_iter().map(|m| m.project_id).collect();
+        if !shared.is_empty() {
+            visible.push(format!("project_id.in.({})", shared.join(",")));
         }
 
-        let supabase_tasks: Vec<SupabaseTask> = response.json().await?;
-        
-        let tasks = supabase_tasks.into_iter().map(|st| {
-            Task {
-                id: st.id,
-                title: st.title,
-                description: st.description,
-                done: st.done,
-                priority: match st.priority {
-                    1 => Priority::Low,
-                    3 => Priority::High,
-                    _ => Priority::Medium,
-                },
-                due_date: st.due_date.and_then(|d| DateTime::parse_from_rfc3339(&d).ok().map(|dt| dt.with_timezone(&Utc))),
-                tags: st.tags,
-                created_at: DateTime::parse_from_rfc3339(&st.created_at).unwrap().with_timezone(&Utc),
-                updated_at: DateTime::parse_from_rfc3339(&st.updated_at).unwrap().with_timezone(&Utc),
-                position: st.position,  // Add this
-            }
-        }).collect();
+        let request = self
+            .rest(Method::GET, "tasks")
+            .query(&[("or", format!("({})", visible.join(",")))])
+            .query(&[("order", "position.asc")]);  // Changed from created_at.desc
+        let response = self.http.send(request).await?;
+
+        let response = check(response, "Failed to fetch tasks").await?;
 
-        Ok(tasks)
+        let supabase_tasks: Vec<SupabaseTask> = response.json().await?;
+        supabase_tasks.into_iter().map(Task::try_from).collect()
     }
 
-    // Update task
-    pub async fn update_task(&self, task: &Task) -> Result<(), Box<dyn std::error::Error>> {
+    // Update task. `before` is the task as the caller last loaded it; what
+    // differs from it goes to the activity log.
+    pub async fn update_task(&self, before: &Task, task: &Task) -> Result<()> {
         let supabase_task = json!({
             "title": task.title,
             "description": task.description,
-            "done": task.done,
+            "status": task.status.as_str(),
+            "done": task.is_done(),
             "priority": match task.priority {
                 Priority::Low => 1,
                 Priority::Medium => 2,
@@ -183,82 +407,334 @@ impl SupabaseClient {
             "tags": task.tags,
             "updated_at": Utc::now().to_rfc3339(),
             "position": task.position,  // Add this
+            "project_id": task.project_id,
+            "assignee_id": task.assignee_id,
+            "estimate_minutes": task.estimate_minutes,
+            "parent_id": task.parent_id,
+            "recurrence": task.recurrence.map(|r| r.interval()),
         });
 
-        let response = self.client
-            .patch(format!("{}/rest/v1/tasks", self.base_url))
-            .header("apikey", &self.api_key)
+        let request = self
+            .rest(Method::PATCH, "tasks")
             .header("Content-Type", "application/json")
+            .header("Prefer", "return=representation")
+            .query(&[("select", "id")])
             .query(&[("id", format!("eq.{}", task.id))])
-            .json(&supabase_task)
-            .send()
-            .await?;
+            .json(&supabase_task);
+        let response = self.http.send_idempotent(request).await?;
 
-        if !response.status().is_success() {
-            let error_text = response.text().await?;
-            return Err(format!("Failed to update task: {}", error_text).into());
-        }
+        let response = check(response, "Failed to update task").await?;
+        expect_rows(response).await?;
 
+        for (action, detail) in activity::changes(before, task) {
+            self.record(&task.id, action, detail).await;
+        }
         Ok(())
     }
 
     //updating position of pre defined tasks
-    pub async fn update_positions(&self, tasks: &[Task]) -> Result<(), Box<dyn std::error::Error>> {
+    pub async fn update_positions(&self, tasks: &[Task]) -> Result<()> {
         for task in tasks {
             let update = json!({
                 "position": task.position,
                 "updated_at": Utc::now().to_rfc3339(),
             });
 
-            self.client
-                .patch(format!("{}/rest/v1/tasks", self.base_url))
-                .header("apikey", &self.api_key)
+            let request = self
+                .rest(Method::PATCH, "tasks")
                 .header("Content-Type", "application/json")
+                .header("Prefer", "return=representation")
+                .query(&[("select", "id")])
                 .query(&[("id", format!("eq.{}", task.id))])
-                .json(&update)
-                .send()
-                .await?;
+                .json(&update);
+            let response = self.http.send_idempotent(request).await?;
+            let response = check(response, "Failed to reorder tasks").await?;
+            expect_rows(response).await?;
         }
         Ok(())
     }
 
 
-    // Delete task
-    pub async fn delete_task(&self, task_id: &str) -> Result<(), Box<dyn std::error::Error>> {
-        let response = self.client
-            .delete(format!("{}/rest/v1/tasks", self.base_url))
-            .header("apikey", &self.api_key)
-            .query(&[("id", format!("eq.{}", task_id))])
-            .send()
-            .await?;
+    // Delete task. Its activity log outlives it, ending with a deletion entry
+    // the database writes (migration 0013).
+    pub async fn delete_task(&self, task_id: &str) -> Result<()> {
+        let request = self
+            .rest(Method::DELETE, "tasks")
+            .query(&[("id", format!("eq.{}", task_id))]);
+        let response = self.http.send(request).await?;
 
-        if !response.status().is_success() {
-            return Err("Failed to delete task".into());
-        }
+        check(response, "Failed to delete task").await?;
 
         Ok(())
     }
 
-    // Toggle task done status
-    pub async fn toggle_done(&self, task_id: &str, current_status: bool) -> Result<(), Box<dyn std::error::Error>> {
+    // Set task status, keeping the legacy done column in step. `from` is the
+    // status the caller last saw, for the activity log.
+    pub async fn set_status(&self, task_id: &str, from: Status, status: Status) -> Result<()> {
         let update = json!({
-            "done": !current_status,
+            "status": status.as_str(),
+            "done": status == Status::Done,
             "updated_at": Utc::now().to_rfc3339(),
         });
 
-        let response = self.client
-            .patch(format!("{}/rest/v1/tasks", self.base_url))
-            .header("apikey", &self.api_key)
+        let request = self
+            .rest(Method::PATCH, "tasks")
             .header("Content-Type", "application/json")
+            .header("Prefer", "return=representation")
+            .query(&[("select", "id")])
             .query(&[("id", format!("eq.{}", task_id))])
-            .json(&update)
-            .send()
-            .await?;
+            .json(&update);
+        let response = self.http.send_idempotent(request).await?;
+
+        let response = check(response, "Failed to update task status").await?;
+        expect_rows(response).await?;
+
+        if from != status {
+            let (action, detail) = activity::status_change(from, status);
+            self.record(task_id, action, detail).await;
+        }
+        Ok(())
+    }
+
+    // Append an entry to the task's activity log. The mutation it describes
+    // has already succeeded, so a failure here is only logged.
+    async fn record(&self, task_id: &str, action: Action, detail: Option<String>) {
+        if let Err(e) = self.insert_activity(task_id, action, detail).await {
+            warn!("Failed to record {:?} on task {}: {}", action, task_id, e);
+        }
+    }
+
+    async fn insert_activity(&self, task_id: &str, action: Action, detail: Option<String>) -> Result<()> {
+        let actor = self.actor.read().ok().and_then(|a| a.clone());
+        let entry = json!({
+            "task_id": task_id,
+            "user_id": actor,
+            "action": action,
+            "detail": detail,
+        });
+
+        let request = self
+            .rest(Method::POST, "task_activity")
+            .header("Content-Type", "application/json")
+            .json(&entry);
+        let response = self.http.send(request).await?;
+
+        check(response, "Failed to record activity").await?;
+
+        Ok(())
+    }
+
+    pub async fn add_comment(&self, task_id: &str, body: &str) -> Result<()> {
+        self.insert_activity(task_id, Action::Commented, Some(body.to_string())).await
+    }
 
-        if !response.status().is_success() {
-            return Err("Failed to toggle task".into());
+    // Activity and comments for a task, oldest first
+    pub async fn get_activity(&self, task_id: &str) -> Result<Vec<Activity>> {
+        let request = self
+            .rest(Method::GET, "task_activity")
+            .query(&[("task_id", format!("eq.{}", task_id))])
+            .query(&[("order", "created_at.asc")]);
+        let response = self.http.send(request).await?;
+
+        let response = check(response, "Failed to fetch task activity").await?;
+
+        Ok(response.json().await?)
+    }
+
+    // Replace a tag on every task of the signed-in user via the rename_tag
+    // function (migration 0009). Merging is the same operation onto an
+    // existing tag.
+    pub async fn rename_tag(&self, old: &str, new: &str) -> Result<i64> {
+        let params = json!({
+            "p_old": old,
+            "p_new": new,
+        });
+
+        let request = self
+            .rest(Method::POST, "rpc/rename_tag")
+            .header("Content-Type", "application/json")
+            .json(&params);
+        let response = self.http.send(request).await?;
+
+        let response = check(response, "Failed to rename tag").await?;
+
+        let changed: i64 = response.json().await?;
+        Ok(changed)
+    }
+
+    // Projects the user owns or is a member of, with the user's role in each
+    pub async fn get_projects(&self, user_id: &str) -> Result<Vec<Project>> {
+        let memberships = self.get_memberships(user_id).await?;
+        let mut visible = vec![format!("user_id.eq.{}", user_id)];
+        if !memberships.is_empty() {
+            let ids: Vec<&str> = memberships.iter().map(|m| m.project_id.as_str()).collect();
+            visible.push(format!("id.in.({})", ids.join(",")));
         }
 
+        let request = self
+            .rest(Method::GET, "projects")
+            .query(&[("or", format!("({})", visible.join(",")))])
+            .query(&[("order", "position.asc")]);
+        let response = self.http.send(request).await?;
+
+        let response = check(response, "Failed to fetch projects").await?;
+
+        let projects: Vec<SupabaseProject> = response.json().await?;
+        Ok(projects.into_iter().map(|sp| {
+            let role = if sp.user_id == user_id {
+                Role::Owner
+            } else {
+                memberships
+                    .iter()
+                    .find(|m| m.project_id == sp.id)
+                    .map(|m| m.role)
+                    .unwrap_or(Role::Viewer)
+            };
+            Project {
+                id: sp.id,
+                name: sp.name,
+                color: sp.color,
+                position: sp.position,
+                created_at: DateTime::parse_from_rfc3339(&sp.created_at)
+                    .map(|dt| dt.with_timezone(&Utc))
+                    .unwrap_or_else(|_| Utc::now()),
+                owner_id: sp.user_id,
+                role,
+            }
+        }).collect())
+    }
+
+    // Create project
+    pub async fn create_project(&self, project: &Project, user_id: &str) -> Result<()> {
+        let body = json!({
+            "id": project.id,
+            "user_id": user_id,
+            "name": project.name,
+            "color": project.color,
+            "position": project.position,
+            "created_at": project.created_at.to_rfc3339(),
+        });
+
+        let request = self
+            .rest(Method::POST, "projects")
+            .header("Content-Type", "application/json")
+            .json(&body);
+        let response = self.http.send(request).await?;
+
+        check(response, "Failed to create project").await?;
+
+        Ok(())
+    }
+
+    // Update project name, color and position
+    pub async fn update_project(&self, project: &Project) -> Result<()> {
+        let body = json!({
+            "name": project.name,
+            "color": project.color,
+            "position": project.position,
+        });
+
+        let request = self
+            .rest(Method::PATCH, "projects")
+            .header("Content-Type", "application/json")
+            .query(&[("id", format!("eq.{}", project.id))])
+            .json(&body);
+        let response = self.http.send_idempotent(request).await?;
+
+        check(response, "Failed to update project").await?;
+
+        Ok(())
+    }
+
+    // Delete project; its tasks fall back to no project
+    pub async fn delete_project(&self, project_id: &str) -> Result<()> {
+        let request = self
+            .rest(Method::DELETE, "projects")
+            .query(&[("id", format!("eq.{}", project_id))]);
+        let response = self.http.send(request).await?;
+
+        check(response, "Failed to delete project").await?;
+
+        Ok(())
+    }
+
+    // Projects shared with the user, from project_members (migration 0005)
+    async fn get_memberships(&self, user_id: &str) -> Result<Vec<ProjectMember>> {
+        let request = self
+            .rest(Method::GET, "project_members")
+            .query(&[("user_id", format!("eq.{}", user_id))]);
+        let response = self.http.send(request).await?;
+
+        let response = check(response, "Failed to fetch project memberships").await?;
+
+        Ok(response.json().await?)
+    }
+
+    pub async fn get_members(&self, project_id: &str) -> Result<Vec<ProjectMember>> {
+        let request = self
+            .rest(Method::GET, "project_members")
+            .query(&[("project_id", format!("eq.{}", project_id))]);
+        let response = self.http.send(request).await?;
+
+        let response = check(response, "Failed to fetch project members").await?;
+
+        Ok(response.json().await?)
+    }
+
+    // Add a member or change their role
+    pub async fn set_member(&self, member: &ProjectMember) -> Result<()> {
+        let request = self
+            .rest(Method::POST, "project_members")
+            .header("Content-Type", "application/json")
+            .header("Prefer", "resolution=merge-duplicates")
+            .json(member);
+        let response = self.http.send_idempotent(request).await?;
+
+        check(response, "Failed to share project").await?;
+
         Ok(())
     }
-}
\ No newline at end of file
+
+    pub async fn remove_member(&self, project_id: &str, user_id: &str) -> Result<()> {
+        let request = self
+            .rest(Method::DELETE, "project_members")
+            .query(&[("project_id", format!("eq.{}", project_id))])
+            .query(&[("user_id", format!("eq.{}", user_id))]);
+        let response = self.http.send(request).await?;
+
+        check(response, "Failed to remove project member").await?;
+
+        Ok(())
+    }
+
+    pub async fn find_user(&self, username: &str) -> Result<Option<UserSummary>> {
+        let request = self
+            .rest(Method::GET, "users")
+            .query(&[("select", "id,username")])
+            .query(&[("username", format!("eq.{}", username))]);
+        let response = self.http.send(request).await?;
+
+        let response = check(response, "Failed to look up user").await?;
+
+        let users: Vec<UserSummary> = response.json().await?;
+        Ok(users.into_iter().next())
+    }
+
+    // Usernames for a set of user ids, for showing assignees and members
+    pub async fn get_usernames(&self, ids: &[String]) -> Result<HashMap<String, String>> {
+        if ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let request = self
+            .rest(Method::GET, "users")
+            .query(&[("select", "id,username")])
+            .query(&[("id", format!("in.({})", ids.join(",")))]);
+        let response = self.http.send(request).await?;
+
+        let response = check(response, "Failed to fetch users").await?;
+
+        let users: Vec<UserSummary> = response.json().await?;
+        Ok(users.into_iter().map(|u| (u.id, u.username)).collect())
+    }
+}
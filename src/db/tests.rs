@@ -0,0 +1,468 @@
+use super::mock_server::{self, MockServer};
+use super::*;
+use axum::http::StatusCode;
+use serde_json::Value;
+
+async fn register(server: &MockServer, username: &str) -> (SupabaseClient, SupabaseUser) {
+    let sb = server.client();
+    let email = format!("{}@example.com", username);
+    let user = sb.register(username, &email, "hunter22").await.unwrap();
+    (sb, user)
+}
+
+fn task(title: &str, position: i32) -> Task {
+    let mut task = Task::new(title.to_string());
+    task.position = position;
+    task
+}
+
+fn titles(tasks: &[Task]) -> Vec<&str> {
+    tasks.iter().map(|t| t.title.as_str()).collect()
+}
+
+fn actions(server: &MockServer, task_id: &str) -> Vec<String> {
+    server
+        .rows("task_activity")
+        .iter()
+        .filter(|a| a["task_id"] == task_id)
+        .map(|a| a["action"].as_str().unwrap_or_default().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn register_then_login() {
+    let server = MockServer::start().await;
+    let (_, user) = register(&server, "alice").await;
+
+    // users.id reuses the Supabase Auth id so RLS can match auth.uid()
+    let users = server.rows("users");
+    assert_eq!(users.len(), 1);
+    assert_eq!(users[0]["id"], user.id.as_str());
+    assert_ne!(users[0]["password_hash"], "hunter22");
+
+    let sb = server.client();
+    let logged_in = sb.login("alice", "hunter22").await.unwrap();
+    assert_eq!(logged_in.id, user.id);
+    assert_eq!(logged_in.email, "alice@example.com");
+    // The password is checked server-side; the hash is never fetched
+    let requests = server.requests();
+    assert!(requests.contains(&"POST /rest/v1/rpc/verify_login".to_string()));
+    assert!(!requests.contains(&"GET /rest/v1/users".to_string()));
+    assert!(requests.contains(&"POST /auth/v1/token".to_string()));
+}
+
+#[tokio::test]
+async fn login_rejects_bad_credentials() {
+    let server = MockServer::start().await;
+    register(&server, "alice").await;
+    let sb = server.client();
+
+    assert!(matches!(sb.login("alice", "wrong password").await, Err(AppError::Auth(_))));
+    assert!(matches!(sb.login("nobody", "hunter22").await, Err(AppError::Auth(_))));
+}
+
+#[tokio::test]
+async fn login_needs_a_matching_auth_account() {
+    let server = MockServer::start().await;
+    register(&server, "alice").await;
+    let hash = bcrypt::hash("hunter22", 4).unwrap();
+
+    // A users row without a Supabase Auth account, as on anon-key-only setups
+    server.insert("users", serde_json::json!({ "username": "bob", "email": "bob@example.com", "password_hash": hash }));
+    let err = server.client().login("bob", "hunter22").await.unwrap_err();
+    assert!(matches!(&err, AppError::Auth(msg) if msg.contains("sign-in failed")), "{:?}", err);
+
+    // An Auth account that isn't the row's: RLS would hide everything
+    server.insert("users", serde_json::json!({ "username": "mallory", "email": "alice@example.com", "password_hash": hash }));
+    let err = server.client().login("mallory", "hunter22").await.unwrap_err();
+    assert!(matches!(&err, AppError::Auth(msg) if msg.contains("doesn't match")), "{:?}", err);
+}
+
+#[tokio::test]
+async fn registration_reuses_an_unfinished_auth_account() {
+    let server = MockServer::start().await;
+    let sb = server.client();
+
+    // Signed up earlier, but the users row was never created
+    sb.sign_up("alice@example.com", "hunter22").await.unwrap();
+    let user = sb.register("alice", "alice@example.com", "hunter22").await.unwrap();
+    assert_eq!(server.rows("users")[0]["id"], user.id.as_str());
+
+    // Only with that account's password
+    let sb = server.client();
+    assert!(matches!(sb.register("alice2", "alice@example.com", "wrong").await, Err(AppError::Auth(_))));
+    assert_eq!(server.rows("users").len(), 1);
+}
+
+#[tokio::test]
+async fn task_crud_round_trip() {
+    let server = MockServer::start().await;
+    let (sb, user) = register(&server, "alice").await;
+
+    let mut first = task("Write report", 0);
+    first.priority = Priority::High;
+    first.tags = vec!["work".to_string()];
+    sb.create_task(&first, &user.id).await.unwrap();
+    sb.create_task(&task("Buy milk", 1), &user.id).await.unwrap();
+
+    let tasks = sb.get_tasks(&user.id).await.unwrap();
+    assert_eq!(titles(&tasks), ["Write report", "Buy milk"]);
+    assert_eq!(tasks[0].priority, Priority::High);
+    assert_eq!(tasks[0].tags, ["work"]);
+    assert_eq!(tasks[0].status, Status::Todo);
+
+    let mut edited = tasks[0].clone();
+    edited.title = "Write quarterly report".to_string();
+    edited.priority = Priority::Low;
+    sb.update_task(&tasks[0], &edited).await.unwrap();
+    sb.set_status(&edited.id, Status::Todo, Status::Done).await.unwrap();
+
+    let tasks = sb.get_tasks(&user.id).await.unwrap();
+    assert_eq!(tasks[0].title, "Write quarterly report");
+    assert_eq!(tasks[0].priority, Priority::Low);
+    assert_eq!(tasks[0].status, Status::Done);
+    // The legacy flag is kept in step for older clients
+    assert_eq!(server.rows("tasks")[0]["done"], true);
+
+    sb.delete_task(&tasks[1].id).await.unwrap();
+    assert_eq!(titles(&sb.get_tasks(&user.id).await.unwrap()), ["Write quarterly report"]);
+}
+
+#[tokio::test]
+async fn mutations_are_recorded_in_activity_log() {
+    let server = MockServer::start().await;
+    let (sb, user) = register(&server, "alice").await;
+
+    let original = task("Draft", 0);
+    sb.create_task(&original, &user.id).await.unwrap();
+    let mut renamed = original.clone();
+    renamed.title = "Final draft".to_string();
+    sb.update_task(&original, &renamed).await.unwrap();
+    sb.set_status(&original.id, Status::Todo, Status::Done).await.unwrap();
+    sb.add_comment(&original.id, "Sent to the team").await.unwrap();
+
+    assert_eq!(actions(&server, &original.id), ["created", "renamed", "completed", "commented"]);
+    // Changes are worked out locally, without reading the task back first
+    assert!(!server.requests().contains(&"GET /rest/v1/tasks".to_string()));
+    let activity = sb.get_activity(&original.id).await.unwrap();
+    assert!(activity.iter().all(|a| a.user_id.as_deref() == Some(user.id.as_str())));
+    assert_eq!(activity[3].detail.as_deref(), Some("Sent to the team"));
+
+    // Reordering alone is not worth a timeline entry
+    let mut moved = renamed.clone();
+    moved.position = 5;
+    sb.update_positions(&[moved]).await.unwrap();
+    assert_eq!(actions(&server, &original.id).len(), 4);
+
+    // The log outlives the task and ends with its deletion, which the
+    // database records rather than the client
+    sb.delete_task(&renamed.id).await.unwrap();
+    assert_eq!(actions(&server, &original.id).last().map(String::as_str), Some("deleted"));
+    let deleted = server.rows("task_activity").pop().unwrap();
+    assert_eq!(deleted["detail"], "Final draft");
+    assert_eq!(deleted["user_id"], user.id.as_str());
+    assert_eq!(server.requests().last().map(String::as_str), Some("DELETE /rest/v1/tasks"));
+}
+
+#[tokio::test]
+async fn update_positions_reorders_tasks() {
+    let server = MockServer::start().await;
+    let (sb, user) = register(&server, "alice").await;
+    for (i, title) in ["a", "b", "c"].iter().enumerate() {
+        sb.create_task(&task(title, i as i32), &user.id).await.unwrap();
+    }
+
+    let mut tasks = sb.get_tasks(&user.id).await.unwrap();
+    // Move "c" to the top
+    tasks[2].position = 0;
+    tasks[0].position = 1;
+    tasks[1].position = 2;
+    sb.update_positions(&tasks).await.unwrap();
+
+    assert_eq!(titles(&sb.get_tasks(&user.id).await.unwrap()), ["c", "a", "b"]);
+}
+
+#[tokio::test]
+async fn completing_a_repeating_task_schedules_the_next_one() {
+    let server = MockServer::start().await;
+    let (sb, user) = register(&server, "alice").await;
+    let mut rent = task("Pay rent", 0);
+    rent.due_date = Some("2024-01-31T09:00:00Z".parse().unwrap());
+    rent.recurrence = Recurrence::parse("monthly");
+    sb.create_task(&rent, &user.id).await.unwrap();
+    sb.create_task(&task("Buy milk", 1), &user.id).await.unwrap();
+    assert_eq!(server.rows("tasks")[0]["recurrence"], "1 month");
+
+    sb.set_status(&rent.id, Status::Todo, Status::Done).await.unwrap();
+
+    let tasks = sb.get_tasks(&user.id).await.unwrap();
+    assert_eq!(titles(&tasks), ["Pay rent", "Buy milk", "Pay rent"]);
+    assert_eq!(tasks[0].status, Status::Done);
+    assert_eq!(tasks[0].recurrence, None);
+    let next = &tasks[2];
+    assert_eq!(next.status, Status::Todo);
+    assert_eq!(next.recurrence, rent.recurrence);
+    assert_eq!(next.due_date, Some("2024-02-29T09:00:00Z".parse().unwrap()));
+    assert_eq!(actions(&server, &next.id), ["created"]);
+
+    // Reopening and completing the finished one again doesn't repeat it twice
+    sb.set_status(&rent.id, Status::Done, Status::Todo).await.unwrap();
+    sb.set_status(&rent.id, Status::Todo, Status::Done).await.unwrap();
+    assert_eq!(sb.get_tasks(&user.id).await.unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn unknown_recurrence_is_an_error() {
+    let server = MockServer::start().await;
+    let (sb, user) = register(&server, "alice").await;
+    server.insert(
+        "tasks",
+        serde_json::json!({
+            "user_id": user.id, "title": "Odd", "description": "", "done": false, "priority": 2,
+            "tags": [], "created_at": "2024-05-01T10:00:00Z", "updated_at": "2024-05-01T10:00:00Z",
+            "position": 0, "recurrence": "now and then",
+        }),
+    );
+
+    let err = sb.get_tasks(&user.id).await.unwrap_err();
+    assert!(matches!(err, AppError::Serialization(ref msg) if msg.contains("recurrence")), "{:?}", err);
+}
+
+#[tokio::test]
+async fn shared_project_tasks_are_visible_to_members() {
+    let server = MockServer::start().await;
+    let (owner_sb, owner) = register(&server, "alice").await;
+    let (member_sb, member) = register(&server, "bob").await;
+
+    let project = Project::new("Website".to_string(), None, owner.id.clone());
+    owner_sb.create_project(&project, &owner.id).await.unwrap();
+    let mut shared = task("Fix header", 0);
+    shared.project_id = Some(project.id.clone());
+    owner_sb.create_task(&shared, &owner.id).await.unwrap();
+    owner_sb.create_task(&task("Private", 1), &owner.id).await.unwrap();
+
+    assert!(member_sb.get_tasks(&member.id).await.unwrap().is_empty());
+
+    let membership = ProjectMember {
+        project_id: project.id.clone(),
+        user_id: member.id.clone(),
+        role: Role::Viewer,
+    };
+    owner_sb.set_member(&membership).await.unwrap();
+    // Sharing again updates the role instead of failing on the duplicate
+    owner_sb.set_member(&ProjectMember { role: Role::Editor, ..membership }).await.unwrap();
+    assert_eq!(server.rows("project_members").len(), 1);
+
+    let projects = member_sb.get_projects(&member.id).await.unwrap();
+    assert_eq!(projects.len(), 1);
+    assert_eq!(projects[0].role, Role::Editor);
+    assert_eq!(titles(&member_sb.get_tasks(&member.id).await.unwrap()), ["Fix header"]);
+
+    owner_sb.remove_member(&project.id, &member.id).await.unwrap();
+    assert!(member_sb.get_tasks(&member.id).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn assigned_tasks_are_visible_to_assignee() {
+    let server = MockServer::start().await;
+    let (owner_sb, owner) = register(&server, "alice").await;
+    let (member_sb, member) = register(&server, "bob").await;
+
+    let mut assigned = task("Review PR", 0);
+    assigned.assignee_id = Some(member.id.clone());
+    owner_sb.create_task(&assigned, &owner.id).await.unwrap();
+
+    let tasks = member_sb.get_tasks(&member.id).await.unwrap();
+    assert_eq!(titles(&tasks), ["Review PR"]);
+    let names = member_sb.get_usernames(&[owner.id.clone(), member.id.clone()]).await.unwrap();
+    assert_eq!(names[&member.id], "bob");
+    assert_eq!(member_sb.find_user("alice").await.unwrap().map(|u| u.id), Some(owner.id));
+}
+
+#[tokio::test]
+async fn rename_tag_merges_duplicates() {
+    let server = MockServer::start().await;
+    let (sb, user) = register(&server, "alice").await;
+    let mut both = task("Both", 0);
+    both.tags = vec!["wrk".to_string(), "work".to_string()];
+    let mut one = task("One", 1);
+    one.tags = vec!["wrk".to_string()];
+    sb.create_task(&both, &user.id).await.unwrap();
+    sb.create_task(&one, &user.id).await.unwrap();
+    // Only the caller's own tasks are renamed
+    let (other_sb, other) = register(&server, "bob").await;
+    let mut theirs = task("Theirs", 0);
+    theirs.tags = vec!["wrk".to_string()];
+    other_sb.create_task(&theirs, &other.id).await.unwrap();
+
+    assert_eq!(sb.rename_tag("wrk", "work").await.unwrap(), 2);
+    let tasks = sb.get_tasks(&user.id).await.unwrap();
+    assert_eq!(tasks[0].tags, ["work"]);
+    assert_eq!(tasks[1].tags, ["work"]);
+    assert_eq!(other_sb.get_tasks(&other.id).await.unwrap()[0].tags, ["wrk"]);
+}
+
+#[tokio::test]
+async fn server_errors_are_reported() {
+    let server = MockServer::start().await;
+    let (sb, user) = register(&server, "alice").await;
+
+    // A POST isn't repeated after a 500: the insert may have happened
+    server.fail_next(StatusCode::INTERNAL_SERVER_ERROR, "boom");
+    let err = sb.create_task(&task("Maybe", 0), &user.id).await.unwrap_err();
+    assert!(matches!(err, AppError::Http { status: 500, ref body, .. } if body == "boom"));
+    assert!(err.is_retryable());
+
+    server.fail_next(StatusCode::BAD_REQUEST, "violates check constraint");
+    let err = sb.create_task(&task("Bad", 0), &user.id).await.unwrap_err();
+    assert!(err.to_string().contains("violates check constraint"));
+    assert!(!err.is_retryable());
+    assert!(server.rows("tasks").is_empty());
+
+    server.fail_next(StatusCode::FORBIDDEN, "permission denied for table tasks");
+    let err = sb.get_tasks(&user.id).await.unwrap_err();
+    assert!(matches!(err, AppError::Auth(_)));
+
+    // The next request goes through again
+    assert!(sb.get_tasks(&user.id).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn malformed_timestamps_are_an_error() {
+    let server = MockServer::start().await;
+    let (sb, user) = register(&server, "alice").await;
+    sb.create_task(&task("Fine", 0), &user.id).await.unwrap();
+    server.insert(
+        "tasks",
+        serde_json::json!({
+            "user_id": user.id, "title": "Odd", "description": "", "done": false, "priority": 2,
+            "tags": [], "created_at": "yesterday", "updated_at": "2024-05-01T10:00:00Z", "position": 1,
+        }),
+    );
+
+    let err = sb.get_tasks(&user.id).await.unwrap_err();
+    assert!(matches!(err, AppError::Serialization(ref msg) if msg.contains("created_at")), "{:?}", err);
+}
+
+#[tokio::test]
+async fn malformed_due_dates_are_an_error() {
+    let server = MockServer::start().await;
+    let (sb, user) = register(&server, "alice").await;
+    server.insert(
+        "tasks",
+        serde_json::json!({
+            "user_id": user.id, "title": "Odd", "description": "", "done": false, "priority": 2,
+            "due_date": "next friday", "tags": [], "created_at": "2024-05-01T10:00:00Z",
+            "updated_at": "2024-05-01T10:00:00Z", "position": 0,
+        }),
+    );
+
+    let err = sb.get_tasks(&user.id).await.unwrap_err();
+    assert!(matches!(err, AppError::Serialization(ref msg) if msg.contains("due_date")), "{:?}", err);
+}
+
+#[tokio::test]
+async fn updating_a_deleted_task_is_not_found() {
+    let server = MockServer::start().await;
+    let (sb, user) = register(&server, "alice").await;
+    let gone = task("Gone", 0);
+    sb.create_task(&gone, &user.id).await.unwrap();
+    sb.delete_task(&gone.id).await.unwrap();
+
+    assert!(matches!(sb.update_task(&gone, &gone).await, Err(AppError::NotFound(_))));
+    assert!(matches!(sb.set_status(&gone.id, Status::Todo, Status::Done).await, Err(AppError::NotFound(_))));
+    // Row-level security refusing a reorder looks the same
+    assert!(matches!(sb.update_positions(&[gone]).await, Err(AppError::NotFound(_))));
+}
+
+#[tokio::test]
+async fn transient_failures_are_retried() {
+    let server = MockServer::start().await;
+    let (sb, user) = register(&server, "alice").await;
+    let reads = |server: &MockServer| {
+        server.requests().iter().filter(|r| *r == "GET /rest/v1/project_members").count()
+    };
+
+    // get_tasks starts by reading memberships; two failures are absorbed
+    server.fail_next(StatusCode::SERVICE_UNAVAILABLE, "restarting");
+    server.fail_next(StatusCode::BAD_GATEWAY, "upstream");
+    assert!(sb.get_tasks(&user.id).await.unwrap().is_empty());
+    assert_eq!(reads(&server), 3);
+
+    // A third one exhausts the retries
+    for _ in 0..3 {
+        server.fail_next(StatusCode::SERVICE_UNAVAILABLE, "restarting");
+    }
+    let err = sb.get_tasks(&user.id).await.unwrap_err();
+    assert!(matches!(err, AppError::Http { status: 503, .. }));
+}
+
+#[tokio::test]
+async fn rate_limited_requests_honor_retry_after() {
+    let server = MockServer::start().await;
+    let (sb, user) = register(&server, "alice").await;
+
+    // 429 means nothing was done, so even an insert is sent again
+    server.fail_next_with_retry_after(StatusCode::TOO_MANY_REQUESTS, 0);
+    sb.create_task(&task("Once", 0), &user.id).await.unwrap();
+    assert_eq!(server.rows("tasks").len(), 1);
+
+    // A wait longer than the retry limit is reported instead of slept through
+    server.fail_next_with_retry_after(StatusCode::TOO_MANY_REQUESTS, 3600);
+    let err = sb.get_tasks(&user.id).await.unwrap_err();
+    assert!(matches!(err, AppError::Http { status: 429, .. }));
+}
+
+#[tokio::test]
+async fn unreachable_server_is_a_network_error() {
+    // Nothing listens on port 9 (discard) on the loopback interface
+    let sb = SupabaseClient::new("http://127.0.0.1:9".to_string(), "key".to_string(), &mock_server::settings());
+    let err = sb.get_tasks("someone").await.unwrap_err();
+    assert!(matches!(err, AppError::Network(_)));
+    assert!(err.is_retryable());
+}
+
+#[tokio::test]
+async fn repeated_failures_switch_to_offline() {
+    let settings = NetworkSettings {
+        max_retries: 0,
+        failure_threshold: 2,
+        ..mock_server::settings()
+    };
+    let sb = SupabaseClient::new("http://127.0.0.1:9".to_string(), "key".to_string(), &settings);
+
+    assert!(matches!(sb.get_tasks("someone").await, Err(AppError::Network(_))));
+    assert!(!sb.is_offline());
+    assert!(matches!(sb.get_tasks("someone").await, Err(AppError::Network(_))));
+    assert!(sb.is_offline());
+    // Refused without trying the connection
+    assert!(matches!(sb.get_tasks("someone").await, Err(AppError::Offline(_))));
+}
+
+#[tokio::test]
+async fn wrong_api_key_is_rejected() {
+    let server = MockServer::start().await;
+    let sb = SupabaseClient::new(server.base_url.clone(), "not-the-key".to_string(), &mock_server::settings());
+    assert!(matches!(sb.get_tasks("someone").await, Err(AppError::Auth(_))));
+}
+
+#[tokio::test]
+async fn schema_version_reports_missing_function() {
+    let server = MockServer::start().await;
+    let sb = server.client();
+    assert_eq!(sb.schema_version().await.unwrap(), Some(migrations::latest()));
+
+    server.set_schema_version(None);
+    assert_eq!(sb.schema_version().await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn seeded_rows_are_readable() {
+    let server = MockServer::start().await;
+    server.insert("users", serde_json::json!({ "username": "carol", "email": "c@example.com", "password_hash": "x" }));
+    let sb = server.client();
+
+    let found = sb.find_user("carol").await.unwrap().unwrap();
+    let users: Vec<Value> = server.rows("users");
+    assert_eq!(users[0]["id"], found.id.as_str());
+}
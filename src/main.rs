@@ -3,22 +3,39 @@ mod core;
 mod ai;
 mod config;
 mod db;
+mod net;
 
 use crossterm::{
-    event::{self, Event, KeyCode},
+    event::{
+        DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+        Event, EventStream, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{
     backend::CrosstermBackend,
+    layout::Rect,
     Terminal,
 };
+use futures::StreamExt;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
 use std::io;
-use ui::{AppState, Mode};
-use core::Priority;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::MissedTickBehavior;
+use ui::{jobs, preview, AppState, BoardGroup, Breakdown, ChatSession, DayPlan, Duplicates, Hit, Mode, ProjectScope, TaskDetail, View};
+use config::HistorySearch;
+use core::{Priority, Project, Role, Status, Task};
+use core::error::AppError;
+use core::projects::resolve;
+use core::sort::{SortKey, SortSpec};
+use core::tags::{extract_tags, normalize_tag, with_tags};
 use dotenv::dotenv;
 use ai::AIAssistant;
-use db::SupabaseClient;
+use ai::chat::{ChatContext, TaskChange};
+use db::{migrations, ProjectMember, SupabaseClient};
 use log::{info, error};
 
 #[tokio::main]
@@ -27,9 +44,16 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     info!("Starting TODO AI application");
     
     dotenv().ok();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("db") {
+        return run_db_command(&args[1..]).await;
+    }
     
-    let api_key = std::env::var("GROQ_API_KEY").unwrap_or_default();
-    let ai = AIAssistant::new(api_key);
+    let mut state = AppState::new();
+    let network = state.config.network.clone();
+
+    let ai = Arc::new(AIAssistant::from_settings(&state.config.ai_settings, &network));
     
     // Initialize Supabase
     let supabase_url = std::env::var("SUPABASE_URL")
@@ -37,389 +61,636 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let supabase_key = std::env::var("SUPABASE_KEY")
         .expect("SUPABASE_KEY must be set in .env");
     
-    let supabase = SupabaseClient::new(supabase_url, supabase_key);
+    let supabase = Arc::new(SupabaseClient::new(supabase_url, supabase_key, &network));
+    check_schema(&supabase).await?;
     
     enable_raw_mode()?;
+    let _guard = TerminalGuard;
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        // Restore first so the panic message is readable
+        restore_terminal();
+        default_hook(info);
+    }));
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen)?;
+    execute!(stdout, EnterAlternateScreen, EnableBracketedPaste, EnableMouseCapture)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let mut state = AppState::new();
     state.supabase = Some(supabase);
     state.mode = Mode::Login;
 
     let mut g_pressed = false;
+    let mut events = EventStream::new();
+    let mut finished = state.jobs.take_receiver().expect("job receiver is taken once");
+    // Redraws the spinner and expires status messages while nothing else happens
+    let mut tick = tokio::time::interval(Duration::from_millis(100));
+    tick.set_missed_tick_behavior(MissedTickBehavior::Skip);
 
-    loop {
+    while !state.quit {
         state.clear_old_status();
+        state.open_next_preview();
         terminal.draw(|f| {
-            ui::render(f, &state);
+            ui::render(f, &mut state);
         })?;
 
-        if let Event::Key(key) = event::read()? {
-            match state.mode {
-                Mode::Normal => {
-                    match key.code {
-                        KeyCode::Char('q') => break,
-                        KeyCode::Char('j') | KeyCode::Down => state.next(),
-                        KeyCode::Char('k') | KeyCode::Up => state.previous(),
-                        
-                        // Move task up (Shift+K)
-                        KeyCode::Char('K') => {
-                            if state.selected > 0 && !state.tasks.is_empty() {
-                                if let (Some(ref sb), Some(ref user)) = (&state.supabase, &state.current_user) {
-                                    // Swap positions
-                                    let current_pos = state.tasks[state.selected].position;
-                                    let above_pos = state.tasks[state.selected - 1].position;
-                                    
-                                    state.tasks[state.selected].position = above_pos;
-                                    state.tasks[state.selected - 1].position = current_pos;
-                                    
-                                    // Update in database
-                                    let tasks_to_update = vec![
-                                        state.tasks[state.selected].clone(),
-                                        state.tasks[state.selected - 1].clone(),
-                                    ];
-                                    
-                                    if let Ok(_) = sb.update_positions(&tasks_to_update).await {
-                                        state.selected -= 1;
-                                        if let Ok(tasks) = sb.get_tasks(&user.id).await {
-                                            state.tasks = tasks;
-                                        }
-                                        state.set_status("Task moved up".to_string());
-                                    }
-                                }
-                            }
-                        }
-                        
-                        // Move task down (Shift+J)
-                        KeyCode::Char('J') => {
-                            if state.selected < state.tasks.len() - 1 && !state.tasks.is_empty() {
-                                if let (Some(ref sb), Some(ref user)) = (&state.supabase, &state.current_user) {
-                                    // Swap positions
-                                    let current_pos = state.tasks[state.selected].position;
-                                    let below_pos = state.tasks[state.selected + 1].position;
-                                    
-                                    state.tasks[state.selected].position = below_pos;
-                                    state.tasks[state.selected + 1].position = current_pos;
-                                    
-                                    // Update in database
-                                    let tasks_to_update = vec![
-                                        state.tasks[state.selected].clone(),
-                                        state.tasks[state.selected + 1].clone(),
-                                    ];
-                                    
-                                    if let Ok(_) = sb.update_positions(&tasks_to_update).await {
-                                        state.selected += 1;
-                                        if let Ok(tasks) = sb.get_tasks(&user.id).await {
-                                            state.tasks = tasks;
-                                        }
-                                        state.set_status("Task moved down".to_string());
-                                    }
-                                }
-                            }
-                        }
-                        
-                        KeyCode::Char(' ') => {
-                            if let (Some(task), Some(ref sb), Some(ref user)) = 
-                                (state.tasks.get(state.selected), &state.supabase, &state.current_user) {
-                                match sb.toggle_done(&task.id, task.done).await {
-                                    Ok(_) => {
-                                        if let Ok(tasks) = sb.get_tasks(&user.id).await {
-                                            state.tasks = tasks;
-                                        }
-                                    }
-                                    Err(e) => {
-                                        error!("Failed to toggle task: {}", e);
-                                    }
-                                }
-                            }
-                        }
-                        KeyCode::Char('?') => state.show_help = !state.show_help,
-                        KeyCode::Char(':') => {
-                            state.mode = Mode::Command;
-                            state.command_input.clear();
-                        }
-                        KeyCode::Char('e') => {
-                            if let Some(task) = state.tasks.get(state.selected) {
-                                state.mode = Mode::Edit;
-                                state.command_input = task.title.clone();
-                                state.editing_task = Some(task.id.clone());
-                            }
-                        }
-                        KeyCode::Char('1') => {
-                            if let (Some(task), Some(ref sb), Some(ref user)) = 
-                                (state.tasks.get_mut(state.selected), &state.supabase, &state.current_user) {
-                                task.priority = Priority::Low;
-                                if let Ok(_) = sb.update_task(task).await {
-                                    if let Ok(tasks) = sb.get_tasks(&user.id).await {
-                                        state.tasks = tasks;
-                                    }
-                                }
-                            }
-                        }
-                        KeyCode::Char('2') => {
-                            if let (Some(task), Some(ref sb), Some(ref user)) = 
-                                (state.tasks.get_mut(state.selected), &state.supabase, &state.current_user) {
-                                task.priority = Priority::Medium;
-                                if let Ok(_) = sb.update_task(task).await {
-                                    if let Ok(tasks) = sb.get_tasks(&user.id).await {
-                                        state.tasks = tasks;
-                                    }
-                                }
-                            }
-                        }
-                        KeyCode::Char('3') => {
-                            if let (Some(task), Some(ref sb), Some(ref user)) = 
-                                (state.tasks.get_mut(state.selected), &state.supabase, &state.current_user) {
-                                task.priority = Priority::High;
-                                if let Ok(_) = sb.update_task(task).await {
-                                    if let Ok(tasks) = sb.get_tasks(&user.id).await {
-                                        state.tasks = tasks;
-                                    }
-                                }
-                            }
-                        }
-                        KeyCode::Char('g') => {
-                            if g_pressed {
-                                state.selected = 0;
-                                g_pressed = false;
-                            } else {
-                                g_pressed = true;
-                            }
-                        }
-                        KeyCode::Char('G') => {
-                            if !state.tasks.is_empty() {
-                                state.selected = state.tasks.len() - 1;
+        let event = tokio::select! {
+            event = events.next() => match event {
+                Some(event) => event?,
+                None => break,
+            },
+            Some(done) = finished.recv() => {
+                jobs::apply(&mut state, done);
+                continue;
+            }
+            _ = tick.tick(), if state.jobs.is_busy() || state.status_timer.is_some() => continue,
+        };
+
+        let key = match event {
+            Event::Key(key) => key,
+            Event::Paste(text) => {
+                let typing = match state.mode {
+                    Mode::Normal | Mode::Detail | Mode::Dedupe => false,
+                    Mode::Preview => state.preview.as_ref().is_some_and(|p| p.editing),
+                    Mode::Breakdown => state.breakdown.as_ref().is_some_and(|b| b.editing),
+                    Mode::Chat => state.chat.as_ref().is_some_and(|c| c.proposal.is_empty()),
+                    _ => true,
+                };
+                if typing && state.history_search.is_none() {
+                    state.command_input.insert_str(&text);
+                }
+                continue;
+            }
+            Event::Mouse(mouse) => {
+                if matches!(state.mode, Mode::Normal) && !state.show_help {
+                    let area = terminal.size()?;
+                    handle_mouse(&mut state, mouse, area);
+                }
+                continue;
+            }
+            _ => continue,
+        };
+
+        match state.mode {
+            Mode::Normal => {
+                match key.code {
+                    KeyCode::Char('q') => break,
+                    KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => state.half_page_down(),
+                    KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => state.half_page_up(),
+                    KeyCode::PageDown => state.page_down(),
+                    KeyCode::PageUp => state.page_up(),
+                    KeyCode::Char('j') | KeyCode::Down => state.next(),
+                    KeyCode::Char('k') | KeyCode::Up => state.previous(),
+                    _ if is_edit_key(&state, key) && state.is_offline() => {
+                        state.set_status("Offline: changes can't be saved until the server responds again".to_string());
+                    }
+                    _ if is_edit_key(&state, key) && state.selected_task().is_some_and(|t| !state.can_edit(t)) => {
+                        state.set_status("Read-only: you are a viewer of this project".to_string());
+                    }
+                    
+                    // Move task up (Shift+K)
+                    KeyCode::Char('K') => reorder(&mut state, -1),
+                    
+                    // Move task down (Shift+J)
+                    KeyCode::Char('J') => reorder(&mut state, 1),
+                    
+                    KeyCode::Char(' ') => toggle_selected(&mut state),
+                    KeyCode::Char('s') => {
+                        let next = state
+                            .selected_task()
+                            .and_then(|t| state.config.workflow.cycle(t.status));
+                        if let Some(status) = next {
+                            if change_status(&mut state, status) {
+                                state.set_status(format!("Status: {}", status.label()));
                             }
                         }
-                        KeyCode::Char('d') => {
-                            if let (Some(task), Some(ref sb), Some(ref user)) = 
-                                (state.tasks.get(state.selected), &state.supabase, &state.current_user) {
-                                let task_id = task.id.clone();
-                                match sb.delete_task(&task_id).await {
-                                    Ok(_) => {
-                                        if let Ok(tasks) = sb.get_tasks(&user.id).await {
-                                            state.tasks = tasks;
-                                            if state.selected >= state.tasks.len() && state.selected > 0 {
-                                                state.selected -= 1;
-                                            }
-                                        }
-                                    }
-                                    Err(e) => {
-                                        error!("Failed to delete task: {}", e);
-                                    }
-                                }
-                            }
+                    }
+                    KeyCode::Char('h') | KeyCode::Left if state.view == View::Board => state.move_column(-1),
+                    KeyCode::Char('l') | KeyCode::Right if state.view == View::Board => state.move_column(1),
+                    KeyCode::Char('H') if state.view == View::Board => move_card(&mut state, -1),
+                    KeyCode::Char('L') if state.view == View::Board => move_card(&mut state, 1),
+                    KeyCode::Char('h') | KeyCode::Left if state.view == View::Calendar => state.move_calendar(-1),
+                    KeyCode::Char('l') | KeyCode::Right if state.view == View::Calendar => state.move_calendar(1),
+                    KeyCode::Char('H') if state.view == View::Calendar => state.move_calendar(-7),
+                    KeyCode::Char('L') if state.view == View::Calendar => state.move_calendar(7),
+                    KeyCode::Char('<') if state.view == View::Calendar => state.move_calendar_months(-1),
+                    KeyCode::Char('>') if state.view == View::Calendar => state.move_calendar_months(1),
+                    KeyCode::Char('m') if state.view == View::Calendar && state.rescheduling.is_none() => {
+                        state.start_reschedule();
+                    }
+                    KeyCode::Enter | KeyCode::Char('m') if state.rescheduling.is_some() => {
+                        finish_reschedule(&mut state);
+                    }
+                    KeyCode::Esc if state.rescheduling.is_some() => state.cancel_reschedule(),
+                    KeyCode::Tab | KeyCode::BackTab if state.rescheduling.is_some() => {}
+                    KeyCode::Enter => open_detail(&mut state),
+                    KeyCode::Char('t') => state.show_tags = !state.show_tags,
+                    KeyCode::Char('p') => state.show_projects = !state.show_projects,
+                    KeyCode::Char('{') => state.cycle_project(-1),
+                    KeyCode::Char('}') => state.cycle_project(1),
+                    KeyCode::Char('[') => state.cycle_tag_filter(-1),
+                    KeyCode::Char(']') => state.cycle_tag_filter(1),
+                    KeyCode::Tab => state.set_view(state.view.next()),
+                    KeyCode::BackTab => state.set_view(state.view.previous()),
+                    KeyCode::Char('?') => state.show_help = !state.show_help,
+                    KeyCode::Char(':') => {
+                        state.mode = Mode::Command;
+                        state.command_input.clear();
+                    }
+                    KeyCode::Char('e') => {
+                        if let Some(task) = state.selected_task().cloned() {
+                            state.mode = Mode::Edit;
+                            state.command_input.set(with_tags(&task.title, &task.tags));
+                            state.editing_task = Some(task.id);
                         }
-                        _ => {
+                    }
+                    KeyCode::Char('1') => set_priority(&mut state, Priority::Low),
+                    KeyCode::Char('2') => set_priority(&mut state, Priority::Medium),
+                    KeyCode::Char('3') => set_priority(&mut state, Priority::High),
+                    KeyCode::Char('g') => {
+                        if g_pressed {
+                            state.selected = 0;
                             g_pressed = false;
+                        } else {
+                            g_pressed = true;
                         }
                     }
+                    KeyCode::Char('G') => state.select_last(),
+                    KeyCode::Char('d') => delete_selected(&mut state),
+                    _ => {
+                        g_pressed = false;
+                    }
                 }
-                Mode::Command => {
-                    match key.code {
-                        KeyCode::Esc => {
-                            state.mode = Mode::Normal;
-                            state.command_input.clear();
-                            state.command_history.reset();
-                        }
-                        KeyCode::Enter => {
-                            state.command_history.add(state.command_input.clone());
-                            handle_command(&mut state, &ai).await?;
-                            state.mode = Mode::Normal;
-                            state.command_history.reset();
-                        }
-                        KeyCode::Up => {
-                            if let Some(cmd) = state.command_history.previous() {
-                                state.command_input = cmd;
-                            }
-                        }
-                        KeyCode::Down => {
-                            if let Some(cmd) = state.command_history.next() {
-                                state.command_input = cmd;
-                            } else {
-                                state.command_input.clear();
-                            }
-                        }
-                        KeyCode::Char(c) => {
-                            state.command_input.push(c);
-                        }
-                        KeyCode::Backspace => {
-                            state.command_input.pop();
+            }
+            Mode::Command => handle_command_key(&mut state, key, &ai),
+            Mode::Edit => {
+                match key.code {
+                    KeyCode::Esc => {
+                        state.mode = Mode::Normal;
+                        state.command_input.clear();
+                        state.editing_task = None;
+                    }
+                    KeyCode::Tab => state.complete_tag(),
+                    KeyCode::Enter => {
+                        let edited = state
+                            .editing_task
+                            .as_ref()
+                            .and_then(|id| state.tasks.iter().find(|t| &t.id == id))
+                            .cloned();
+                        if let Some(mut task) = edited {
+                            let (title, tags) = extract_tags(state.command_input.as_str());
+                            task.title = title;
+                            task.tags = tags;
+                            save_task(&mut state, task, "Saving task", None);
                         }
-                        _ => {}
+                        state.mode = Mode::Normal;
+                        state.command_input.clear();
+                        state.editing_task = None;
+                    }
+                    _ => {
+                        state.command_input.handle_key(key);
                     }
                 }
-                Mode::Edit => {
+            }
+            Mode::Detail => {
+                let page = state.list_height.max(1) as isize;
+                if let Some(detail) = state.detail.as_mut() {
                     match key.code {
-                        KeyCode::Esc => {
+                        KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => {
+                            state.detail = None;
                             state.mode = Mode::Normal;
-                            state.command_input.clear();
-                            state.editing_task = None;
                         }
-                        KeyCode::Enter => {
-                            if let (Some(task_id), Some(ref sb), Some(ref user)) = 
-                                (&state.editing_task, &state.supabase, &state.current_user) {
-                                if let Some(task) = state.tasks.iter_mut().find(|t| &t.id == task_id) {
-                                    task.title = state.command_input.clone();
-                                    let _ = sb.update_task(task).await;
-                                    if let Ok(tasks) = sb.get_tasks(&user.id).await {
-                                        state.tasks = tasks;
-                                    }
-                                }
-                            }
-                            state.mode = Mode::Normal;
+                        KeyCode::Char('j') | KeyCode::Down => detail.scroll_by(1),
+                        KeyCode::Char('k') | KeyCode::Up => detail.scroll_by(-1),
+                        KeyCode::PageDown => detail.scroll_by(page),
+                        KeyCode::PageUp => detail.scroll_by(-page),
+                        KeyCode::Char('G') => detail.scroll = usize::MAX,
+                        KeyCode::Char('g') => detail.scroll = 0,
+                        KeyCode::Char('c') => {
+                            state.mode = Mode::Comment;
                             state.command_input.clear();
-                            state.editing_task = None;
-                        }
-                        KeyCode::Char(c) => {
-                            state.command_input.push(c);
-                        }
-                        KeyCode::Backspace => {
-                            state.command_input.pop();
                         }
                         _ => {}
                     }
                 }
-                Mode::Login => {
-                    match key.code {
-                        KeyCode::Esc => {
-                            std::process::exit(0);
-                        }
-                        KeyCode::Enter => {
-                            let parts: Vec<&str> = state.command_input.split_whitespace().collect();
-                            if parts.len() == 2 {
-                                let username = parts[0];
-                                let password = parts[1];
-                                
-                                if let Some(ref sb) = state.supabase {
-                                    match sb.login(username, password).await {
-                                        Ok(user) => {
-                                            info!("User logged in: {}", user.username);
-                                            let user_id = user.id.clone();
-                                            state.current_user = Some(user);
-                                            state.mode = Mode::Normal;
-                                            
-                                            if let Some(ref sb) = state.supabase {
-                                                match sb.get_tasks(&user_id).await {
-                                                    Ok(tasks) => state.tasks = tasks,
-                                                    Err(e) => {
-                                                        error!("Failed to load tasks: {}", e);
-                                                        state.set_status("Failed to load tasks".to_string());
-                                                    }
-                                                }
-                                            }
-                                        }
-                                        Err(_) => {
-                                            state.command_input = "Login failed. Press : to register".to_string();
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                        KeyCode::Char(':') => {
-                            if state.command_input.is_empty() {
-                                state.mode = Mode::Register;
-                                state.command_input.clear();
-                            } else {
-                                state.command_input.push(':');
-                            }
-                        }
-                        KeyCode::Char(c) => {
-                            state.command_input.push(c);
-                        }
-                        KeyCode::Backspace => {
-                            state.command_input.pop();
-                        }
-                        _ => {}
+            }
+            Mode::Comment => {
+                match key.code {
+                    KeyCode::Esc => {
+                        state.mode = Mode::Detail;
+                        state.command_input.clear();
+                    }
+                    KeyCode::Enter => {
+                        add_comment(&mut state);
+                        state.mode = Mode::Detail;
+                        state.command_input.clear();
+                    }
+                    _ => {
+                        state.command_input.handle_key(key);
                     }
                 }
-                Mode::Register => {
-                    match key.code {
-                        KeyCode::Esc => {
-                            state.mode = Mode::Login;
-                            state.command_input.clear();
-                        }
-                        KeyCode::Enter => {
-                            let parts: Vec<&str> = state.command_input.split_whitespace().collect();
-                            if parts.len() == 3 {
-                                let username = parts[0];
-                                let password = parts[1];
-                                let email = parts[2];
-                                
-                                if let Some(ref sb) = state.supabase {
-                                    match sb.register(username, email, password).await {
-                                        Ok(user) => {
-                                            info!("User registered: {}", user.username);
-                                            state.current_user = Some(user);
-                                            state.mode = Mode::Normal;
-                                        }
-                                        Err(e) => {
-                                            state.command_input = format!("Registration failed: {}", e);
-                                        }
-                                    }
-                                }
-                            }
+            }
+            Mode::Preview => handle_preview_key(&mut state, key, &ai),
+            Mode::Breakdown => handle_breakdown_key(&mut state, key),
+            Mode::Plan => handle_plan_key(&mut state, key),
+            Mode::Chat => handle_chat_key(&mut state, key, &ai),
+            Mode::Dedupe => handle_dedupe_key(&mut state, key),
+            Mode::Login => {
+                match key.code {
+                    KeyCode::Esc => break,
+                    KeyCode::Enter => {
+                        let parts: Vec<String> = state.command_input.as_str().split_whitespace().map(str::to_string).collect();
+                        if let [username, password] = parts.as_slice() {
+                            login(&mut state, username.clone(), password.clone());
                         }
-                        KeyCode::Char(c) => {
-                            state.command_input.push(c);
+                    }
+                    KeyCode::Char(':') => {
+                        if state.command_input.is_empty() {
+                            state.mode = Mode::Register;
+                            state.command_input.clear();
+                        } else {
+                            state.command_input.insert_char(':');
                         }
-                        KeyCode::Backspace => {
-                            state.command_input.pop();
+                    }
+                    _ => {
+                        state.command_input.handle_key(key);
+                    }
+                }
+            }
+            Mode::Register => {
+                match key.code {
+                    KeyCode::Esc => {
+                        state.mode = Mode::Login;
+                        state.command_input.clear();
+                    }
+                    KeyCode::Enter => {
+                        let parts: Vec<String> = state.command_input.as_str().split_whitespace().map(str::to_string).collect();
+                        if let [username, password, email] = parts.as_slice() {
+                            register(&mut state, username.clone(), email.clone(), password.clone());
                         }
-                        _ => {}
+                    }
+                    _ => {
+                        state.command_input.handle_key(key);
                     }
                 }
             }
         }
     }
 
-    disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
     Ok(())
 }
 
-async fn handle_command(state: &mut AppState, ai: &AIAssistant) -> Result<(), Box<dyn std::error::Error>> {
-    let parts: Vec<&str> = state.command_input.trim().split_whitespace().collect();
+// Puts the terminal back the way the shell had it, however main returns
+struct TerminalGuard;
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore_terminal();
+    }
+}
+
+// Errors are ignored: this also runs while panicking, and there is nothing
+// better to do with them
+fn restore_terminal() {
+    let _ = disable_raw_mode();
+    let _ = execute!(io::stdout(), DisableMouseCapture, DisableBracketedPaste, LeaveAlternateScreen);
+}
+
+// `todo_tui db migrate` applies pending migrations using DATABASE_URL (a
+// direct Postgres connection string); `todo_tui db status` lists them.
+async fn run_db_command(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let database_url = std::env::var("DATABASE_URL")
+        .map_err(|_| "DATABASE_URL must be set to a Postgres connection string")?;
+
+    match args.first().map(String::as_str) {
+        Some("migrate") => {
+            let ran = migrations::migrate(&database_url).await?;
+            if ran.is_empty() {
+                println!("Database is up to date (version {})", migrations::latest());
+            }
+            for migration in ran {
+                println!("Applied {}", migration.name);
+            }
+        }
+        Some("status") => {
+            let pending = migrations::pending(&database_url).await?;
+            if pending.is_empty() {
+                println!("Database is up to date (version {})", migrations::latest());
+            }
+            for migration in pending {
+                println!("Pending {}", migration.name);
+            }
+        }
+        _ => return Err("Usage: todo_tui db <migrate|status>".into()),
+    }
+    Ok(())
+}
+
+// Refuse to start against a database whose schema this build doesn't match.
+// An unreachable server is let through; login reports that on its own.
+async fn check_schema(sb: &SupabaseClient) -> Result<(), Box<dyn std::error::Error>> {
+    let required = migrations::latest();
+    match sb.schema_version().await {
+        Ok(Some(version)) if version < required => Err(format!(
+            "Database schema is at version {} but this build needs {}; run `todo_tui db migrate`",
+            version, required
+        )
+        .into()),
+        Ok(Some(version)) if version > required => Err(format!(
+            "Database schema version {} is newer than this build supports ({}); upgrade todo_tui",
+            version, required
+        )
+        .into()),
+        Ok(Some(_)) => Ok(()),
+        Ok(None) => Err("Database schema is not versioned; run `todo_tui db migrate`".into()),
+        Err(e) => {
+            error!("Could not check the database schema version: {}", e);
+            Ok(())
+        }
+    }
+}
+
+// The backend handle and signed-in user's id, for handing to a background job
+fn session(state: &AppState) -> Option<(Arc<SupabaseClient>, String)> {
+    let sb = state.supabase.clone()?;
+    let user = state.current_user.as_ref()?;
+    Some((sb, user.id.clone()))
+}
+
+// Run a backend call without blocking the UI. When it finishes its message
+// (if any) goes to the status bar, a failure is reported there instead, and
+// `then` reloads whatever the call may have changed either way.
+fn background<F, T>(state: &mut AppState, label: &str, job: F, then: T)
+where
+    F: Future<Output = Result<Option<String>, AppError>> + Send + 'static,
+    T: FnOnce(&mut AppState) + Send + 'static,
+{
+    let context = label.to_string();
+    state.jobs.spawn(label, async move {
+        let result = job.await;
+        move |state: &mut AppState| {
+            match result {
+                Ok(Some(message)) => state.set_status(message),
+                Ok(None) => {}
+                Err(e) => {
+                    error!("{} failed: {}", context, e);
+                    state.set_status(e.user_message());
+                }
+            }
+            then(state);
+        }
+    });
+}
+
+fn login(state: &mut AppState, username: String, password: String) {
+    let Some(sb) = state.supabase.clone() else {
+        return;
+    };
+    // One attempt at a time
+    if state.jobs.is_busy() {
+        return;
+    }
+
+    state.jobs.spawn("Logging in", async move {
+        let result = sb.login(&username, &password).await;
+        move |state: &mut AppState| match result {
+            Ok(user) => {
+                info!("User logged in: {}", user.username);
+                state.current_user = Some(user);
+                state.mode = Mode::Normal;
+                state.command_input.clear();
+                refresh_tasks(state);
+                refresh_projects(state);
+            }
+            Err(AppError::Auth(_)) => {
+                state.command_input.set("Login failed. Press : to register");
+            }
+            Err(e) => {
+                error!("Login failed: {}", e);
+                state.command_input.set(e.user_message());
+            }
+        }
+    });
+}
+
+fn register(state: &mut AppState, username: String, email: String, password: String) {
+    let Some(sb) = state.supabase.clone() else {
+        return;
+    };
+    if state.jobs.is_busy() {
+        return;
+    }
+
+    state.jobs.spawn("Creating account", async move {
+        let result = sb.register(&username, &email, &password).await;
+        move |state: &mut AppState| match result {
+            Ok(user) => {
+                info!("User registered: {}", user.username);
+                state.current_user = Some(user);
+                state.mode = Mode::Normal;
+                state.command_input.clear();
+            }
+            Err(e) => {
+                state.command_input.set(format!("Registration failed: {}", e.user_message()));
+            }
+        }
+    });
+}
+
+fn handle_command(state: &mut AppState, ai: &Arc<AIAssistant>) {
+    let command = state.command_input.to_string();
+    let parts: Vec<&str> = command.split_whitespace().collect();
     if parts.is_empty() {
-        return Ok(());
+        return;
     }
 
     match parts[0] {
         "add" => {
             if parts.len() > 1 {
                 let input = parts[1..].join(" ");
-                let mut task = ai.parse_task(&input).await?;
-                
-                info!("Task created: {}", task.title);
-                
-                if let (Some(ref sb), Some(ref user)) = (&state.supabase, &state.current_user) {
-                    let max_position = state.tasks.iter().map(|t| t.position).max().unwrap_or(-1);
-                    task.position = max_position + 1;
-                    
-                    sb.create_task(&task, &user.id).await?;
-                    state.tasks = sb.get_tasks(&user.id).await?;
-                    state.set_status("Task added!".to_string());
+                let ai = Arc::clone(ai);
+                let projects = state.projects.clone();
+                let project_id = state.current_project().map(|p| p.id.clone());
+
+                // The parsed task is shown for review before anything is saved
+                state.jobs.spawn("Parsing task", async move {
+                    let result = ai.parse_task(&input, &projects).await;
+                    move |state: &mut AppState| match result {
+                        Ok(mut task) => {
+                            if task.project_id.is_none() {
+                                task.project_id = project_id;
+                            }
+                            state.pending_adds.push_back(task);
+                        }
+                        Err(e) => {
+                            error!("Failed to parse task: {}", e);
+                            state.set_status(e.user_message());
+                        }
+                    }
+                });
+            }
+        }
+        "breakdown" => match state.selected_task().cloned() {
+            Some(task) if !state.can_edit(&task) => {
+                state.set_status("Read-only: you are a viewer of this project".to_string());
+            }
+            Some(task) => {
+                let ai = Arc::clone(ai);
+                // The proposal is shown as a checklist before anything is saved
+                state.jobs.spawn("Breaking down task", async move {
+                    let result = ai.breakdown(&task).await;
+                    move |state: &mut AppState| match result {
+                        Ok(subtasks) => state.pending_breakdowns.push_back(Breakdown::new(task, subtasks)),
+                        Err(e) => {
+                            error!("Failed to break down task: {}", e);
+                            state.set_status(e.user_message());
+                        }
+                    }
+                });
+            }
+            None => state.set_status("Select a task to break down".to_string()),
+        },
+        "plan" => {
+            // The open tasks in the current project that the plan may change
+            let tasks: Vec<Task> = state
+                .tasks
+                .iter()
+                .filter(|t| !t.status.is_closed() && state.project_scope.matches(t) && state.can_edit(t))
+                .cloned()
+                .collect();
+            if tasks.is_empty() {
+                state.set_status("No open tasks to plan".to_string());
+            } else {
+                let ai = Arc::clone(ai);
+                state.jobs.spawn("Planning your day", async move {
+                    let result = ai.plan_day(&tasks).await;
+                    move |state: &mut AppState| match result {
+                        Ok(steps) => state.pending_plan = Some(DayPlan::new(steps)),
+                        Err(e) => {
+                            error!("Failed to plan the day: {}", e);
+                            state.set_status(e.user_message());
+                        }
+                    }
+                });
+            }
+        }
+        "dedupe" => {
+            let tasks = state.tasks.clone();
+            let ai = Arc::clone(ai);
+            state.jobs.spawn("Looking for duplicates", async move {
+                let groups = ai.duplicate_groups(&tasks).await;
+                move |state: &mut AppState| {
+                    if groups.is_empty() {
+                        state.set_status("No likely duplicates among the open tasks".to_string());
+                    } else {
+                        state.pending_duplicates = Some(Duplicates::new(groups));
+                    }
                 }
+            });
+        }
+        "chat" => {
+            state.chat.get_or_insert_with(ChatSession::new);
+            state.mode = Mode::Chat;
+            if parts.len() > 1 {
+                send_chat(state, ai, parts[1..].join(" "));
             }
         }
         "done" => {
-            if let (Some(task), Some(ref sb), Some(ref user)) = 
-                (state.tasks.get(state.selected), &state.supabase, &state.current_user) {
-                sb.toggle_done(&task.id, task.done).await?;
-                state.tasks = sb.get_tasks(&user.id).await?;
+            change_status(state, Status::Done);
+        }
+        "status" => {
+            // Accept "in progress" as well as "in_progress"
+            match Status::parse(&parts[1..].join(" ")) {
+                Some(status) => {
+                    change_status(state, status);
+                }
+                None => {
+                    let names: Vec<&str> = Status::ALL.iter().map(|s| s.as_str()).collect();
+                    state.set_status(format!("Usage: :status <{}>", names.join("|")));
+                }
+            }
+        }
+        "sync" => load_tasks(state, Some("Tasks synced!")),
+        "board" => {
+            if let Some(name) = parts.get(1) {
+                match BoardGroup::from_name(name) {
+                    Some(group) => state.board_group = group,
+                    None => {
+                        state.set_status(format!("Unknown board grouping: {}", name));
+                        state.command_input.clear();
+                        return;
+                    }
+                }
             }
+            state.set_view(View::Board);
+            state.board_column = 0;
         }
-        "sync" => {
-            if let (Some(ref sb), Some(ref user)) = (&state.supabase, &state.current_user) {
-                state.tasks = sb.get_tasks(&user.id).await?;
-                state.set_status("Tasks synced!".to_string());
+        "project" => handle_project_command(state, &parts[1..]),
+        "share" | "unshare" | "members" => handle_share_command(state, parts[0], &parts[1..]),
+        "assign" => match parts.get(1) {
+            Some(who) => assign_selected(state, Some(who)),
+            None => state.set_status("Usage: :assign <username|me>".to_string()),
+        },
+        "unassign" => assign_selected(state, None),
+        "tag" => match parts.get(1).copied() {
+            Some(action @ ("rename" | "merge")) if parts.len() == 4 => {
+                let (Some(from), Some(to)) = (normalize_tag(parts[2]), normalize_tag(parts[3])) else {
+                    state.set_status("Invalid tag name".to_string());
+                    state.command_input.clear();
+                    return;
+                };
+                let exists = |tag: &str| state.tasks.iter().any(|t| t.tags.iter().any(|x| x == tag));
+                if !exists(&from) {
+                    state.set_status(format!("No tasks tagged +{}", from));
+                } else if action == "rename" && exists(&to) {
+                    state.set_status(format!("+{} already exists; use :tag merge {} {}", to, from, to));
+                } else if action == "merge" && !exists(&to) {
+                    state.set_status(format!("No tasks tagged +{}; use :tag rename {} {}", to, from, to));
+                } else if let Some((sb, _)) = session(state) {
+                    state.jobs.spawn("Renaming tag", async move {
+                        let result = sb.rename_tag(&from, &to).await;
+                        move |state: &mut AppState| {
+                            match result {
+                                Ok(changed) => {
+                                    if state.tag_filter.as_deref() == Some(from.as_str()) {
+                                        state.tag_filter = Some(to.clone());
+                                    }
+                                    state.set_status(format!("+{} -> +{} on {} task(s)", from, to, changed));
+                                }
+                                Err(e) => {
+                                    error!("Failed to rename tag: {}", e);
+                                    state.set_status(e.user_message());
+                                }
+                            }
+                            refresh_tasks(state);
+                        }
+                    });
+                }
+            }
+            Some("rename" | "merge") => {
+                state.set_status("Usage: :tag rename <old> <new> | :tag merge <from> <into>".to_string());
+            }
+            Some(name) => {
+                state.tag_filter = normalize_tag(name);
+                state.selected = 0;
+            }
+            None => {
+                state.tag_filter = None;
+                state.selected = 0;
+            }
+        },
+        "sort" => {
+            match SortSpec::parse(&parts[1..].join(",")) {
+                Ok(spec) => {
+                    state.sort = spec;
+                    state.selected = 0;
+                    state.set_status(format!("Sorted by {}", state.sort));
+                }
+                Err(e) => state.set_status(e),
             }
         }
         "quit" | "q" => {
-            std::process::exit(0);
+            state.quit = true;
         }
         "config" => {
             if parts.len() == 1 {
@@ -434,7 +705,1272 @@ async fn handle_command(state: &mut AppState, ai: &AIAssistant) -> Result<(), Bo
         }
         _ => {}
     }
-    
+
     state.command_input.clear();
-    Ok(())
-}
\ No newline at end of file
+}
+
+// Keys while a Ctrl-r search is active. Enter and Esc end the search; Enter
+// leaves the accepted match in command_input for the caller to execute.
+// Command line keys, including Ctrl-R reverse search through the history
+fn handle_command_key(state: &mut AppState, key: KeyEvent, ai: &Arc<AIAssistant>) {
+    if state.history_search.is_some() {
+        handle_history_search(state, key);
+        if matches!(key.code, KeyCode::Enter) {
+            submit_command(state, ai);
+        }
+        return;
+    }
+
+    match key.code {
+        KeyCode::Esc => {
+            state.mode = Mode::Normal;
+            state.command_input.clear();
+            state.command_history.reset();
+        }
+        KeyCode::Enter => submit_command(state, ai),
+        KeyCode::Tab => state.complete_tag(),
+        KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            state.command_history.reset();
+            state.history_search = Some(HistorySearch::new(state.command_input.to_string()));
+        }
+        KeyCode::Up => {
+            if let Some(cmd) = state.command_history.previous(state.command_input.as_str()) {
+                state.command_input.set(cmd);
+            }
+        }
+        KeyCode::Down => {
+            if let Some(cmd) = state.command_history.next() {
+                state.command_input.set(cmd);
+            }
+        }
+        _ => {
+            if state.command_input.handle_key(key) {
+                state.command_history.reset();
+            }
+        }
+    }
+}
+
+// Run the typed command. Commands like :chat switch to a mode of their own,
+// so Normal mode is set first.
+fn submit_command(state: &mut AppState, ai: &Arc<AIAssistant>) {
+    state.command_history.add(state.command_input.to_string());
+    state.mode = Mode::Normal;
+    handle_command(state, ai);
+    state.command_history.reset();
+}
+
+fn handle_history_search(state: &mut AppState, key: KeyEvent) {
+    let Some(search) = state.history_search.as_mut() else {
+        return;
+    };
+
+    match key.code {
+        KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            if let Some(older) = state.command_history.search(&search.query, search.matched) {
+                search.matched = Some(older);
+            }
+        }
+        // Ctrl-C, Alt-B and the like aren't part of the query
+        KeyCode::Char(c) if !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {
+            search.query.push(c);
+            search.matched = state.command_history.search(&search.query, None);
+        }
+        KeyCode::Backspace => {
+            search.query.pop();
+            search.matched = state.command_history.search(&search.query, None);
+        }
+        KeyCode::Esc => {
+            state.command_input.set(search.original_input.clone());
+            state.history_search = None;
+        }
+        KeyCode::Enter | KeyCode::Right | KeyCode::Left => {
+            if let Some(cmd) = search.matched.and_then(|i| state.command_history.get(i)) {
+                state.command_input.set(cmd);
+            }
+            state.history_search = None;
+        }
+        _ => {}
+    }
+}
+
+fn refresh_tasks(state: &mut AppState) {
+    load_tasks(state, None);
+}
+
+// Reload the task list, along with usernames of new assignees. `synced` is
+// shown once the list arrives.
+fn load_tasks(state: &mut AppState, synced: Option<&'static str>) {
+    let Some((sb, user_id)) = session(state) else {
+        return;
+    };
+    let known = known_usernames(state);
+
+    state.jobs.spawn("Loading tasks", async move {
+        let result = sb.get_tasks(&user_id).await;
+        let names = match &result {
+            Ok(tasks) => fetch_usernames(&sb, &known, tasks.iter().filter_map(|t| t.assignee_id.clone())).await,
+            Err(_) => HashMap::new(),
+        };
+
+        move |state: &mut AppState| {
+            state.usernames.extend(names);
+            match result {
+                Ok(tasks) => {
+                    // Keep the same task selected across the reload
+                    let selected = state.selected_task().map(|t| t.id.clone());
+                    state.tasks = tasks;
+                    match selected {
+                        Some(id) => state.select_id(&id),
+                        None => state.clamp_selection(),
+                    }
+                    if let Some(message) = synced {
+                        state.set_status(message.to_string());
+                    }
+                }
+                Err(e) => {
+                    error!("Failed to load tasks: {}", e);
+                    if synced.is_some() {
+                        state.set_status(e.user_message());
+                    }
+                }
+            }
+        }
+    });
+}
+
+fn known_usernames(state: &AppState) -> HashSet<String> {
+    state.usernames.keys().cloned().collect()
+}
+
+// Look up usernames for ids not already known. A failure only costs the
+// names, so it is logged rather than reported.
+async fn fetch_usernames(
+    sb: &SupabaseClient,
+    known: &HashSet<String>,
+    ids: impl Iterator<Item = String>,
+) -> HashMap<String, String> {
+    let mut ids: Vec<String> = ids.filter(|id| !known.contains(id)).collect();
+    ids.sort();
+    ids.dedup();
+    if ids.is_empty() {
+        return HashMap::new();
+    }
+    match sb.get_usernames(&ids).await {
+        Ok(names) => names,
+        Err(e) => {
+            error!("Failed to load usernames: {}", e);
+            HashMap::new()
+        }
+    }
+}
+
+// Show the selected task with its activity log and comments. The view opens
+// at once and fills in when the activity arrives.
+fn open_detail(state: &mut AppState) {
+    let Some(task_id) = state.selected_task().map(|t| t.id.clone()) else {
+        return;
+    };
+    if state.supabase.is_none() {
+        return;
+    }
+
+    state.detail = Some(TaskDetail::loading(task_id.clone()));
+    state.mode = Mode::Detail;
+    load_activity(state, task_id, None);
+}
+
+fn add_comment(state: &mut AppState) {
+    let body = state.command_input.as_str().trim().to_string();
+    let Some(task_id) = state.detail.as_ref().map(|d| d.task_id.clone()) else {
+        return;
+    };
+    if body.is_empty() {
+        return;
+    }
+    load_activity(state, task_id, Some(body));
+}
+
+// Fetch a task's timeline into the open detail view, posting `comment`
+// first if given
+fn load_activity(state: &mut AppState, task_id: String, comment: Option<String>) {
+    let Some(sb) = state.supabase.clone() else {
+        return;
+    };
+    let known = known_usernames(state);
+    let label = if comment.is_some() { "Posting comment" } else { "Loading activity" };
+
+    state.jobs.spawn(label, async move {
+        let result = match comment {
+            Some(body) => match sb.add_comment(&task_id, &body).await {
+                Ok(()) => sb.get_activity(&task_id).await,
+                Err(e) => Err(e),
+            },
+            None => sb.get_activity(&task_id).await,
+        };
+        let names = match &result {
+            Ok(activity) => fetch_usernames(&sb, &known, activity.iter().filter_map(|a| a.user_id.clone())).await,
+            Err(_) => HashMap::new(),
+        };
+
+        move |state: &mut AppState| {
+            state.usernames.extend(names);
+            // The user may have closed the view or opened another task meanwhile
+            let detail = state.detail.as_mut().filter(|d| d.task_id == task_id);
+            match result {
+                Ok(activity) => {
+                    if let Some(detail) = detail {
+                        *detail = TaskDetail::new(task_id, activity);
+                    }
+                }
+                Err(e) => {
+                    if let Some(detail) = detail {
+                        detail.loading = false;
+                    }
+                    error!("Failed to load activity: {}", e);
+                    state.set_status(e.user_message());
+                }
+            }
+        }
+    });
+}
+
+// Keys in the review popup for a parsed `:add`. While a field is being
+// edited the keys go to the status-line editor instead.
+fn handle_preview_key(state: &mut AppState, key: KeyEvent, ai: &Arc<AIAssistant>) {
+    let Some(preview) = state.preview.as_mut() else {
+        state.mode = Mode::Normal;
+        return;
+    };
+
+    // Saving waits for the duplicate check, which Esc abandons along with the
+    // task; the check's result is dropped once the preview is gone
+    if preview.checking {
+        if key.code == KeyCode::Esc {
+            state.preview = None;
+            state.mode = Mode::Normal;
+            state.set_status("Task discarded".to_string());
+        }
+        return;
+    }
+    if let Some(similar) = preview.similar.clone() {
+        match key.code {
+            KeyCode::Char('a') | KeyCode::Char('y') | KeyCode::Enter => add_previewed(state),
+            KeyCode::Char('m') => merge_previewed(state, &similar.task_id),
+            KeyCode::Char('c') | KeyCode::Char('n') | KeyCode::Esc => {
+                state.preview = None;
+                state.mode = Mode::Normal;
+                state.set_status("Task discarded".to_string());
+            }
+            _ => {}
+        }
+        return;
+    }
+
+    if preview.editing {
+        match key.code {
+            KeyCode::Esc => {
+                preview.editing = false;
+                state.command_input.clear();
+            }
+            KeyCode::Enter => {
+                let field = preview.field();
+                match preview.set(field, state.command_input.as_str(), &state.projects) {
+                    Ok(()) => {
+                        preview.editing = false;
+                        state.command_input.clear();
+                    }
+                    Err(message) => state.set_status(message),
+                }
+            }
+            KeyCode::Tab if preview.field() == preview::Field::Tags => state.complete_tag(),
+            _ => {
+                state.command_input.handle_key(key);
+            }
+        }
+        return;
+    }
+
+    match key.code {
+        KeyCode::Char('j') | KeyCode::Down | KeyCode::Tab => preview.move_by(1),
+        KeyCode::Char('k') | KeyCode::Up | KeyCode::BackTab => preview.move_by(-1),
+        KeyCode::Char('e') | KeyCode::Char('i') => {
+            let text = preview.text(preview.field(), &state.projects);
+            preview.editing = true;
+            state.command_input.set(text);
+        }
+        KeyCode::Char('1') => preview.task.priority = Priority::Low,
+        KeyCode::Char('2') => preview.task.priority = Priority::Medium,
+        KeyCode::Char('3') => preview.task.priority = Priority::High,
+        KeyCode::Enter | KeyCode::Char('y') => check_duplicates(state, ai),
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('n') => {
+            state.preview = None;
+            state.mode = Mode::Normal;
+            state.set_status("Task discarded".to_string());
+        }
+        _ => {}
+    }
+}
+
+// Compare the reviewed task with the open tasks before saving it. When one
+// looks like the same task, the user decides between adding it anyway,
+// merging it into that one, or cancelling.
+fn check_duplicates(state: &mut AppState, ai: &Arc<AIAssistant>) {
+    let Some(preview) = state.preview.as_mut() else {
+        return;
+    };
+    preview.checking = true;
+    let task = preview.task.clone();
+    let tasks = state.tasks.clone();
+    let ai = Arc::clone(ai);
+
+    state.jobs.spawn("Checking for duplicates", async move {
+        let similar = ai.find_similar(&task, &tasks).await;
+        move |state: &mut AppState| {
+            let Some(preview) = state.preview.as_mut().filter(|p| p.checking && p.task.id == task.id) else {
+                return;
+            };
+            preview.checking = false;
+            match similar.into_iter().next() {
+                Some(similar) => preview.similar = Some(similar),
+                None => add_previewed(state),
+            }
+        }
+    });
+}
+
+fn add_previewed(state: &mut AppState) {
+    if let Some(preview) = state.preview.take() {
+        state.mode = Mode::Normal;
+        create_task(state, preview.task);
+    }
+}
+
+// Fold the reviewed task into the similar one instead of adding it
+fn merge_previewed(state: &mut AppState, into: &str) {
+    let Some(existing) = state.tasks.iter().find(|t| t.id == into).cloned() else {
+        // Gone in the meantime, so there is nothing to duplicate
+        add_previewed(state);
+        return;
+    };
+    if !state.can_edit(&existing) {
+        state.set_status("Read-only: you are a viewer of this project".to_string());
+        return;
+    }
+    if let Some(preview) = state.preview.take() {
+        state.mode = Mode::Normal;
+        let merged = preview.merge_into(&existing);
+        let message = format!("Merged into '{}'", existing.title);
+        save_task(state, merged, "Merging task", Some(message));
+    }
+}
+
+fn handle_dedupe_key(state: &mut AppState, key: KeyEvent) {
+    let Some(duplicates) = state.duplicates.as_mut() else {
+        state.mode = Mode::Normal;
+        return;
+    };
+
+    match key.code {
+        KeyCode::Char('j') | KeyCode::Down => duplicates.move_by(1),
+        KeyCode::Char('k') | KeyCode::Up => duplicates.move_by(-1),
+        KeyCode::Enter => {
+            let id = duplicates.selected_id().map(|id| id.to_string());
+            state.duplicates = None;
+            state.mode = Mode::Normal;
+            if let Some(id) = id {
+                // Widen the list when the task is hidden by the view or filters
+                if !state.visible().iter().any(|&i| state.tasks[i].id == id) {
+                    state.project_scope = ProjectScope::All;
+                    state.tag_filter = None;
+                    state.set_view(View::All);
+                }
+                state.select_id(&id);
+            }
+        }
+        KeyCode::Esc | KeyCode::Char('q') => {
+            state.duplicates = None;
+            state.mode = Mode::Normal;
+        }
+        _ => {}
+    }
+}
+
+// Keys in the checklist of subtasks proposed by `:breakdown`
+fn handle_breakdown_key(state: &mut AppState, key: KeyEvent) {
+    let Some(breakdown) = state.breakdown.as_mut() else {
+        state.mode = Mode::Normal;
+        return;
+    };
+
+    if breakdown.editing {
+        match key.code {
+            KeyCode::Esc => {
+                breakdown.editing = false;
+                state.command_input.clear();
+            }
+            KeyCode::Enter => match breakdown.set(state.command_input.as_str()) {
+                Ok(()) => {
+                    breakdown.editing = false;
+                    state.command_input.clear();
+                }
+                Err(message) => state.set_status(message),
+            },
+            _ => {
+                state.command_input.handle_key(key);
+            }
+        }
+        return;
+    }
+
+    match key.code {
+        KeyCode::Char('j') | KeyCode::Down | KeyCode::Tab => breakdown.move_by(1),
+        KeyCode::Char('k') | KeyCode::Up | KeyCode::BackTab => breakdown.move_by(-1),
+        KeyCode::Char(' ') | KeyCode::Char('x') => breakdown.toggle(),
+        KeyCode::Char('e') | KeyCode::Char('i') => {
+            let text = breakdown.text();
+            breakdown.editing = true;
+            state.command_input.set(text);
+        }
+        KeyCode::Enter | KeyCode::Char('y') => {
+            if let Some(breakdown) = state.breakdown.take() {
+                state.mode = Mode::Normal;
+                let subtasks = breakdown.accepted();
+                if subtasks.is_empty() {
+                    state.set_status("No subtasks selected".to_string());
+                } else {
+                    create_subtasks(state, &breakdown.parent.id, subtasks);
+                }
+            }
+        }
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('n') => {
+            state.breakdown = None;
+            state.mode = Mode::Normal;
+            state.set_status("Breakdown discarded".to_string());
+        }
+        _ => {}
+    }
+}
+
+// Keys while a suggested plan for the day is shown
+fn handle_plan_key(state: &mut AppState, key: KeyEvent) {
+    let Some(plan) = state.plan.as_mut() else {
+        state.mode = Mode::Normal;
+        return;
+    };
+
+    let apply = match key.code {
+        KeyCode::Char('j') | KeyCode::Down => {
+            plan.move_by(1);
+            None
+        }
+        KeyCode::Char('k') | KeyCode::Up => {
+            plan.move_by(-1);
+            None
+        }
+        KeyCode::Enter | KeyCode::Char('y') => Some((true, true)),
+        KeyCode::Char('o') => Some((true, false)),
+        KeyCode::Char('p') => Some((false, true)),
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('n') => {
+            state.plan = None;
+            state.mode = Mode::Normal;
+            state.set_status("Plan dismissed".to_string());
+            None
+        }
+        _ => None,
+    };
+    if let Some((order, priorities)) = apply {
+        if let Some(plan) = state.plan.take() {
+            state.mode = Mode::Normal;
+            apply_plan(state, &plan, order, priorities);
+        }
+    }
+}
+
+fn handle_chat_key(state: &mut AppState, key: KeyEvent, ai: &Arc<AIAssistant>) {
+    let page = state.list_height.max(1) as isize;
+    let Some(chat) = state.chat.as_mut() else {
+        state.mode = Mode::Normal;
+        return;
+    };
+
+    match key.code {
+        KeyCode::PageUp => chat.scroll_by(-page),
+        KeyCode::PageDown => chat.scroll_by(page),
+        // Nothing else until the proposed changes are applied or discarded
+        KeyCode::Enter | KeyCode::Char('y') if !chat.proposal.is_empty() => {
+            let changes = chat.resolve(true);
+            apply_chat_changes(state, changes);
+        }
+        KeyCode::Esc | KeyCode::Char('n') if !chat.proposal.is_empty() => {
+            chat.resolve(false);
+        }
+        _ if !chat.proposal.is_empty() => {}
+        KeyCode::Esc => {
+            state.mode = Mode::Normal;
+            state.command_input.clear();
+        }
+        KeyCode::Char('l') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            if chat.waiting {
+                state.set_status("Wait for the answer before starting over".to_string());
+            } else {
+                *chat = ChatSession::new();
+            }
+        }
+        KeyCode::Enter => {
+            let text = state.command_input.to_string().trim().to_string();
+            if chat.waiting {
+                state.set_status("Still thinking about the last message".to_string());
+            } else if !text.is_empty() {
+                state.command_input.clear();
+                send_chat(state, ai, text);
+            }
+        }
+        _ => {
+            state.command_input.handle_key(key);
+        }
+    }
+}
+
+// Send a chat message. The assistant works on a fresh copy of the tasks
+// from the server and only proposes changes; they are saved once the user
+// confirms them.
+fn send_chat(state: &mut AppState, ai: &Arc<AIAssistant>, text: String) {
+    let Some((sb, user_id)) = session(state) else {
+        return;
+    };
+    let Some(chat) = state.chat.as_mut() else {
+        return;
+    };
+    let history = chat.ask(text);
+    let ai = Arc::clone(ai);
+    let projects = state.projects.clone();
+    let workflow = state.config.workflow.clone();
+    let default_project = state.current_project().map(|p| p.id.clone());
+
+    state.jobs.spawn("Thinking", async move {
+        let result = match sb.get_tasks(&user_id).await {
+            Ok(tasks) => {
+                let context = ChatContext { tasks, projects, user_id, workflow, default_project };
+                ai.chat(history, &context).await
+            }
+            Err(e) => Err(e),
+        };
+        move |state: &mut AppState| {
+            let Some(chat) = state.chat.as_mut() else {
+                return;
+            };
+            match result {
+                Ok(reply) => chat.answer(reply),
+                Err(e) => {
+                    error!("Chat failed: {}", e);
+                    chat.failed(e.user_message());
+                }
+            }
+            if !matches!(state.mode, Mode::Chat) {
+                state.set_status("The assistant answered; :chat to see it".to_string());
+            }
+        }
+    });
+}
+
+// Save confirmed chat changes. New tasks go to the end of the list; the
+// list shows everything straight away and is reloaded afterwards.
+fn apply_chat_changes(state: &mut AppState, mut changes: Vec<TaskChange>) {
+    let Some((sb, user_id)) = session(state) else {
+        return;
+    };
+    let mut position = state.tasks.iter().map(|t| t.position).max().unwrap_or(-1) + 1;
+    for change in &mut changes {
+        match change {
+            TaskChange::Create(task) => {
+                task.position = position;
+                position += 1;
+                state.tasks.push(task.clone());
+            }
+            TaskChange::Update { after, .. } => {
+                if let Some(local) = state.tasks.iter_mut().find(|t| t.id == after.id) {
+                    *local = after.clone();
+                }
+            }
+            TaskChange::Delete(task) => state.tasks.retain(|t| t.id != task.id),
+        }
+    }
+    state.clamp_selection();
+    info!("Applying {} chat change(s)", changes.len());
+
+    let count = changes.len();
+    background(state, "Applying changes", async move {
+        for change in &changes {
+            match change {
+                TaskChange::Create(task) => sb.create_task(task, &user_id).await?,
+                TaskChange::Update { before, after } => sb.update_task(before, after).await?,
+                TaskChange::Delete(task) => sb.delete_task(&task.id).await?,
+            }
+        }
+        Ok(Some(format!("Applied {} change(s)", count)))
+    }, refresh_tasks);
+}
+
+// Move the planned tasks to the top of the list and/or give them the
+// suggested priorities, saving everything in one background job
+fn apply_plan(state: &mut AppState, plan: &DayPlan, order: bool, priorities: bool) {
+    let Some(sb) = state.supabase.clone() else {
+        return;
+    };
+    let editable: HashSet<String> = state.tasks.iter().filter(|t| state.can_edit(t)).map(|t| t.id.clone()).collect();
+
+    let moved = if order {
+        plan.reorder(&mut state.tasks, |t| editable.contains(&t.id))
+    } else {
+        Vec::new()
+    };
+    // Each as (before, after)
+    let reprioritized: Vec<(Task, Task)> = if priorities {
+        plan.priority_changes(&state.tasks)
+            .into_iter()
+            .filter(|t| editable.contains(&t.id))
+            .filter_map(|t| state.tasks.iter().find(|local| local.id == t.id).map(|local| (local.clone(), t)))
+            .collect()
+    } else {
+        Vec::new()
+    };
+    if moved.is_empty() && reprioritized.is_empty() {
+        state.set_status("The list already follows the plan".to_string());
+        return;
+    }
+
+    for (_, task) in &reprioritized {
+        if let Some(local) = state.tasks.iter_mut().find(|t| t.id == task.id) {
+            local.priority = task.priority;
+        }
+    }
+    state.tasks.sort_by_key(|t| t.position);
+    if let Some(first) = plan.steps.first() {
+        state.select_id(&first.task_id);
+    }
+
+    let mut message = format!("Plan applied: {} moved, {} reprioritized", moved.len(), reprioritized.len());
+    if order && !state.sort.is_manual() {
+        message.push_str(" (:sort manual shows the new order)");
+    }
+    background(state, "Applying plan", async move {
+        sb.update_positions(&moved).await?;
+        for (before, task) in &reprioritized {
+            sb.update_task(before, task).await?;
+        }
+        Ok(Some(message))
+    }, refresh_tasks);
+}
+
+// Save accepted subtasks right below their parent and any subtasks it
+// already has. Tasks further down move along to make room.
+fn create_subtasks(state: &mut AppState, parent_id: &str, mut subtasks: Vec<Task>) {
+    let Some((sb, user_id)) = session(state) else {
+        return;
+    };
+    let Some(after) = state
+        .tasks
+        .iter()
+        .filter(|t| t.id == parent_id || t.parent_id.as_deref() == Some(parent_id))
+        .map(|t| t.position)
+        .max()
+    else {
+        state.set_status("The task was deleted in the meantime".to_string());
+        return;
+    };
+
+    let count = subtasks.len() as i32;
+    let movable: Vec<bool> = state.tasks.iter().map(|t| t.position > after && state.can_edit(t)).collect();
+    let mut shifted = Vec::new();
+    for (task, _) in state.tasks.iter_mut().zip(movable).filter(|(_, movable)| *movable) {
+        task.position += count;
+        shifted.push(task.clone());
+    }
+    for (i, task) in subtasks.iter_mut().enumerate() {
+        task.position = after + 1 + i as i32;
+    }
+    state.tasks.extend(subtasks.iter().cloned());
+    state.tasks.sort_by_key(|t| t.position);
+    state.select_id(parent_id);
+    info!("{} subtask(s) created under {}", count, parent_id);
+
+    background(state, "Adding subtasks", async move {
+        sb.update_positions(&shifted).await?;
+        for task in &subtasks {
+            sb.create_task(task, &user_id).await?;
+        }
+        Ok(Some(format!("Added {} subtask(s)", count)))
+    }, refresh_tasks);
+}
+
+// Save a new task at the end of the list. It shows up straight away and the
+// list is reloaded once the server has answered.
+fn create_task(state: &mut AppState, mut task: Task) {
+    let Some((sb, user_id)) = session(state) else {
+        return;
+    };
+    task.position = state.tasks.iter().map(|t| t.position).max().unwrap_or(-1) + 1;
+    state.tasks.push(task.clone());
+    state.select_id(&task.id);
+    info!("Task created: {}", task.title);
+
+    background(state, "Adding task", async move {
+        sb.create_task(&task, &user_id).await?;
+        Ok(Some("Task added!".to_string()))
+    }, refresh_tasks);
+}
+
+// Keys that change the selected task, refused for tasks the user can't edit
+fn is_edit_key(state: &AppState, key: KeyEvent) -> bool {
+    if key.modifiers.contains(KeyModifiers::CONTROL) {
+        return false;
+    }
+    match key.code {
+        KeyCode::Char(' ' | 's' | 'e' | 'd' | 'J' | 'K' | '1' | '2' | '3') => true,
+        KeyCode::Char('H' | 'L') => state.view == View::Board,
+        KeyCode::Char('m') => state.view == View::Calendar,
+        _ => false,
+    }
+}
+
+// Save an edited copy of a task. The list shows the change straight away and
+// is reloaded once the server has answered.
+fn save_task(state: &mut AppState, task: Task, label: &str, message: Option<String>) {
+    let Some(sb) = state.supabase.clone() else {
+        return;
+    };
+    let Some(local) = state.tasks.iter_mut().find(|t| t.id == task.id) else {
+        return;
+    };
+    let before = std::mem::replace(local, task.clone());
+    state.select_id(&task.id);
+    if let Some(message) = message {
+        state.set_status(message);
+    }
+
+    background(state, label, async move {
+        sb.update_task(&before, &task).await?;
+        Ok(None)
+    }, refresh_tasks);
+}
+
+fn set_priority(state: &mut AppState, priority: Priority) {
+    if let Some(mut task) = state.selected_task().cloned() {
+        task.priority = priority;
+        save_task(state, task, "Updating priority", None);
+    }
+}
+
+fn delete_selected(state: &mut AppState) {
+    let Some(sb) = state.supabase.clone() else {
+        return;
+    };
+    let Some(task_id) = state.selected_task().map(|t| t.id.clone()) else {
+        return;
+    };
+    state.tasks.retain(|t| t.id != task_id);
+    state.clamp_selection();
+
+    background(state, "Deleting task", async move {
+        sb.delete_task(&task_id).await?;
+        Ok(None)
+    }, refresh_tasks);
+}
+
+// :assign <username|me> / :unassign for the selected task
+fn assign_selected(state: &mut AppState, who: Option<&str>) {
+    let (Some(sb), Some(user)) = (state.supabase.clone(), state.current_user.clone()) else {
+        return;
+    };
+    let Some(mut task) = state.selected_task().cloned() else {
+        return;
+    };
+    if !state.can_edit(&task) {
+        state.set_status("Read-only: you are a viewer of this project".to_string());
+        return;
+    }
+
+    let who = who.map(str::to_string);
+    let before = task.clone();
+    background(state, "Assigning", async move {
+        let assignee = match who.as_deref() {
+            None => None,
+            Some("me") => Some((user.id, user.username)),
+            Some(name) => match sb.find_user(name).await? {
+                Some(found) => Some((found.id, found.username)),
+                None => return Err(AppError::Validation(format!("No user named '{}'", name))),
+            },
+        };
+
+        task.assignee_id = assignee.as_ref().map(|(id, _)| id.clone());
+        sb.update_task(&before, &task).await?;
+        Ok(Some(match assignee {
+            Some((_, username)) => format!("Assigned to {}", username),
+            None => "Unassigned".to_string(),
+        }))
+    }, refresh_tasks);
+}
+
+// :share <username> [editor|viewer] | :unshare <username> | :members, for the current project
+fn handle_share_command(state: &mut AppState, action: &str, args: &[&str]) {
+    let Some(sb) = state.supabase.clone() else {
+        return;
+    };
+    let Some(project) = state.current_project().cloned() else {
+        state.set_status("Switch to a project first".to_string());
+        return;
+    };
+
+    if action == "members" {
+        background(state, "Loading members", async move {
+            let members = sb.get_members(&project.id).await?;
+            let ids: Vec<String> = members.iter().map(|m| m.user_id.clone()).collect();
+            let usernames = sb.get_usernames(&ids).await?;
+            let names: Vec<String> = members
+                .iter()
+                .map(|m| {
+                    let name = usernames.get(&m.user_id).map_or("?", String::as_str);
+                    format!("{} ({})", name, m.role.as_str())
+                })
+                .collect();
+            Ok(Some(format!("{}: {}", project.name, names.join(", "))))
+        }, |_| {});
+        return;
+    }
+
+    if project.role != Role::Owner {
+        state.set_status("Only the project owner can change sharing".to_string());
+        return;
+    }
+
+    let (Some(username), role) = (args.first(), args.get(1)) else {
+        state.set_status("Usage: :share <username> [editor|viewer] | :unshare <username>".to_string());
+        return;
+    };
+    let role = match role.map(|r| Role::parse(r)) {
+        None => Role::Editor,
+        Some(Some(role @ (Role::Editor | Role::Viewer))) => role,
+        Some(_) => {
+            state.set_status("Role must be editor or viewer".to_string());
+            return;
+        }
+    };
+
+    let username = username.to_string();
+    let share = action == "share";
+    background(state, "Updating sharing", async move {
+        let Some(member) = sb.find_user(&username).await? else {
+            return Err(AppError::Validation(format!("No user named '{}'", username)));
+        };
+        if member.id == project.owner_id {
+            return Err(AppError::Validation("The owner is always a member".to_string()));
+        }
+
+        if share {
+            sb.set_member(&ProjectMember {
+                project_id: project.id.clone(),
+                user_id: member.id.clone(),
+                role,
+            })
+            .await?;
+            Ok(Some(format!("Shared '{}' with {} as {}", project.name, member.username, role.as_str())))
+        } else {
+            sb.remove_member(&project.id, &member.id).await?;
+            Ok(Some(format!("Stopped sharing '{}' with {}", project.name, member.username)))
+        }
+    }, |_| {});
+}
+
+fn refresh_projects(state: &mut AppState) {
+    let Some((sb, user_id)) = session(state) else {
+        return;
+    };
+
+    state.jobs.spawn("Loading projects", async move {
+        let result = sb.get_projects(&user_id).await;
+        move |state: &mut AppState| match result {
+            Ok(projects) => {
+                state.projects = projects;
+                if state.current_project().is_none() && matches!(state.project_scope, ProjectScope::Project(_)) {
+                    state.project_scope = ProjectScope::All;
+                }
+            }
+            Err(e) => error!("Failed to load projects: {}", e),
+        }
+    });
+}
+
+// :project [name|inbox] | new <name> [color] | rename <name> | delete | move <name|inbox>
+fn handle_project_command(state: &mut AppState, args: &[&str]) {
+    let Some((sb, user_id)) = session(state) else {
+        return;
+    };
+
+    match args.first().copied() {
+        None => {
+            state.project_scope = ProjectScope::All;
+            state.selected = 0;
+        }
+        Some("new") if args.len() > 1 => {
+            // A trailing color name or #hex is taken as the project color
+            let (name_parts, color) = match args.last() {
+                Some(last) if args.len() > 2 && last.parse::<ratatui::style::Color>().is_ok() => {
+                    (&args[1..args.len() - 1], Some(last.to_string()))
+                }
+                _ => (&args[1..], None),
+            };
+            let name = name_parts.join(" ");
+            if state.projects.iter().any(|p| p.name.eq_ignore_ascii_case(&name)) {
+                state.set_status(format!("Project '{}' already exists", name));
+                return;
+            }
+
+            let mut project = Project::new(name, color, user_id.clone());
+            project.position = state.projects.iter().map(|p| p.position).max().unwrap_or(-1) + 1;
+            // Switch to it now; the reload drops it again if creating failed
+            state.projects.push(project.clone());
+            state.project_scope = ProjectScope::Project(project.id.clone());
+            state.selected = 0;
+
+            background(state, "Creating project", async move {
+                sb.create_project(&project, &user_id).await?;
+                Ok(Some(format!("Project '{}' created", project.name)))
+            }, refresh_projects);
+        }
+        Some("rename") if args.len() > 1 => {
+            let Some(mut project) = state.current_project().cloned() else {
+                state.set_status("Switch to a project first".to_string());
+                return;
+            };
+            project.name = args[1..].join(" ");
+            if let Some(local) = state.projects.iter_mut().find(|p| p.id == project.id) {
+                local.name = project.name.clone();
+            }
+
+            background(state, "Renaming project", async move {
+                sb.update_project(&project).await?;
+                Ok(Some(format!("Project renamed to '{}'", project.name)))
+            }, refresh_projects);
+        }
+        Some("delete") => {
+            let Some(project) = state.current_project().cloned() else {
+                state.set_status("Switch to a project first".to_string());
+                return;
+            };
+            state.project_scope = ProjectScope::All;
+
+            background(state, "Deleting project", async move {
+                sb.delete_project(&project.id).await?;
+                Ok(Some(format!("Project '{}' deleted; its tasks moved to Inbox", project.name)))
+            }, |state| {
+                refresh_projects(state);
+                refresh_tasks(state);
+            });
+        }
+        Some("move") if args.len() > 1 => {
+            let target = args[1..].join(" ");
+            let project_id = if target.eq_ignore_ascii_case("inbox") {
+                None
+            } else {
+                match resolve(&state.projects, &target) {
+                    Some(project) => Some(project.id.clone()),
+                    None => {
+                        state.set_status(format!("No project named '{}'", target));
+                        return;
+                    }
+                }
+            };
+            let Some(mut task) = state.selected_task().cloned() else {
+                return;
+            };
+            if !state.can_edit(&task) {
+                state.set_status("Read-only: you are a viewer of this project".to_string());
+                return;
+            }
+            task.project_id = project_id;
+            save_task(state, task, "Moving task", Some(format!("Moved to {}", target)));
+        }
+        Some("new" | "rename" | "move") => {
+            state.set_status("Usage: :project new <name> [color] | rename <name> | move <name|inbox>".to_string());
+        }
+        Some(_) => {
+            let name = args.join(" ");
+            if name.eq_ignore_ascii_case("inbox") {
+                state.project_scope = ProjectScope::Inbox;
+            } else if let Some(project) = resolve(&state.projects, &name) {
+                state.project_scope = ProjectScope::Project(project.id.clone());
+            } else {
+                state.set_status(format!("No project named '{}'", name));
+                return;
+            }
+            state.selected = 0;
+        }
+    }
+}
+
+fn toggle_selected(state: &mut AppState) {
+    if let Some(task) = state.selected_task() {
+        let target = if task.is_done() { Status::Todo } else { Status::Done };
+        change_status(state, target);
+    }
+}
+
+// Move the selected task to `status` if the configured workflow allows it
+fn change_status(state: &mut AppState, status: Status) -> bool {
+    let Some(task) = state.selected_task() else {
+        return false;
+    };
+    if !state.can_edit(task) {
+        state.set_status("Read-only: you are a viewer of this project".to_string());
+        return false;
+    }
+    let (id, current) = (task.id.clone(), task.status);
+
+    if !state.transition_allowed(current, status) {
+        return false;
+    }
+
+    let Some(sb) = state.supabase.clone() else {
+        return false;
+    };
+    if let Some(task) = state.tasks.iter_mut().find(|t| t.id == id) {
+        task.status = status;
+    }
+    state.select_id(&id);
+
+    background(state, "Updating status", async move {
+        sb.set_status(&id, current, status).await?;
+        Ok(None)
+    }, refresh_tasks);
+    true
+}
+
+// Shift+J/K. Under manual order this moves the task; sorted by priority it
+// raises or lowers the priority instead; other computed orders can't be
+// rearranged by hand.
+fn reorder(state: &mut AppState, delta: isize) {
+    if state.view == View::Agenda {
+        state.set_status("Agenda is ordered by due date".to_string());
+        return;
+    }
+
+    match state.sort.primary() {
+        SortKey::Manual => {
+            let from = state.selected;
+            let to = from as isize + delta;
+            if to < 0 || to as usize >= state.visible().len() {
+                return;
+            }
+            if move_task(state, from, to as usize) {
+                let direction = if delta < 0 { "up" } else { "down" };
+                state.set_status(format!("Task moved {}", direction));
+            }
+        }
+        SortKey::Priority => {
+            let Some(index) = state.selected_index() else {
+                return;
+            };
+            // Moving towards the top means "more important" when high
+            // priorities are listed first
+            let raise = (delta < 0) == state.sort.fields[0].descending;
+            let mut task = state.tasks[index].clone();
+            task.priority = match (task.priority, raise) {
+                (Priority::Low, true) => Priority::Medium,
+                (Priority::Medium, true) | (Priority::High, true) => Priority::High,
+                (Priority::High, false) => Priority::Medium,
+                (Priority::Medium, false) | (Priority::Low, false) => Priority::Low,
+            };
+            let message = format!("Priority set to {:?}", task.priority);
+            save_task(state, task, "Updating priority", Some(message));
+        }
+        _ => {
+            state.set_status(format!("Sorted by {} — use :sort manual to reorder", state.sort));
+        }
+    }
+}
+
+// Move the task at visible row `from` to row `to` and save the new order
+fn move_task(state: &mut AppState, from: usize, to: usize) -> bool {
+    let Some(sb) = state.supabase.clone() else {
+        return false;
+    };
+    let Some(changed) = state.move_row(from, to) else {
+        return false;
+    };
+
+    background(state, "Reordering", async move {
+        sb.update_positions(&changed).await?;
+        Ok(None)
+    }, refresh_tasks);
+    true
+}
+
+// Move the selected board card to the neighbouring column, updating the
+// field the board is grouped by
+fn move_card(state: &mut AppState, delta: isize) {
+    let columns = state.board_group.columns().len() as isize;
+    let target = state.board_column as isize + delta;
+    if target < 0 || target >= columns {
+        return;
+    }
+
+    let group = state.board_group;
+    let Some(index) = state.selected_index() else {
+        return;
+    };
+    let mut task = state.tasks[index].clone();
+    group.apply(&mut task, target as usize);
+
+    if !state.transition_allowed(state.tasks[index].status, task.status) {
+        return;
+    }
+
+    let message = format!("Moved to {}", group.columns()[target as usize]);
+    save_task(state, task, "Moving card", Some(message));
+}
+
+// Save the due date of the task dropped on the calendar cursor
+fn finish_reschedule(state: &mut AppState) {
+    let Some((id, original)) = state.rescheduling.take() else {
+        return;
+    };
+    let Some(local) = state.tasks.iter_mut().find(|t| t.id == id) else {
+        return;
+    };
+    // The task moved with the cursor; save_task compares against what the
+    // server still has
+    let task = local.clone();
+    local.due_date = original;
+
+    let message = format!("Rescheduled to {}", state.calendar_date.format("%a %b %d"));
+    save_task(state, task, "Rescheduling", Some(message));
+}
+
+// The checks keyboard edits go through in normal mode, for the selected
+// task; a refused edit says why in the status line
+fn mouse_edit_allowed(state: &mut AppState) -> bool {
+    if state.is_offline() {
+        state.set_status("Offline: changes can't be saved until the server responds again".to_string());
+        false
+    } else if state.selected_task().is_some_and(|t| !state.can_edit(t)) {
+        state.set_status("Read-only: you are a viewer of this project".to_string());
+        false
+    } else {
+        true
+    }
+}
+
+fn handle_mouse(state: &mut AppState, mouse: MouseEvent, area: Rect) {
+    let hit = ui::hit_test(area, state, mouse.column, mouse.row);
+
+    match mouse.kind {
+        MouseEventKind::ScrollDown => state.next(),
+        MouseEventKind::ScrollUp => state.previous(),
+        MouseEventKind::Down(MouseButton::Left) => match hit {
+            Some(Hit::Tab(view)) => state.set_view(view),
+            Some(Hit::Checkbox(row)) => {
+                state.selected = row;
+                if mouse_edit_allowed(state) {
+                    toggle_selected(state);
+                }
+            }
+            Some(Hit::Row(row)) => {
+                state.selected = row;
+                state.drag_from = Some(row);
+            }
+            None => {}
+        },
+        MouseEventKind::Drag(MouseButton::Left) => {
+            if let (Some(_), Some(Hit::Row(row) | Hit::Checkbox(row))) = (state.drag_from, hit) {
+                state.selected = row;
+            }
+        }
+        MouseEventKind::Up(MouseButton::Left) => {
+            if let Some(from) = state.drag_from.take() {
+                let to = state.selected;
+                if from == to {
+                    return;
+                }
+                // The dragged task stays selected unless it is moved
+                state.selected = from;
+                if !state.sort.is_manual() {
+                    state.set_status(format!("Sorted by {} — use :sort manual to reorder", state.sort));
+                } else if mouse_edit_allowed(state) && move_task(state, from, to) {
+                    state.set_status("Task moved".to_string());
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use config::{CommandHistory, Config};
+
+    fn press(state: &mut AppState, ai: &Arc<AIAssistant>, code: KeyCode) {
+        handle_command_key(state, KeyEvent::new(code, KeyModifiers::NONE), ai);
+    }
+
+    #[test]
+    fn commands_picked_from_history_search_keep_their_mode() {
+        let config = Config::default();
+        let ai = Arc::new(AIAssistant::from_settings(&config.ai_settings, &config.network));
+        let mut history = CommandHistory::new();
+        history.add("chat".to_string());
+        history.add("sort due".to_string());
+        let mut state = AppState::with_config(config, history);
+
+        state.mode = Mode::Command;
+        handle_command_key(&mut state, KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL), &ai);
+        for c in "ch".chars() {
+            press(&mut state, &ai, KeyCode::Char(c));
+        }
+        press(&mut state, &ai, KeyCode::Enter);
+
+        assert!(matches!(state.mode, Mode::Chat));
+        assert!(state.chat.is_some());
+        assert!(state.history_search.is_none());
+        // The accepted command moves to the front of the history
+        assert_eq!(state.command_history.previous(""), Some("chat".to_string()));
+    }
+
+    #[test]
+    fn history_search_ignores_control_and_alt_characters() {
+        let config = Config::default();
+        let ai = Arc::new(AIAssistant::from_settings(&config.ai_settings, &config.network));
+        let mut history = CommandHistory::new();
+        history.add("sort due".to_string());
+        let mut state = AppState::with_config(config, history);
+
+        state.mode = Mode::Command;
+        handle_command_key(&mut state, KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL), &ai);
+        press(&mut state, &ai, KeyCode::Char('s'));
+        handle_command_key(&mut state, KeyEvent::new(KeyCode::Char('a'), KeyModifiers::CONTROL), &ai);
+        handle_command_key(&mut state, KeyEvent::new(KeyCode::Char('b'), KeyModifiers::ALT), &ai);
+        // Shifted letters still type
+        handle_command_key(&mut state, KeyEvent::new(KeyCode::Char('O'), KeyModifiers::SHIFT), &ai);
+
+        assert_eq!(state.history_search.as_ref().map(|s| s.query.as_str()), Some("sO"));
+    }
+    #[test]
+    fn esc_abandons_a_task_waiting_for_the_duplicate_check() {
+        let config = Config::default();
+        let ai = Arc::new(AIAssistant::from_settings(&config.ai_settings, &config.network));
+        let mut state = AppState::with_config(config, CommandHistory::new());
+        let mut preview = preview::TaskPreview::new(Task::new("Buy milk".to_string()));
+        preview.checking = true;
+        state.preview = Some(preview);
+        state.mode = Mode::Preview;
+
+        // Other keys wait for the check
+        handle_preview_key(&mut state, KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE), &ai);
+        assert!(state.preview.is_some());
+
+        handle_preview_key(&mut state, KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE), &ai);
+        assert!(state.preview.is_none());
+        assert!(matches!(state.mode, Mode::Normal));
+    }
+}
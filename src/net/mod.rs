@@ -0,0 +1,262 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use chrono::{DateTime, Utc};
+use log::warn;
+use rand::Rng;
+use reqwest::header::RETRY_AFTER;
+use reqwest::{Client, IntoUrl, Method, Request, RequestBuilder, Response, StatusCode};
+use crate::config::NetworkSettings;
+use crate::core::error::{AppError, Result};
+
+// HTTP client shared by the database and AI layers. Every request has
+// timeouts, requests that are safe to repeat are retried with backoff, and
+// after repeated failures a circuit breaker refuses requests for a while so
+// the app works offline instead of waiting on a dead connection.
+pub struct HttpClient {
+    client: Client,
+    retry: RetryPolicy,
+    breaker: CircuitBreaker,
+}
+
+impl HttpClient {
+    pub fn new(settings: &NetworkSettings, request_timeout_secs: u64) -> Self {
+        let client = Client::builder()
+            .connect_timeout(Duration::from_secs(settings.connect_timeout_secs))
+            .timeout(Duration::from_secs(request_timeout_secs))
+            .build()
+            .unwrap_or_else(|e| {
+                warn!("Could not configure HTTP timeouts: {}", e);
+                Client::new()
+            });
+
+        Self {
+            client,
+            retry: RetryPolicy {
+                max_retries: settings.max_retries,
+                base: Duration::from_millis(settings.retry_base_ms),
+                max: Duration::from_millis(settings.retry_max_ms),
+            },
+            breaker: CircuitBreaker::new(
+                settings.failure_threshold,
+                Duration::from_secs(settings.offline_cooldown_secs),
+            ),
+        }
+    }
+
+    pub fn request<U: IntoUrl>(&self, method: Method, url: U) -> RequestBuilder {
+        self.client.request(method, url)
+    }
+
+    pub fn post<U: IntoUrl>(&self, url: U) -> RequestBuilder {
+        self.client.post(url)
+    }
+
+    // Send a request, retrying failures only when its method makes repeating
+    // it harmless
+    pub async fn send(&self, request: RequestBuilder) -> Result<Response> {
+        let request = request.build()?;
+        let idempotent = matches!(
+            *request.method(),
+            Method::GET | Method::HEAD | Method::OPTIONS | Method::PUT | Method::DELETE
+        );
+        self.execute(request, idempotent).await
+    }
+
+    // Send a request the caller knows is safe to repeat even though its
+    // method doesn't say so, such as a read-only RPC or an AI completion
+    pub async fn send_idempotent(&self, request: RequestBuilder) -> Result<Response> {
+        self.execute(request.build()?, true).await
+    }
+
+    // Whether requests are currently refused after repeated failures
+    pub fn is_offline(&self) -> bool {
+        self.breaker.is_open()
+    }
+
+    async fn execute(&self, mut request: Request, idempotent: bool) -> Result<Response> {
+        self.breaker.check()?;
+        let target = format!("{} {}", request.method(), request.url().path());
+
+        let mut attempt = 0;
+        loop {
+            // Streaming bodies can't be cloned and get a single attempt
+            let spare = request.try_clone();
+            let result = self.client.execute(request).await;
+
+            let (retryable, retry_after) = match &result {
+                Ok(response) if !is_transient(response.status()) => {
+                    self.breaker.record(true);
+                    return Ok(result?);
+                }
+                // 429 and 503 mean the server did nothing, so even a POST
+                // can be sent again
+                Ok(response) => (
+                    idempotent || matches!(response.status(), StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE),
+                    retry_after(response),
+                ),
+                Err(_) => (idempotent, None),
+            };
+
+            let delay = retry_after.unwrap_or_else(|| self.retry.backoff(attempt));
+            match spare {
+                // A Retry-After longer than our own limit is left to the user
+                Some(next) if retryable && attempt < self.retry.max_retries && delay <= self.retry.max => {
+                    warn!("{} failed (attempt {}), retrying in {:?}", target, attempt + 1, delay);
+                    tokio::time::sleep(delay).await;
+                    request = next;
+                    attempt += 1;
+                }
+                _ => {
+                    self.breaker.record(false);
+                    return Ok(result?);
+                }
+            }
+        }
+    }
+}
+
+// Statuses worth retrying: the server is overloaded or briefly unavailable
+fn is_transient(status: StatusCode) -> bool {
+    status == StatusCode::REQUEST_TIMEOUT || status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+fn retry_after(response: &Response) -> Option<Duration> {
+    let value = response.headers().get(RETRY_AFTER)?.to_str().ok()?;
+    parse_retry_after(value, Utc::now())
+}
+
+// Retry-After is either a number of seconds or an HTTP date
+pub fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    // A date in the past means "now"
+    Some((date.with_timezone(&Utc) - now).to_std().unwrap_or(Duration::ZERO))
+}
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    // Attempts after the first
+    pub max_retries: u32,
+    pub base: Duration,
+    pub max: Duration,
+}
+
+impl RetryPolicy {
+    // Delay before retry number `attempt` (from 0): the exponential backoff,
+    // capped at `max`, with the upper half randomized so clients that failed
+    // together don't retry together
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let ceiling = self.base.saturating_mul(2u32.saturating_pow(attempt)).min(self.max);
+        let half = ceiling / 2;
+        half + half.mul_f64(rand::thread_rng().gen_range(0.0..=1.0))
+    }
+}
+
+// Counts consecutive failed requests. At the threshold it opens and refuses
+// requests until the cooldown ends; the next request is then a trial that
+// closes it again on success or reopens it on failure.
+struct CircuitBreaker {
+    // 0 disables the breaker
+    threshold: u32,
+    cooldown: Duration,
+    state: Mutex<BreakerState>,
+}
+
+#[derive(Default)]
+struct BreakerState {
+    failures: u32,
+    open_until: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    fn new(threshold: u32, cooldown: Duration) -> Self {
+        Self { threshold, cooldown, state: Mutex::new(BreakerState::default()) }
+    }
+
+    fn check(&self) -> Result<()> {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        match state.open_until {
+            Some(until) if Instant::now() < until => {
+                let wait = until.saturating_duration_since(Instant::now()).as_secs() + 1;
+                Err(AppError::Offline(format!("the server isn't responding; trying again in {}s", wait)))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn is_open(&self) -> bool {
+        self.check().is_err()
+    }
+
+    fn record(&self, success: bool) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if success {
+            *state = BreakerState::default();
+            return;
+        }
+        state.failures += 1;
+        if self.threshold > 0 && state.failures >= self.threshold {
+            if state.open_until.is_none() {
+                warn!("{} requests failed in a row; going offline for {:?}", state.failures, self.cooldown);
+            }
+            state.open_until = Some(Instant::now() + self.cooldown);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn backoff_grows_with_jitter_up_to_the_cap() {
+        let policy = RetryPolicy {
+            max_retries: 5,
+            base: Duration::from_millis(100),
+            max: Duration::from_millis(1000),
+        };
+        for _ in 0..20 {
+            let first = policy.backoff(0);
+            assert!(first >= Duration::from_millis(50) && first <= Duration::from_millis(100));
+            let third = policy.backoff(2);
+            assert!(third >= Duration::from_millis(200) && third <= Duration::from_millis(400));
+            let late = policy.backoff(10);
+            assert!(late >= Duration::from_millis(500) && late <= Duration::from_millis(1000));
+        }
+    }
+
+    #[test]
+    fn retry_after_accepts_seconds_and_dates() {
+        let now = Utc.with_ymd_and_hms(2015, 10, 21, 7, 28, 0).unwrap();
+        assert_eq!(parse_retry_after("120", now), Some(Duration::from_secs(120)));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:30 GMT", now), Some(Duration::from_secs(30)));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:00:00 GMT", now), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    #[test]
+    fn breaker_opens_after_threshold_and_closes_on_success() {
+        let breaker = CircuitBreaker::new(3, Duration::from_secs(60));
+        breaker.record(false);
+        breaker.record(false);
+        assert!(!breaker.is_open());
+        breaker.record(false);
+        assert!(matches!(breaker.check(), Err(AppError::Offline(_))));
+
+        breaker.record(true);
+        assert!(!breaker.is_open());
+    }
+
+    #[test]
+    fn zero_threshold_never_opens() {
+        let breaker = CircuitBreaker::new(0, Duration::from_secs(60));
+        for _ in 0..10 {
+            breaker.record(false);
+        }
+        assert!(!breaker.is_open());
+    }
+}
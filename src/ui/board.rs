@@ -0,0 +1,120 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, List, ListItem, ListState},
+    Frame,
+};
+use crate::core::{Priority, Status, Task};
+use super::{scroll_offset, AppState};
+
+const STATUS_COLUMNS: [Status; 5] = [
+    Status::Todo,
+    Status::InProgress,
+    Status::Blocked,
+    Status::Waiting,
+    Status::Done,
+];
+
+// Which task field the board's columns are built from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BoardGroup {
+    Status,
+    Priority,
+}
+
+impl BoardGroup {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "status" => Some(BoardGroup::Status),
+            "priority" => Some(BoardGroup::Priority),
+            _ => None,
+        }
+    }
+
+    pub fn columns(&self) -> &'static [&'static str] {
+        match self {
+            BoardGroup::Status => &["Todo", "In Progress", "Blocked", "Waiting", "Done"],
+            BoardGroup::Priority => &["Low", "Medium", "High"],
+        }
+    }
+
+    // Cancelled tasks have no status column and are left off the board
+    pub fn column_of(&self, task: &Task) -> Option<usize> {
+        match self {
+            BoardGroup::Status => STATUS_COLUMNS.iter().position(|s| *s == task.status),
+            BoardGroup::Priority => Some(match task.priority {
+                Priority::Low => 0,
+                Priority::Medium => 1,
+                Priority::High => 2,
+            }),
+        }
+    }
+
+    // Update the task so it belongs in `column`
+    pub fn apply(&self, task: &mut Task, column: usize) {
+        match self {
+            BoardGroup::Status => {
+                if let Some(status) = STATUS_COLUMNS.get(column) {
+                    task.status = *status;
+                }
+            }
+            BoardGroup::Priority => {
+                task.priority = match column {
+                    0 => Priority::Low,
+                    2 => Priority::High,
+                    _ => Priority::Medium,
+                }
+            }
+        }
+    }
+}
+
+pub fn render_board(f: &mut Frame, area: Rect, state: &mut AppState) {
+    let columns = state.board_group.columns();
+    let areas = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(vec![Constraint::Ratio(1, columns.len() as u32); columns.len()])
+        .split(area);
+
+    state.list_height = area.height.saturating_sub(2) as usize;
+
+    for (col, name) in columns.iter().enumerate() {
+        let cards: Vec<&Task> = state.visible_in_column(col).into_iter().map(|i| &state.tasks[i]).collect();
+        let active = col == state.board_column;
+
+        let items: Vec<ListItem> = cards
+            .iter()
+            .enumerate()
+            .map(|(i, task)| {
+                let style = if active && i == state.selected {
+                    Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+                } else if task.status.is_closed() {
+                    Style::default().fg(Color::DarkGray)
+                } else {
+                    Style::default()
+                };
+                ListItem::new(format!("{} {}", task.status.symbol(), task.title)).style(style)
+            })
+            .collect();
+
+        let border_style = if active {
+            Style::default().fg(Color::Cyan)
+        } else {
+            Style::default()
+        };
+        let list = List::new(items).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(border_style)
+                .title(format!("{} ({})", name, cards.len())),
+        );
+
+        let mut list_state = ListState::default();
+        if active && !cards.is_empty() {
+            list_state = list_state
+                .with_offset(scroll_offset(state.selected, 0, state.list_height, cards.len()))
+                .with_selected(Some(state.selected));
+        }
+        f.render_stateful_widget(list, areas[col], &mut list_state);
+    }
+}
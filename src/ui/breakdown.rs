@@ -0,0 +1,219 @@
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+use crate::ai::breakdown::Subtask;
+use crate::core::{dates, Task};
+use super::preview::popup_area;
+use super::AppState;
+
+// One proposed subtask in the checklist
+#[derive(Debug, Clone, PartialEq)]
+pub struct BreakdownItem {
+    pub title: String,
+    pub estimate_minutes: Option<u32>,
+    // Unchecked items are dropped when the breakdown is accepted
+    pub keep: bool,
+}
+
+// Subtasks proposed by `:breakdown` for review before they are created
+#[derive(Debug, Clone)]
+pub struct Breakdown {
+    pub parent: Task,
+    pub items: Vec<BreakdownItem>,
+    pub selected: usize,
+    // The selected item is being edited in the status line
+    pub editing: bool,
+}
+
+impl Breakdown {
+    pub fn new(parent: Task, subtasks: Vec<Subtask>) -> Self {
+        let items = subtasks
+            .into_iter()
+            .map(|s| BreakdownItem {
+                title: s.title.trim().to_string(),
+                estimate_minutes: s.estimate_minutes.and_then(|m| u32::try_from(m).ok()),
+                keep: true,
+            })
+            .collect();
+        Self { parent, items, selected: 0, editing: false }
+    }
+
+    pub fn move_by(&mut self, delta: isize) {
+        let last = self.items.len() as isize - 1;
+        self.selected = (self.selected as isize + delta).clamp(0, last.max(0)) as usize;
+    }
+
+    pub fn toggle(&mut self) {
+        if let Some(item) = self.items.get_mut(self.selected) {
+            item.keep = !item.keep;
+        }
+    }
+
+    // The selected item as "Title (45m)"; `set` accepts the same format back
+    pub fn text(&self) -> String {
+        let Some(item) = self.items.get(self.selected) else {
+            return String::new();
+        };
+        match item.estimate_minutes {
+            Some(minutes) => format!("{} ({})", item.title, dates::format_duration(minutes)),
+            None => item.title.clone(),
+        }
+    }
+
+    // Replace the selected item from edited text. A trailing "(45m)" sets the
+    // estimate; a parenthesis that isn't a duration stays in the title.
+    pub fn set(&mut self, text: &str) -> Result<(), String> {
+        let text = text.trim();
+        let (title, estimate) = match text.strip_suffix(')').and_then(|t| t.rsplit_once('(')) {
+            Some((title, duration)) => match dates::parse_duration(duration) {
+                Some(minutes) => (title.trim(), Some(minutes)),
+                None => (text, None),
+            },
+            None => (text, None),
+        };
+        if title.is_empty() {
+            return Err("The title can't be empty".to_string());
+        }
+        if let Some(item) = self.items.get_mut(self.selected) {
+            item.title = title.to_string();
+            item.estimate_minutes = estimate;
+            item.keep = true;
+        }
+        Ok(())
+    }
+
+    // The checked items as new tasks under the parent. They share its
+    // project and priority; positions are left to the caller.
+    pub fn accepted(&self) -> Vec<Task> {
+        self.items
+            .iter()
+            .filter(|item| item.keep)
+            .map(|item| {
+                let mut task = Task::new(item.title.clone());
+                task.estimate_minutes = item.estimate_minutes;
+                task.priority = self.parent.priority;
+                task.project_id = self.parent.project_id.clone();
+                task.parent_id = Some(self.parent.id.clone());
+                task
+            })
+            .collect()
+    }
+
+    // Total estimate of the checked items, in minutes
+    fn total_minutes(&self) -> u32 {
+        self.items.iter().filter(|i| i.keep).filter_map(|i| i.estimate_minutes).sum()
+    }
+}
+
+pub fn render_breakdown(f: &mut Frame, area: Rect, state: &AppState) {
+    let Some(breakdown) = &state.breakdown else {
+        return;
+    };
+
+    let mut lines: Vec<Line> = breakdown
+        .items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let marker = if i == breakdown.selected { "› " } else { "  " };
+            let check = if item.keep { "[x] " } else { "[ ] " };
+            let style = if i == breakdown.selected {
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+            } else if item.keep {
+                Style::default()
+            } else {
+                Style::default().fg(Color::DarkGray).add_modifier(Modifier::CROSSED_OUT)
+            };
+            let mut spans = vec![Span::raw(marker), Span::raw(check), Span::styled(item.title.clone(), style)];
+            if let Some(minutes) = item.estimate_minutes {
+                spans.push(Span::styled(
+                    format!("  {}", dates::format_duration(minutes)),
+                    Style::default().fg(Color::DarkGray),
+                ));
+            }
+            Line::from(spans)
+        })
+        .collect();
+
+    let kept = breakdown.items.iter().filter(|i| i.keep).count();
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        format!("{} of {} selected, {} in total", kept, breakdown.items.len(), dates::format_duration(breakdown.total_minutes())),
+        Style::default().fg(Color::DarkGray),
+    )));
+
+    let popup = popup_area(area, 72, lines.len() as u16 + 2);
+    f.render_widget(Clear, popup);
+    f.render_widget(
+        Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan))
+                .title(format!("Break down: {}", breakdown.parent.title)),
+        ),
+        popup,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Priority;
+
+    fn breakdown() -> Breakdown {
+        let mut parent = Task::new("Prepare quarterly release".to_string());
+        parent.priority = Priority::High;
+        parent.project_id = Some("p1".to_string());
+        let subtasks = vec![
+            Subtask { title: "Freeze the branch".to_string(), estimate_minutes: Some(15) },
+            Subtask { title: "Write changelog".to_string(), estimate_minutes: Some(90) },
+            Subtask { title: "Tag the release".to_string(), estimate_minutes: None },
+        ];
+        Breakdown::new(parent, subtasks)
+    }
+
+    #[test]
+    fn edits_round_trip_through_the_text_format() {
+        let mut breakdown = breakdown();
+        breakdown.move_by(1);
+        assert_eq!(breakdown.text(), "Write changelog (1h30m)");
+
+        let cases = [
+            ("Write release notes (2h)", "Write release notes", Some(120)),
+            ("Write release notes", "Write release notes", None),
+            ("Update docs (website)", "Update docs (website)", None),
+            ("  Ping QA ( 45m ) ", "Ping QA", Some(45)),
+        ];
+        for (text, title, estimate) in cases {
+            breakdown.set(text).unwrap();
+            let item = &breakdown.items[1];
+            assert_eq!((item.title.as_str(), item.estimate_minutes), (title, estimate), "{:?}", text);
+        }
+        assert!(breakdown.set(" (30m)").is_err());
+        assert_eq!(breakdown.items[1].title, "Ping QA");
+    }
+
+    #[test]
+    fn only_kept_items_become_subtasks_of_the_parent() {
+        let mut breakdown = breakdown();
+        breakdown.move_by(2);
+        breakdown.toggle();
+        breakdown.move_by(10);
+        assert_eq!(breakdown.selected, 2);
+        assert_eq!(breakdown.total_minutes(), 105);
+
+        let tasks = breakdown.accepted();
+        let titles: Vec<&str> = tasks.iter().map(|t| t.title.as_str()).collect();
+        assert_eq!(titles, ["Freeze the branch", "Write changelog"]);
+        for task in &tasks {
+            assert_eq!(task.parent_id.as_deref(), Some(breakdown.parent.id.as_str()));
+            assert_eq!(task.project_id.as_deref(), Some("p1"));
+            assert_eq!(task.priority, Priority::High);
+        }
+        assert_eq!(tasks[1].estimate_minutes, Some(90));
+    }
+}
@@ -0,0 +1,191 @@
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, Utc};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+use crate::core::{dates, Task};
+use super::{render_list, scroll_offset, AppState};
+
+// Agenda sections, in display order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Bucket {
+    Overdue,
+    Today,
+    Tomorrow,
+    ThisWeek,
+    Later,
+}
+
+impl Bucket {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Bucket::Overdue => "Overdue",
+            Bucket::Today => "Today",
+            Bucket::Tomorrow => "Tomorrow",
+            Bucket::ThisWeek => "This week",
+            Bucket::Later => "Later",
+        }
+    }
+
+    pub fn of(due: NaiveDate, today: NaiveDate) -> Bucket {
+        let days = (due - today).num_days();
+        match days {
+            d if d < 0 => Bucket::Overdue,
+            0 => Bucket::Today,
+            1 => Bucket::Tomorrow,
+            d if d < 7 => Bucket::ThisWeek,
+            _ => Bucket::Later,
+        }
+    }
+}
+
+pub fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+pub fn local_date(due: DateTime<Utc>) -> NaiveDate {
+    due.with_timezone(&Local).date_naive()
+}
+
+// Move `due` to `date`, keeping its local time of day
+pub fn reschedule(due: Option<DateTime<Utc>>, date: NaiveDate) -> Option<DateTime<Utc>> {
+    let time = due.map_or_else(dates::default_due_time, |d| d.with_timezone(&Local).time());
+    dates::local_datetime(date, time)
+}
+
+// Short due-date label for list rows, and whether it is overdue
+pub fn due_label(due: DateTime<Utc>, today: NaiveDate) -> (String, bool) {
+    let date = local_date(due);
+    let label = match (date - today).num_days() {
+        0 => "today".to_string(),
+        1 => "tomorrow".to_string(),
+        -1 => "yesterday".to_string(),
+        d if (2..7).contains(&d) => date.format("%a").to_string(),
+        _ if date.year() == today.year() => date.format("%b %d").to_string(),
+        _ => date.format("%b %d %Y").to_string(),
+    };
+    (label, date < today)
+}
+
+pub fn render_agenda(f: &mut Frame, area: Rect, state: &mut AppState) {
+    let visible = state.visible();
+    let today = today();
+
+    // Interleave section headers with task rows, remembering which row holds
+    // the selected task so the viewport can follow it
+    let mut items = Vec::new();
+    let mut selected_row = 0;
+    let mut current = None;
+    for (i, &idx) in visible.iter().enumerate() {
+        let task = &state.tasks[idx];
+        let Some(due) = task.due_date else {
+            continue;
+        };
+        let bucket = Bucket::of(local_date(due), today);
+        if current != Some(bucket) {
+            current = Some(bucket);
+            let style = if bucket == Bucket::Overdue {
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)
+            };
+            items.push(ListItem::new(Line::from(Span::styled(bucket.label(), style))));
+        }
+
+        if i == state.selected {
+            selected_row = items.len();
+        }
+        let (label, _) = due_label(due, today);
+        let style = if i == state.selected {
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+        items.push(
+            ListItem::new(format!("  {} {:<10} {}", task.status.symbol(), label, task.title)).style(style),
+        );
+    }
+
+    let height = area.height.saturating_sub(2) as usize;
+    state.list_height = height;
+    state.list_offset = scroll_offset(selected_row, state.list_offset, height, items.len());
+
+    let list = List::new(items).block(Block::default().borders(Borders::ALL).title("Agenda"));
+    let mut list_state = ListState::default().with_offset(state.list_offset);
+    f.render_stateful_widget(list, area, &mut list_state);
+}
+
+pub fn render_calendar(f: &mut Frame, area: Rect, state: &mut AppState) {
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Length(7 * CELL_WIDTH + 2), Constraint::Min(20)])
+        .split(area);
+
+    render_month(f, chunks[0], state);
+
+    let title = match &state.rescheduling {
+        Some(_) => format!("{} — moving (Enter to drop, Esc to cancel)", state.calendar_date.format("%a %b %d")),
+        None => state.calendar_date.format("%A %B %d").to_string(),
+    };
+    render_list(f, chunks[1], state, &title);
+}
+
+const CELL_WIDTH: u16 = 6;
+
+fn render_month(f: &mut Frame, area: Rect, state: &AppState) {
+    let cursor = state.calendar_date;
+    let today = today();
+    let first = cursor.with_day(1).unwrap();
+    let grid_start = first - Duration::days(first.weekday().num_days_from_monday() as i64);
+
+    let mut lines = vec![
+        Line::from(Span::styled(
+            "  Mo    Tu    We    Th    Fr    Sa    Su",
+            Style::default().fg(Color::Yellow),
+        )),
+    ];
+
+    for week in 0..6 {
+        let mut spans = Vec::new();
+        for weekday in 0..7 {
+            let date = grid_start + Duration::days(week * 7 + weekday);
+            let tasks: Vec<&Task> = state
+                .tasks
+                .iter()
+                .filter(|t| t.due_date.map(local_date) == Some(date))
+                .collect();
+            let open = tasks.iter().filter(|t| !t.status.is_closed()).count();
+
+            let count = if tasks.is_empty() { String::new() } else { format!("·{}", tasks.len()) };
+            let text = format!("{:>2}{:<4}", date.day(), count);
+
+            let mut style = if date.month() != cursor.month() {
+                Style::default().fg(Color::DarkGray)
+            } else if date < today && open > 0 {
+                Style::default().fg(Color::Red)
+            } else if !tasks.is_empty() {
+                Style::default().fg(Color::Green)
+            } else {
+                Style::default()
+            };
+            if date == today {
+                style = style.add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+            }
+            if date == cursor {
+                style = style.add_modifier(Modifier::REVERSED);
+            }
+            spans.push(Span::styled(text, style));
+        }
+        lines.push(Line::from(spans));
+    }
+
+    let month = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(cursor.format("%B %Y").to_string()),
+    );
+    f.render_widget(month, area);
+}
@@ -0,0 +1,302 @@
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Wrap},
+    Frame,
+};
+use crate::ai::chat::{ChatReply, TaskChange};
+use crate::ai::provider::ChatMessage;
+use crate::core::{dates, Priority, Project, Task};
+use super::AppState;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Speaker {
+    User,
+    Assistant,
+    // Outcomes and errors, shown but never sent to the model
+    Note,
+}
+
+// The `:chat` conversation. It stays around when the chat is left with Esc
+// so it can be picked up again; Ctrl+L starts over.
+#[derive(Debug, Clone, Default)]
+pub struct ChatSession {
+    // Everything sent to the model so far, tool calls included
+    pub history: Vec<ChatMessage>,
+    pub transcript: Vec<(Speaker, String)>,
+    // Changes from the last answer, waiting for confirmation
+    pub proposal: Vec<TaskChange>,
+    // Set while the model is working on a message
+    pub waiting: bool,
+    // First transcript line shown; clamped while rendering
+    pub scroll: usize,
+    // What became of the last proposal, told to the model with the next
+    // message so it doesn't assume its changes were saved
+    outcome: Option<String>,
+}
+
+impl ChatSession {
+    pub fn new() -> Self {
+        Self { scroll: usize::MAX, ..Self::default() }
+    }
+
+    // Record the user's message and return the conversation to send
+    pub fn ask(&mut self, text: String) -> Vec<ChatMessage> {
+        let content = match &self.outcome {
+            Some(outcome) => format!("({})\n\n{}", outcome, text),
+            None => text.clone(),
+        };
+        self.transcript.push((Speaker::User, text));
+        self.history.push(ChatMessage::user(content));
+        self.waiting = true;
+        self.scroll = usize::MAX;
+        self.history.clone()
+    }
+
+    pub fn answer(&mut self, reply: ChatReply) {
+        self.history = reply.history;
+        if !reply.text.is_empty() {
+            self.transcript.push((Speaker::Assistant, reply.text));
+        } else if reply.changes.is_empty() {
+            self.transcript.push((Speaker::Assistant, "(no answer)".to_string()));
+        }
+        self.proposal = reply.changes;
+        self.outcome = None;
+        self.waiting = false;
+        self.scroll = usize::MAX;
+    }
+
+    // The message couldn't be answered; it is dropped from the history so
+    // the user can simply try again
+    pub fn failed(&mut self, message: String) {
+        self.history.pop();
+        self.transcript.push((Speaker::Note, message));
+        self.waiting = false;
+        self.scroll = usize::MAX;
+    }
+
+    // Close the pending proposal, returning its changes when they are to
+    // be saved
+    pub fn resolve(&mut self, apply: bool) -> Vec<TaskChange> {
+        let changes = std::mem::take(&mut self.proposal);
+        let (note, outcome) = if apply {
+            (format!("Applied {} change(s)", changes.len()), "The user applied the proposed changes.")
+        } else {
+            ("Changes discarded".to_string(), "The user discarded the proposed changes; nothing was saved.")
+        };
+        self.transcript.push((Speaker::Note, note));
+        self.outcome = Some(outcome.to_string());
+        self.scroll = usize::MAX;
+        if apply {
+            changes
+        } else {
+            Vec::new()
+        }
+    }
+
+    pub fn scroll_by(&mut self, delta: isize) {
+        self.scroll = self.scroll.saturating_add_signed(delta);
+    }
+}
+
+fn priority_label(priority: Priority) -> &'static str {
+    match priority {
+        Priority::Low => "low",
+        Priority::Medium => "medium",
+        Priority::High => "high",
+    }
+}
+
+fn project_label(projects: &[Project], id: Option<&String>) -> String {
+    id.and_then(|id| projects.iter().find(|p| &p.id == id))
+        .map_or("Inbox".to_string(), |p| p.name.clone())
+}
+
+fn due_label(task: &Task) -> String {
+    task.due_date.map_or("none".to_string(), dates::format_local)
+}
+
+fn tags_label(task: &Task) -> String {
+    if task.tags.is_empty() {
+        return "none".to_string();
+    }
+    task.tags.iter().map(|t| format!("+{}", t)).collect::<Vec<_>>().join(" ")
+}
+
+// A change as its marker, the task's title and one line per field it
+// sets or changes, e.g. ('~', "Deploy", ["due: 2030-01-04 17:00 → 2030-01-11 17:00"])
+pub fn describe_change(change: &TaskChange, projects: &[Project]) -> (char, String, Vec<String>) {
+    match change {
+        TaskChange::Create(task) => {
+            let mut fields = Vec::new();
+            if task.priority != Priority::Medium {
+                fields.push(format!("priority: {}", priority_label(task.priority)));
+            }
+            if task.due_date.is_some() {
+                fields.push(format!("due: {}", due_label(task)));
+            }
+            if let Some(minutes) = task.estimate_minutes {
+                fields.push(format!("estimate: {}", dates::format_duration(minutes)));
+            }
+            if !task.tags.is_empty() {
+                fields.push(format!("tags: {}", tags_label(task)));
+            }
+            if task.project_id.is_some() {
+                fields.push(format!("project: {}", project_label(projects, task.project_id.as_ref())));
+            }
+            ('+', task.title.clone(), fields)
+        }
+        TaskChange::Update { before, after } => {
+            let mut fields = Vec::new();
+            let mut diff = |name: &str, old: String, new: String| {
+                if old != new {
+                    fields.push(format!("{}: {} → {}", name, old, new));
+                }
+            };
+            diff("title", before.title.clone(), after.title.clone());
+            diff("status", before.status.label().to_string(), after.status.label().to_string());
+            diff("priority", priority_label(before.priority).to_string(), priority_label(after.priority).to_string());
+            diff("due", due_label(before), due_label(after));
+            diff("tags", tags_label(before), tags_label(after));
+            diff(
+                "project",
+                project_label(projects, before.project_id.as_ref()),
+                project_label(projects, after.project_id.as_ref()),
+            );
+            ('~', before.title.clone(), fields)
+        }
+        TaskChange::Delete(task) => ('-', task.title.clone(), Vec::new()),
+    }
+}
+
+pub fn render_chat(f: &mut Frame, area: Rect, state: &mut AppState) {
+    let Some(chat) = &state.chat else {
+        return;
+    };
+
+    let dim = Style::default().fg(Color::DarkGray);
+    let mut lines = Vec::new();
+    for (speaker, text) in &chat.transcript {
+        let (name, style) = match speaker {
+            Speaker::User => ("You", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+            Speaker::Assistant => ("Assistant", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)),
+            Speaker::Note => ("", dim),
+        };
+        for (i, row) in text.lines().enumerate() {
+            if *speaker == Speaker::Note {
+                lines.push(Line::from(Span::styled(format!("— {}", row), dim.add_modifier(Modifier::ITALIC))));
+            } else if i == 0 {
+                lines.push(Line::from(vec![Span::styled(format!("{}: ", name), style), Span::raw(row.to_string())]));
+            } else {
+                lines.push(Line::from(row.to_string()));
+            }
+        }
+        lines.push(Line::from(""));
+    }
+    if chat.waiting {
+        lines.push(Line::from(Span::styled("Thinking…", dim)));
+    }
+    if !chat.proposal.is_empty() {
+        lines.push(Line::from(Span::styled(
+            format!("Proposed changes ({}):", chat.proposal.len()),
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+        )));
+        for change in &chat.proposal {
+            let (marker, title, fields) = describe_change(change, &state.projects);
+            let color = match marker {
+                '+' => Color::Green,
+                '-' => Color::Red,
+                _ => Color::Yellow,
+            };
+            lines.push(Line::from(Span::styled(format!("{} {}", marker, title), Style::default().fg(color))));
+            for field in fields {
+                lines.push(Line::from(Span::styled(format!("    {}", field), dim)));
+            }
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled("y: apply all | n: discard", Style::default().fg(Color::Yellow))));
+    }
+    if lines.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "Ask about your tasks or tell me what to change, e.g. \"what's overdue?\"",
+            dim,
+        )));
+    }
+
+    let height = area.height.saturating_sub(2) as usize;
+    let width = area.width.saturating_sub(2).max(1) as usize;
+    // Long answers wrap, so count rendered rows rather than lines
+    let rows: usize = lines.iter().map(|line| line.width().max(1).div_ceil(width)).sum();
+    let max_scroll = rows.saturating_sub(height);
+
+    let Some(chat) = state.chat.as_mut() else {
+        return;
+    };
+    chat.scroll = chat.scroll.min(max_scroll);
+    f.render_widget(
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .scroll((chat.scroll as u16, 0))
+            .block(Block::default().borders(Borders::ALL).title("Chat — Esc: back, Ctrl+L: start over")),
+        area,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Status;
+
+    #[test]
+    fn changes_list_what_they_set() {
+        let work = Project::new("Work".to_string(), None, "me".to_string());
+        let mut before = Task::new("Deploy".to_string());
+        before.due_date = dates::parse_iso("2030-01-04T17:00");
+        before.tags = vec!["infra".to_string()];
+        let mut after = before.clone();
+        after.due_date = dates::parse_iso("2030-01-11T17:00");
+        after.status = Status::InProgress;
+        after.project_id = Some(work.id.clone());
+        let projects = [work];
+
+        let (marker, title, fields) = describe_change(&TaskChange::Update { before: Box::new(before.clone()), after }, &projects);
+        assert_eq!((marker, title.as_str()), ('~', "Deploy"));
+        assert_eq!(
+            fields,
+            [
+                format!("status: {} → {}", Status::Todo.label(), Status::InProgress.label()),
+                "due: 2030-01-04 17:00 → 2030-01-11 17:00".to_string(),
+                "project: Inbox → Work".to_string(),
+            ]
+        );
+
+        let (marker, _, fields) = describe_change(&TaskChange::Create(before.clone()), &projects);
+        assert_eq!(marker, '+');
+        assert_eq!(fields, ["due: 2030-01-04 17:00", "tags: +infra"]);
+        assert_eq!(describe_change(&TaskChange::Delete(before), &projects).0, '-');
+    }
+
+    #[test]
+    fn the_outcome_of_a_proposal_reaches_the_next_message() {
+        let mut chat = ChatSession::new();
+        chat.ask("drop the old ones".to_string());
+        chat.answer(ChatReply {
+            history: vec![ChatMessage::user("drop the old ones"), ChatMessage::assistant("Proposed.")],
+            text: "Proposed.".to_string(),
+            changes: vec![TaskChange::Delete(Task::new("Old".to_string()))],
+        });
+        assert!(chat.resolve(false).is_empty());
+        assert!(chat.proposal.is_empty());
+
+        // A failed message doesn't lose the outcome or stay in the history
+        chat.ask("and now?".to_string());
+        chat.failed("Network error".to_string());
+        assert_eq!(chat.history.len(), 2);
+
+        let sent = chat.ask("and now?".to_string());
+        let last = &sent.last().unwrap().content;
+        assert!(last.starts_with("(The user discarded the proposed changes"), "{}", last);
+        assert!(last.ends_with("and now?"));
+    }
+}
@@ -0,0 +1,114 @@
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+use super::preview::popup_area;
+use super::{calendar, AppState};
+
+// Groups of likely duplicates found by `:dedupe`. The selection runs
+// through the tasks of all groups in turn.
+#[derive(Debug, Clone)]
+pub struct Duplicates {
+    pub groups: Vec<Vec<String>>,
+    pub selected: usize,
+}
+
+impl Duplicates {
+    pub fn new(groups: Vec<Vec<String>>) -> Self {
+        Self { groups, selected: 0 }
+    }
+
+    fn len(&self) -> usize {
+        self.groups.iter().map(|g| g.len()).sum()
+    }
+
+    pub fn move_by(&mut self, delta: isize) {
+        let last = self.len() as isize - 1;
+        self.selected = (self.selected as isize + delta).clamp(0, last.max(0)) as usize;
+    }
+
+    pub fn selected_id(&self) -> Option<&str> {
+        self.groups.iter().flatten().nth(self.selected).map(|id| id.as_str())
+    }
+}
+
+pub fn render_duplicates(f: &mut Frame, area: Rect, state: &AppState) {
+    let Some(duplicates) = &state.duplicates else {
+        return;
+    };
+
+    let dim = Style::default().fg(Color::DarkGray);
+    let today = calendar::today();
+    let mut lines = Vec::new();
+    let mut index = 0;
+    // Line of the selected task, to scroll it into view
+    let mut selected_line = 0;
+    for (n, group) in duplicates.groups.iter().enumerate() {
+        if n > 0 {
+            lines.push(Line::from(""));
+        }
+        lines.push(Line::from(Span::styled(format!("Group {} ({} tasks)", n + 1, group.len()), dim)));
+        for id in group {
+            let selected = index == duplicates.selected;
+            index += 1;
+            let Some(task) = state.tasks.iter().find(|t| &t.id == id) else {
+                continue;
+            };
+            if selected {
+                selected_line = lines.len();
+            }
+            let style = if selected {
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+            let mut spans = vec![
+                Span::raw(if selected { "› " } else { "  " }),
+                Span::styled(task.title.clone(), style),
+                Span::styled(format!("  {}", task.status.label()), dim),
+            ];
+            if let Some(project) = task.project_id.as_ref().and_then(|id| state.projects.iter().find(|p| &p.id == id)) {
+                spans.push(Span::styled(format!("  @{}", project.name), dim));
+            }
+            if let Some(due) = task.due_date {
+                spans.push(Span::styled(format!("  due {}", calendar::due_label(due, today).0), dim));
+            }
+            lines.push(Line::from(spans));
+        }
+    }
+
+    let popup = popup_area(area, 80, lines.len() as u16 + 2);
+    let scroll = (selected_line + 1).saturating_sub(popup.height.saturating_sub(2) as usize);
+    f.render_widget(Clear, popup);
+    f.render_widget(
+        Paragraph::new(lines).wrap(Wrap { trim: false }).scroll((scroll as u16, 0)).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan))
+                .title(format!("Likely duplicates ({} groups)", duplicates.groups.len())),
+        ),
+        popup,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_selection_runs_through_all_groups() {
+        let ids = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        let mut duplicates = Duplicates::new(vec![ids(&["a", "b", "c"]), ids(&["d", "e"])]);
+        assert_eq!(duplicates.selected_id(), Some("a"));
+
+        duplicates.move_by(3);
+        assert_eq!(duplicates.selected_id(), Some("d"));
+        duplicates.move_by(10);
+        assert_eq!(duplicates.selected_id(), Some("e"));
+        duplicates.move_by(-10);
+        assert_eq!(duplicates.selected_id(), Some("a"));
+    }
+}
@@ -0,0 +1,175 @@
+use chrono::Local;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Wrap},
+    Frame,
+};
+use crate::core::activity::{Action, Activity};
+use crate::core::{dates, Priority, Task};
+use super::{calendar, AppState};
+
+// Task opened with Enter: its activity log and comments, oldest first
+#[derive(Debug, Clone)]
+pub struct TaskDetail {
+    pub task_id: String,
+    pub activity: Vec<Activity>,
+    // First timeline line shown; clamped while rendering
+    pub scroll: usize,
+    // Set while the activity is being fetched
+    pub loading: bool,
+}
+
+impl TaskDetail {
+    pub fn new(task_id: String, activity: Vec<Activity>) -> Self {
+        // Start at the newest entries
+        Self { task_id, activity, scroll: usize::MAX, loading: false }
+    }
+
+    // Opened straight away; the activity arrives from a background job
+    pub fn loading(task_id: String) -> Self {
+        Self { loading: true, ..Self::new(task_id, Vec::new()) }
+    }
+
+    pub fn scroll_by(&mut self, delta: isize) {
+        self.scroll = self.scroll.saturating_add_signed(delta);
+    }
+}
+
+pub fn render_detail(f: &mut Frame, area: Rect, state: &mut AppState) {
+    let Some(detail) = &state.detail else {
+        return;
+    };
+    let Some(task) = state.tasks.iter().find(|t| t.id == detail.task_id) else {
+        return;
+    };
+
+    let label = Style::default().fg(Color::DarkGray);
+    let field = |name: &str, value: String| {
+        Line::from(vec![Span::styled(format!("{:<10}", name), label), Span::raw(value)])
+    };
+
+    let project = task
+        .project_id
+        .as_ref()
+        .and_then(|id| state.projects.iter().find(|p| &p.id == id))
+        .map_or("Inbox".to_string(), |p| p.name.clone());
+    let assignee = task
+        .assignee_id
+        .as_ref()
+        .map_or("-".to_string(), |id| username(state, Some(id)));
+    let due = task
+        .due_date
+        .map_or("-".to_string(), |d| calendar::due_label(d, calendar::today()).0);
+    let tags: Vec<String> = task.tags.iter().map(|t| format!("+{}", t)).collect();
+
+    let mut info = vec![
+        Line::from(Span::styled(task.title.clone(), Style::default().add_modifier(Modifier::BOLD))),
+        Line::from(""),
+        field("Status", task.status.label().to_string()),
+        field("Priority", match task.priority {
+            Priority::Low => "Low",
+            Priority::Medium => "Medium",
+            Priority::High => "High",
+        }.to_string()),
+        field("Due", due),
+        field("Repeats", task.recurrence.map_or("-".to_string(), |r| r.to_string())),
+        field("Estimate", task.estimate_minutes.map_or("-".to_string(), dates::format_duration)),
+        field("Project", project),
+        field("Assignee", assignee),
+        field("Tags", if tags.is_empty() { "-".to_string() } else { tags.join(" ") }),
+        field("Created", task.created_at.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string()),
+    ];
+    if let Some(parent) = task.parent_id.as_ref().and_then(|id| state.tasks.iter().find(|t| &t.id == id)) {
+        info.push(field("Part of", parent.title.clone()));
+    }
+    let subtasks: Vec<&Task> = state.tasks.iter().filter(|t| t.parent_id.as_ref() == Some(&task.id)).collect();
+    if !subtasks.is_empty() {
+        let closed = subtasks.iter().filter(|t| t.status.is_closed()).count();
+        info.push(field("Subtasks", format!("{}/{} done", closed, subtasks.len())));
+    }
+    if !task.description.is_empty() {
+        info.push(Line::from(""));
+        info.push(Line::from(task.description.clone()));
+    }
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(info.len() as u16 + 2), Constraint::Min(3)])
+        .split(area);
+
+    f.render_widget(
+        Paragraph::new(info)
+            .wrap(Wrap { trim: false })
+            .block(Block::default().borders(Borders::ALL).title("Task")),
+        chunks[0],
+    );
+
+    let timeline: Vec<Line> = detail.activity.iter().map(|entry| timeline_line(state, entry)).collect();
+    let height = chunks[1].height.saturating_sub(2) as usize;
+    let width = chunks[1].width.saturating_sub(2).max(1) as usize;
+    // Long comments wrap, so count rendered rows rather than entries
+    let rows: usize = timeline.iter().map(|line| line.width().max(1).div_ceil(width)).sum();
+    let max_scroll = rows.saturating_sub(height);
+    let title = format!("Activity ({}) — c: comment, Esc: back", detail.activity.len());
+
+    let Some(detail) = state.detail.as_mut() else {
+        return;
+    };
+    detail.scroll = detail.scroll.min(max_scroll);
+
+    let body = if detail.loading {
+        vec![Line::from(Span::styled("Loading…", label))]
+    } else if timeline.is_empty() {
+        vec![Line::from(Span::styled("No activity yet", label))]
+    } else {
+        timeline
+    };
+    f.render_widget(
+        Paragraph::new(body)
+            .wrap(Wrap { trim: false })
+            .scroll((detail.scroll as u16, 0))
+            .block(Block::default().borders(Borders::ALL).title(title)),
+        chunks[1],
+    );
+}
+
+fn timeline_line(state: &AppState, entry: &Activity) -> Line<'static> {
+    let time = entry.created_at.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string();
+    let who = username(state, entry.user_id.as_ref());
+
+    let mut spans = vec![
+        Span::styled(format!("{}  ", time), Style::default().fg(Color::DarkGray)),
+        Span::styled(who, Style::default().fg(Color::Cyan)),
+        Span::raw(format!(" {}", entry.action.label())),
+    ];
+
+    let detail = entry.detail.as_deref().unwrap_or("");
+    let detail = match entry.action {
+        Action::Reassigned if detail.is_empty() => "unassigned".to_string(),
+        Action::Reassigned => format!("to {}", username(state, Some(&detail.to_string()))),
+        Action::Moved if detail.is_empty() => "to Inbox".to_string(),
+        Action::Moved => state
+            .projects
+            .iter()
+            .find(|p| p.id == detail)
+            .map_or("to another project".to_string(), |p| format!("to {}", p.name)),
+        _ => detail.to_string(),
+    };
+    if !detail.is_empty() {
+        let style = if entry.action == Action::Commented {
+            Style::default().fg(Color::White)
+        } else {
+            Style::default().fg(Color::Gray)
+        };
+        spans.push(Span::styled(format!(": {}", detail), style));
+    }
+    Line::from(spans)
+}
+
+fn username(state: &AppState, id: Option<&String>) -> String {
+    id.and_then(|id| state.usernames.get(id))
+        .cloned()
+        .unwrap_or_else(|| "someone".to_string())
+}
@@ -0,0 +1,401 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::fmt;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+// Single-line text input with a grapheme-aware cursor and readline-style keys.
+// `cursor` is a byte offset that always sits on a grapheme boundary.
+#[derive(Debug, Clone, Default)]
+pub struct LineEditor {
+    text: String,
+    cursor: usize,
+}
+
+impl LineEditor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.cursor = 0;
+    }
+
+    // Replace the contents and put the cursor at the end
+    pub fn set(&mut self, text: impl Into<String>) {
+        self.text = text.into();
+        self.cursor = self.text.len();
+    }
+
+    // Returns true when the key was consumed as an editing action
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+
+        match key.code {
+            KeyCode::Char('a') if ctrl => self.home(),
+            KeyCode::Char('e') if ctrl => self.end(),
+            KeyCode::Char('b') if ctrl => self.move_left(),
+            KeyCode::Char('f') if ctrl => self.move_right(),
+            KeyCode::Char('w') if ctrl => self.delete_word_back(),
+            KeyCode::Char('u') if ctrl => self.kill_to_start(),
+            KeyCode::Char('k') if ctrl => self.kill_to_end(),
+            KeyCode::Char('h') if ctrl => self.backspace(),
+            KeyCode::Char('d') if ctrl => self.delete(),
+            KeyCode::Char('b') if alt => self.word_left(),
+            KeyCode::Char('f') if alt => self.word_right(),
+            KeyCode::Char('d') if alt => self.delete_word_forward(),
+            KeyCode::Left if ctrl || alt => self.word_left(),
+            KeyCode::Right if ctrl || alt => self.word_right(),
+            KeyCode::Backspace if ctrl || alt => self.delete_word_back(),
+            KeyCode::Char(_) if ctrl => return false,
+            KeyCode::Char(c) => self.insert_char(c),
+            KeyCode::Backspace => self.backspace(),
+            KeyCode::Delete => self.delete(),
+            KeyCode::Left => self.move_left(),
+            KeyCode::Right => self.move_right(),
+            KeyCode::Home => self.home(),
+            KeyCode::End => self.end(),
+            _ => return false,
+        }
+        true
+    }
+
+    pub fn insert_char(&mut self, c: char) {
+        self.text.insert(self.cursor, c);
+        self.cursor += c.len_utf8();
+        // A combining mark may have merged into the previous grapheme
+        self.cursor = self.snap(self.cursor);
+    }
+
+    // Insert pasted text; line breaks collapse to spaces since inputs are single-line
+    pub fn insert_str(&mut self, s: &str) {
+        let cleaned: String = s
+            .chars()
+            .map(|c| if c == '\n' || c == '\t' { ' ' } else { c })
+            .filter(|c| *c != '\r' && !c.is_control())
+            .collect();
+        self.text.insert_str(self.cursor, &cleaned);
+        self.cursor += cleaned.len();
+    }
+
+    pub fn backspace(&mut self) {
+        let start = self.prev_boundary(self.cursor);
+        self.text.replace_range(start..self.cursor, "");
+        self.cursor = start;
+    }
+
+    pub fn delete(&mut self) {
+        let end = self.next_boundary(self.cursor);
+        self.text.replace_range(self.cursor..end, "");
+    }
+
+    pub fn move_left(&mut self) {
+        self.cursor = self.prev_boundary(self.cursor);
+    }
+
+    pub fn move_right(&mut self) {
+        self.cursor = self.next_boundary(self.cursor);
+    }
+
+    pub fn home(&mut self) {
+        self.cursor = 0;
+    }
+
+    pub fn end(&mut self) {
+        self.cursor = self.text.len();
+    }
+
+    pub fn word_left(&mut self) {
+        self.cursor = self.prev_word_start(self.cursor);
+    }
+
+    pub fn word_right(&mut self) {
+        self.cursor = self.next_word_end(self.cursor);
+    }
+
+    pub fn delete_word_back(&mut self) {
+        let start = self.prev_word_start(self.cursor);
+        self.text.replace_range(start..self.cursor, "");
+        self.cursor = start;
+    }
+
+    pub fn delete_word_forward(&mut self) {
+        let end = self.next_word_end(self.cursor);
+        self.text.replace_range(self.cursor..end, "");
+    }
+
+    pub fn kill_to_start(&mut self) {
+        self.text.replace_range(..self.cursor, "");
+        self.cursor = 0;
+    }
+
+    pub fn kill_to_end(&mut self) {
+        self.text.truncate(self.cursor);
+    }
+
+    // The whitespace-delimited word ending at the cursor
+    pub fn word_before_cursor(&self) -> &str {
+        let start = self.prev_word_boundary();
+        &self.text[start..self.cursor]
+    }
+
+    // Replace the word ending at the cursor, e.g. to complete it
+    pub fn replace_word_before_cursor(&mut self, replacement: &str) {
+        let start = self.prev_word_boundary();
+        self.text.replace_range(start..self.cursor, replacement);
+        self.cursor = start + replacement.len();
+    }
+
+    fn prev_word_boundary(&self) -> usize {
+        self.text[..self.cursor]
+            .char_indices()
+            .rev()
+            .find(|(_, c)| c.is_whitespace())
+            .map(|(i, c)| i + c.len_utf8())
+            .unwrap_or(0)
+    }
+
+    // Display width of the text before the cursor, in terminal cells
+    pub fn cursor_width(&self) -> usize {
+        self.text[..self.cursor].width()
+    }
+
+    // Visible portion of the text for a field `width` cells wide, scrolled so
+    // the cursor stays on screen, plus the cursor's column within that slice.
+    pub fn view(&self, width: usize) -> (&str, usize) {
+        let width = width.max(1);
+        let cursor_col = self.cursor_width();
+        if cursor_col < width {
+            return (&self.text, cursor_col);
+        }
+
+        // Drop leading graphemes until the cursor fits, keeping one cell spare
+        let mut skipped = 0;
+        let mut start = 0;
+        for (idx, g) in self.text.grapheme_indices(true) {
+            if cursor_col - skipped < width {
+                break;
+            }
+            skipped += g.width();
+            start = idx + g.len();
+        }
+        (&self.text[start..], cursor_col - skipped)
+    }
+
+    fn snap(&self, pos: usize) -> usize {
+        self.text
+            .grapheme_indices(true)
+            .map(|(i, g)| i + g.len())
+            .find(|end| *end >= pos)
+            .unwrap_or(self.text.len())
+    }
+
+    fn prev_boundary(&self, pos: usize) -> usize {
+        self.text[..pos]
+            .grapheme_indices(true)
+            .next_back()
+            .map(|(i, _)| i)
+            .unwrap_or(0)
+    }
+
+    fn next_boundary(&self, pos: usize) -> usize {
+        self.text[pos..]
+            .graphemes(true)
+            .next()
+            .map(|g| pos + g.len())
+            .unwrap_or(pos)
+    }
+
+    fn prev_word_start(&self, pos: usize) -> usize {
+        let before = &self.text[..pos];
+        let trimmed = before.trim_end();
+        trimmed
+            .char_indices()
+            .rev()
+            .find(|(_, c)| c.is_whitespace())
+            .map(|(i, c)| i + c.len_utf8())
+            .unwrap_or(0)
+    }
+
+    fn next_word_end(&self, pos: usize) -> usize {
+        let after = &self.text[pos..];
+        let skip = after.len() - after.trim_start().len();
+        after[skip..]
+            .char_indices()
+            .find(|(_, c)| c.is_whitespace())
+            .map(|(i, _)| pos + skip + i)
+            .unwrap_or(self.text.len())
+    }
+}
+
+impl fmt::Display for LineEditor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn editor(text: &str) -> LineEditor {
+        let mut editor = LineEditor::new();
+        editor.set(text);
+        editor
+    }
+
+    fn press(editor: &mut LineEditor, code: KeyCode, modifiers: KeyModifiers) -> bool {
+        editor.handle_key(KeyEvent::new(code, modifiers))
+    }
+
+    // The text with a '|' at the cursor
+    fn shown(editor: &LineEditor) -> String {
+        format!("{}|{}", &editor.text[..editor.cursor], &editor.text[editor.cursor..])
+    }
+
+    #[test]
+    fn the_cursor_moves_over_whole_graphemes() {
+        // "é" as e + combining acute, a family emoji joined with ZWJs, and CJK
+        let mut e = editor("ae\u{301}👨\u{200d}👩\u{200d}👧日本");
+        e.home();
+        e.move_right();
+        e.move_right();
+        assert_eq!(shown(&e), "ae\u{301}|👨\u{200d}👩\u{200d}👧日本");
+        e.move_right();
+        assert_eq!(shown(&e), "ae\u{301}👨\u{200d}👩\u{200d}👧|日本");
+        e.backspace();
+        assert_eq!(shown(&e), "ae\u{301}|日本");
+        e.delete();
+        assert_eq!(shown(&e), "ae\u{301}|本");
+        e.backspace();
+        assert_eq!(shown(&e), "a|本");
+
+        // Nothing to move past at either end
+        e.end();
+        e.move_right();
+        e.delete();
+        assert_eq!(shown(&e), "a本|");
+        e.home();
+        e.move_left();
+        e.backspace();
+        assert_eq!(shown(&e), "|a本");
+    }
+
+    #[test]
+    fn a_typed_combining_mark_joins_the_character_before_it() {
+        let mut e = editor("cafe");
+        e.insert_char('\u{301}');
+        assert_eq!(shown(&e), "cafe\u{301}|");
+        e.move_left();
+        assert_eq!(shown(&e), "caf|e\u{301}");
+
+        // In the middle of the text too
+        let mut e = editor("ab");
+        e.home();
+        e.move_right();
+        e.insert_char('\u{308}');
+        assert_eq!(shown(&e), "a\u{308}|b");
+    }
+
+    #[test]
+    fn word_motions_stop_at_whitespace() {
+        let mut e = editor("add  milk 牛乳 ");
+        e.word_left();
+        assert_eq!(shown(&e), "add  milk |牛乳 ");
+        e.word_left();
+        assert_eq!(shown(&e), "add  |milk 牛乳 ");
+        e.word_left();
+        e.word_left();
+        assert_eq!(shown(&e), "|add  milk 牛乳 ");
+        e.word_right();
+        assert_eq!(shown(&e), "add|  milk 牛乳 ");
+        e.word_right();
+        assert_eq!(shown(&e), "add  milk| 牛乳 ");
+        e.word_right();
+        e.word_right();
+        assert_eq!(shown(&e), "add  milk 牛乳 |");
+    }
+
+    #[test]
+    fn readline_kills() {
+        let mut e = editor("buy oat milk");
+        assert!(press(&mut e, KeyCode::Char('w'), KeyModifiers::CONTROL));
+        assert_eq!(shown(&e), "buy oat |");
+        assert!(press(&mut e, KeyCode::Backspace, KeyModifiers::ALT));
+        assert_eq!(shown(&e), "buy |");
+
+        let mut e = editor("buy oat milk");
+        press(&mut e, KeyCode::Char('a'), KeyModifiers::CONTROL);
+        press(&mut e, KeyCode::Char('f'), KeyModifiers::ALT);
+        assert_eq!(shown(&e), "buy| oat milk");
+        press(&mut e, KeyCode::Char('d'), KeyModifiers::ALT);
+        assert_eq!(shown(&e), "buy| milk");
+        press(&mut e, KeyCode::Char('k'), KeyModifiers::CONTROL);
+        assert_eq!(shown(&e), "buy|");
+
+        let mut e = editor("buy oat milk");
+        press(&mut e, KeyCode::Left, KeyModifiers::CONTROL);
+        press(&mut e, KeyCode::Char('u'), KeyModifiers::CONTROL);
+        assert_eq!(shown(&e), "|milk");
+
+        // Unbound Ctrl keys are left to the caller instead of typed
+        assert!(!press(&mut e, KeyCode::Char('r'), KeyModifiers::CONTROL));
+        assert_eq!(e.as_str(), "milk");
+    }
+
+    #[test]
+    fn pasted_text_stays_on_one_line() {
+        let mut e = editor("note: ");
+        e.insert_str("first\r\nsecond\tthird\u{7}");
+        assert_eq!(shown(&e), "note: first second third|");
+
+        e.home();
+        e.insert_str("日本 ");
+        assert_eq!(shown(&e), "日本 |note: first second third");
+    }
+
+    #[test]
+    fn the_word_before_the_cursor_can_be_completed() {
+        let mut e = editor("buy milk +erra");
+        assert_eq!(e.word_before_cursor(), "+erra");
+        e.replace_word_before_cursor("+errands");
+        assert_eq!(shown(&e), "buy milk +errands|");
+
+        let mut e = editor("+日本");
+        assert_eq!(e.word_before_cursor(), "+日本");
+        e.replace_word_before_cursor("+日本語");
+        assert_eq!(shown(&e), "+日本語|");
+    }
+
+    #[test]
+    fn view_scrolls_to_keep_the_cursor_visible() {
+        // Fits: nothing scrolls and the cursor is at the end
+        assert_eq!(editor("buy milk").view(10), ("buy milk", 8));
+        // Combining marks take no cell
+        assert_eq!(editor("cafe\u{301}").view(10), ("cafe\u{301}", 4));
+
+        // One cell is kept free for the cursor itself
+        assert_eq!(editor("abcdef").view(6), ("bcdef", 5));
+
+        // Wide characters scroll by whole characters
+        let e = editor("日本語テキスト");
+        assert_eq!(e.cursor_width(), 14);
+        assert_eq!(e.view(6), ("スト", 4));
+        assert_eq!(e.view(7), ("キスト", 6));
+
+        // The cursor at the start shows the start, whatever the length
+        let mut e = editor("日本語テキスト");
+        e.home();
+        assert_eq!(e.view(4), ("日本語テキスト", 0));
+        assert_eq!(editor("").view(0), ("", 0));
+    }
+}
@@ -0,0 +1,124 @@
+use std::future::Future;
+use std::time::Instant;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use super::AppState;
+
+// Change to apply to the state once a background job has finished
+pub type Update = Box<dyn FnOnce(&mut AppState) + Send>;
+
+pub struct Finished {
+    id: u64,
+    update: Update,
+}
+
+// Network work (database and AI calls) running off the UI loop. Each job
+// resolves to an Update that the loop applies when it arrives, so the
+// terminal keeps redrawing and taking keys while requests are in flight.
+pub struct Jobs {
+    sender: UnboundedSender<Finished>,
+    receiver: Option<UnboundedReceiver<Finished>>,
+    // Labels of the jobs still running, oldest first
+    running: Vec<(u64, String)>,
+    next_id: u64,
+    started: Instant,
+}
+
+const SPINNER: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+impl Jobs {
+    pub fn new() -> Self {
+        let (sender, receiver) = unbounded_channel();
+        Self {
+            sender,
+            receiver: Some(receiver),
+            running: Vec::new(),
+            next_id: 0,
+            started: Instant::now(),
+        }
+    }
+
+    // The event loop takes the receiving end once at startup
+    pub fn take_receiver(&mut self) -> Option<UnboundedReceiver<Finished>> {
+        self.receiver.take()
+    }
+
+    pub fn spawn<F, U>(&mut self, label: &str, job: F)
+    where
+        F: Future<Output = U> + Send + 'static,
+        U: FnOnce(&mut AppState) + Send + 'static,
+    {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.running.push((id, label.to_string()));
+
+        let sender = self.sender.clone();
+        let label = label.to_string();
+        tokio::spawn(async move {
+            // A panicking job still has to leave the running list
+            let update: Update = match tokio::spawn(job).await {
+                Ok(update) => Box::new(update),
+                Err(e) => {
+                    log::error!("{} failed: {}", label, e);
+                    Box::new(move |state: &mut AppState| state.set_status(format!("{} failed", label)))
+                }
+            };
+            let _ = sender.send(Finished { id, update });
+        });
+    }
+
+    pub fn is_busy(&self) -> bool {
+        !self.running.is_empty()
+    }
+
+    // Spinner frame and the newest job's label, for the status bar
+    pub fn indicator(&self) -> Option<String> {
+        let (_, label) = self.running.last()?;
+        let frame = (self.started.elapsed().as_millis() / 80) as usize % SPINNER.len();
+        let more = match self.running.len() {
+            1 => String::new(),
+            n => format!(" (+{})", n - 1),
+        };
+        Some(format!("{} {}…{}", SPINNER[frame], label, more))
+    }
+}
+
+impl Default for Jobs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Apply a finished job's result
+pub fn apply(state: &mut AppState, finished: Finished) {
+    state.jobs.running.retain(|(id, _)| *id != finished.id);
+    (finished.update)(state);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{CommandHistory, Config};
+
+    #[tokio::test]
+    async fn results_are_applied_as_messages() {
+        let mut state = AppState::with_config(Config::default(), CommandHistory::new());
+        let mut receiver = state.jobs.take_receiver().unwrap();
+
+        state.jobs.spawn("Loading", async { |state: &mut AppState| state.set_status("loaded".to_string()) });
+        state.jobs.spawn("Saving", async {
+            if true {
+                panic!("boom");
+            }
+            |_: &mut AppState| {}
+        });
+        assert!(state.jobs.indicator().unwrap().contains("Saving… (+1)"));
+
+        for _ in 0..2 {
+            let finished = receiver.recv().await.unwrap();
+            apply(&mut state, finished);
+        }
+        assert!(!state.jobs.is_busy());
+        assert!(state.jobs.indicator().is_none());
+        assert!(matches!(state.status_message.as_deref(), Some("loaded" | "Saving failed")));
+    }
+}
@@ -1,61 +1,572 @@
 use ratatui::{
-    layout::{Constraint, Direction, Layout, Alignment},
+    layout::{Constraint, Direction, Layout, Alignment, Margin, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, Paragraph},
+    widgets::{
+        block::Title, Block, Borders, List, ListItem, ListState, Paragraph, Scrollbar,
+        ScrollbarOrientation, ScrollbarState, Tabs,
+    },
     Frame,
 };
-use crate::core::{Task, Priority};
+use std::collections::{HashMap, VecDeque};
+use std::rc::Rc;
+use std::sync::Arc;
+use crate::core::{Task, Priority, Project, Role, Status};
+use crate::core::sort::SortSpec;
+use crate::core::tags;
+use unicode_width::UnicodeWidthStr;
 
-use crate::config::{Config, CommandHistory};
+pub mod board;
+pub mod breakdown;
+pub mod calendar;
+pub mod chat;
+pub mod dedupe;
+pub mod detail;
+pub mod editor;
+pub mod jobs;
+pub mod plan;
+pub mod preview;
+pub use board::BoardGroup;
+pub use breakdown::Breakdown;
+pub use chat::ChatSession;
+pub use dedupe::Duplicates;
+pub use detail::TaskDetail;
+pub use editor::LineEditor;
+pub use jobs::Jobs;
+pub use plan::DayPlan;
+pub use preview::TaskPreview;
+
+use crate::config::{Config, CommandHistory, HistorySearch};
 use std::time::{Instant, Duration};
+use chrono::{DateTime, Months, NaiveDate, Utc};
 
 
 pub struct AppState {
     pub tasks: Vec<Task>,
     pub selected: usize,
     pub mode: Mode,
-    pub command_input: String,
+    pub command_input: LineEditor,
     pub show_help: bool,
+    // Set by `:quit`; the main loop exits and restores the terminal
+    pub quit: bool,
     pub editing_task: Option<String>,
     pub current_user: Option<crate::db::SupabaseUser>,  // Changed
     pub config: Config,
     pub command_history: CommandHistory,
+    pub history_search: Option<HistorySearch>,
     pub status_message: Option<String>,
-    pub supabase: Option<crate::db::SupabaseClient>,   // Changed
-    pub status_timer: Option<Instant>
+    pub supabase: Option<Arc<crate::db::SupabaseClient>>,
+    pub status_timer: Option<Instant>,
+    pub view: View,
+    pub drag_from: Option<usize>,
+    pub list_offset: usize,
+    pub list_height: usize,
+    pub board_group: BoardGroup,
+    pub board_column: usize,
+    pub calendar_date: NaiveDate,
+    // Task being moved in the calendar, with its due date before the move
+    pub rescheduling: Option<(String, Option<DateTime<Utc>>)>,
+    pub sort: SortSpec,
+    pub show_tags: bool,
+    pub tag_filter: Option<String>,
+    pub projects: Vec<Project>,
+    pub project_scope: ProjectScope,
+    pub show_projects: bool,
+    // Usernames of task assignees and project members, by user id
+    pub usernames: HashMap<String, String>,
+    pub detail: Option<TaskDetail>,
+    pub jobs: Jobs,
+    // Parsed `:add` tasks waiting to be reviewed, and the one on screen
+    pub pending_adds: VecDeque<Task>,
+    pub preview: Option<TaskPreview>,
+    // Subtasks proposed by `:breakdown`, waiting and on screen
+    pub pending_breakdowns: VecDeque<Breakdown>,
+    pub breakdown: Option<Breakdown>,
+    // Plan suggested by `:plan`, waiting and on screen
+    pub pending_plan: Option<DayPlan>,
+    pub plan: Option<DayPlan>,
+    // Groups of likely duplicates found by `:dedupe`, waiting and on screen
+    pub pending_duplicates: Option<Duplicates>,
+    pub duplicates: Option<Duplicates>,
+    // The `:chat` conversation, kept while the chat is closed
+    pub chat: Option<ChatSession>,
+}
+
+// Which project's tasks are shown
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProjectScope {
+    All,
+    Inbox,
+    Project(String),
+}
+
+impl ProjectScope {
+    pub fn matches(&self, task: &Task) -> bool {
+        match self {
+            ProjectScope::All => true,
+            ProjectScope::Inbox => task.project_id.is_none(),
+            ProjectScope::Project(id) => task.project_id.as_deref() == Some(id.as_str()),
+        }
+    }
 }
 
+// Rows kept visible above and below the selection while scrolling
+const SCROLL_OFF: usize = 3;
+
 pub enum Mode {
     Normal,
     Command,
     Edit,
     Login,      // Add this
     Register,
+    Detail,
+    Comment,
+    Preview,
+    Breakdown,
+    Plan,
+    Chat,
+    Dedupe,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum View {
+    All,
+    Open,
+    Done,
+    Assigned,
+    Board,
+    Agenda,
+    Calendar,
+}
+
+impl View {
+    pub const ALL: [View; 7] = [
+        View::All,
+        View::Open,
+        View::Done,
+        View::Assigned,
+        View::Board,
+        View::Agenda,
+        View::Calendar,
+    ];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            View::All => "All",
+            View::Open => "Open",
+            View::Done => "Done",
+            View::Assigned => "Assigned to me",
+            View::Board => "Board",
+            View::Agenda => "Agenda",
+            View::Calendar => "Calendar",
+        }
+    }
+
+    pub fn matches(&self, task: &Task) -> bool {
+        match self {
+            View::All => true,
+            View::Open => !task.status.is_closed(),
+            View::Done => task.is_done(),
+            // Narrowed to the current user in AppState::visible
+            View::Assigned => task.assignee_id.is_some(),
+            View::Board | View::Calendar => true,
+            View::Agenda => task.due_date.is_some() && !task.status.is_closed(),
+        }
+    }
+
+    // Views drawn as a single full-width task list
+    pub fn is_list(&self) -> bool {
+        matches!(self, View::All | View::Open | View::Done | View::Assigned)
+    }
+
+    pub fn next(&self) -> View {
+        let i = Self::ALL.iter().position(|v| v == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    pub fn previous(&self) -> View {
+        let i = Self::ALL.iter().position(|v| v == self).unwrap_or(0);
+        Self::ALL[(i + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
 }
 
 
 impl AppState {
     pub fn new() -> Self {
+        Self::with_config(Config::load(), CommandHistory::load())
+    }
+
+    pub fn with_config(config: Config, command_history: CommandHistory) -> Self {
         Self {
             tasks: Vec::new(),
             selected: 0,
             mode: Mode::Normal,
-            command_input: String::new(),
+            command_input: LineEditor::new(),
             show_help: false,
+            quit: false,
             editing_task: None,
             current_user: None,
-            config: Config::load(),
-            command_history: CommandHistory::new(),
+            config,
+            command_history,
+            history_search: None,
             status_message: None,
             supabase: None,
             status_timer: None,
+            view: View::All,
+            drag_from: None,
+            list_offset: 0,
+            list_height: 0,
+            board_group: BoardGroup::Status,
+            board_column: 0,
+            calendar_date: calendar::today(),
+            rescheduling: None,
+            sort: SortSpec::default(),
+            show_tags: false,
+            tag_filter: None,
+            projects: Vec::new(),
+            project_scope: ProjectScope::All,
+            show_projects: false,
+            usernames: HashMap::new(),
+            detail: None,
+            jobs: Jobs::new(),
+            pending_adds: VecDeque::new(),
+            preview: None,
+            pending_breakdowns: VecDeque::new(),
+            breakdown: None,
+            pending_plan: None,
+            plan: None,
+            pending_duplicates: None,
+            duplicates: None,
+            chat: None,
+        }
+    }
+
+    // Indices into `tasks` of the tasks shown in the current view, in display
+    // order. `selected` is a position within this list; on the board it is
+    // the position within the focused column, on the calendar within the
+    // selected day.
+    pub fn visible(&self) -> Vec<usize> {
+        self.visible_in_column(self.board_column)
+    }
+
+    // What `visible` would list with board column `column` focused, so the
+    // board draws its other columns under the same filters and order
+    pub fn visible_in_column(&self, column: usize) -> Vec<usize> {
+        let mut indices: Vec<usize> = self
+            .tasks
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| self.view.matches(t))
+            .filter(|(_, t)| self.tag_filter.as_ref().is_none_or(|tag| t.tags.contains(tag)))
+            .filter(|(_, t)| self.project_scope.matches(t))
+            .filter(|(_, t)| match self.view {
+                View::Board => self.board_group.column_of(t) == Some(column),
+                View::Calendar => t.due_date.map(calendar::local_date) == Some(self.calendar_date),
+                View::Assigned => self.is_assigned_to_me(t),
+                _ => true,
+            })
+            .map(|(i, _)| i)
+            .collect();
+
+        if self.view == View::Agenda {
+            indices.sort_by_key(|&i| self.tasks[i].due_date);
+        } else if !self.sort.is_manual() {
+            let now = Utc::now();
+            indices.sort_by(|&a, &b| self.sort.compare(&self.tasks[a], &self.tasks[b], now));
+        }
+        indices
+    }
+
+    pub fn selected_index(&self) -> Option<usize> {
+        self.visible().get(self.selected).copied()
+    }
+
+    pub fn selected_task(&self) -> Option<&Task> {
+        self.selected_index().map(|i| &self.tasks[i])
+    }
+
+    // Focus the board column `delta` steps away, keeping the row if possible
+    pub fn move_column(&mut self, delta: isize) {
+        let count = self.board_group.columns().len() as isize;
+        self.board_column = (self.board_column as isize + delta).clamp(0, count - 1) as usize;
+        self.clamp_selection();
+    }
+
+    // Select the task with `id`, following it to its board column if needed
+    pub fn select_id(&mut self, id: &str) {
+        if self.view == View::Board {
+            if let Some(task) = self.tasks.iter().find(|t| t.id == id) {
+                if let Some(column) = self.board_group.column_of(task) {
+                    self.board_column = column;
+                }
+            }
+        }
+        if let Some(pos) = self.visible().iter().position(|&i| self.tasks[i].id == id) {
+            self.selected = pos;
+        } else {
+            self.clamp_selection();
+        }
+    }
+
+    // Move the calendar cursor; a task being rescheduled travels with it
+    pub fn move_calendar(&mut self, days: i64) {
+        self.calendar_date += chrono::Duration::days(days);
+        self.follow_calendar();
+    }
+
+    pub fn move_calendar_months(&mut self, months: i32) {
+        let date = if months >= 0 {
+            self.calendar_date.checked_add_months(Months::new(months as u32))
+        } else {
+            self.calendar_date.checked_sub_months(Months::new(months.unsigned_abs()))
+        };
+        if let Some(date) = date {
+            self.calendar_date = date;
+            self.follow_calendar();
+        }
+    }
+
+    fn follow_calendar(&mut self) {
+        if let Some((id, _)) = self.rescheduling.clone() {
+            let date = self.calendar_date;
+            if let Some(task) = self.tasks.iter_mut().find(|t| t.id == id) {
+                task.due_date = calendar::reschedule(task.due_date, date);
+            }
+            self.select_id(&id);
+        } else {
+            self.selected = 0;
+        }
+    }
+
+    // Pick up the selected task so moving the cursor reschedules it
+    pub fn start_reschedule(&mut self) {
+        if let Some(task) = self.selected_task() {
+            self.rescheduling = Some((task.id.clone(), task.due_date));
+        }
+    }
+
+    pub fn cancel_reschedule(&mut self) {
+        if let Some((id, original)) = self.rescheduling.take() {
+            if let Some(task) = self.tasks.iter_mut().find(|t| t.id == id) {
+                if let Some(due) = original {
+                    self.calendar_date = calendar::local_date(due);
+                }
+                task.due_date = original;
+            }
+            self.select_id(&id);
+        }
+    }
+
+    pub fn is_assigned_to_me(&self, task: &Task) -> bool {
+        match (&task.assignee_id, &self.current_user) {
+            (Some(assignee), Some(user)) => *assignee == user.id,
+            _ => false,
+        }
+    }
+
+    // Viewers of a shared project may look at its tasks but not change them.
+    // Tasks assigned to the user stay editable, matching the tasks RLS policy.
+    pub fn can_edit(&self, task: &Task) -> bool {
+        let project = task
+            .project_id
+            .as_ref()
+            .and_then(|id| self.projects.iter().find(|p| &p.id == id));
+        project.is_none_or(|p| p.role.can_edit()) || self.is_assigned_to_me(task)
+    }
+
+    // Whether the configured workflow lets a task go from `from` to `to`; a
+    // refused transition says why in the status line
+    pub fn transition_allowed(&mut self, from: Status, to: Status) -> bool {
+        let allowed = self.config.workflow.allows(from, to);
+        if !allowed {
+            self.set_status(format!("Can't move from {} to {}", from.label(), to.label()));
+        }
+        allowed
+    }
+
+    // The server has failed repeatedly and requests are refused for now;
+    // the loaded tasks stay browsable
+    pub fn is_offline(&self) -> bool {
+        self.supabase.as_ref().is_some_and(|sb| sb.is_offline())
+    }
+
+    pub fn current_project(&self) -> Option<&Project> {
+        match &self.project_scope {
+            ProjectScope::Project(id) => self.projects.iter().find(|p| &p.id == id),
+            _ => None,
+        }
+    }
+
+    // Step through All, Inbox and each project in order
+    pub fn cycle_project(&mut self, delta: isize) {
+        let mut scopes = vec![ProjectScope::All, ProjectScope::Inbox];
+        scopes.extend(self.projects.iter().map(|p| ProjectScope::Project(p.id.clone())));
+        let current = scopes.iter().position(|s| *s == self.project_scope).unwrap_or(0) as isize;
+        let next = (current + delta).rem_euclid(scopes.len() as isize) as usize;
+        self.project_scope = scopes.swap_remove(next);
+        self.selected = 0;
+    }
+
+    // Step the tag filter through "no filter" and each tag in use
+    pub fn cycle_tag_filter(&mut self, delta: isize) {
+        let tags: Vec<String> = tags::tag_counts(&self.tasks).into_iter().map(|(t, _)| t).collect();
+        let slots = tags.len() as isize + 1;
+        let current = self
+            .tag_filter
+            .as_ref()
+            .and_then(|f| tags.iter().position(|t| t == f))
+            .map_or(0, |i| i as isize + 1);
+        let next = (current + delta).rem_euclid(slots);
+        self.tag_filter = if next == 0 { None } else { Some(tags[next as usize - 1].clone()) };
+        self.selected = 0;
+    }
+
+    // Complete a `+tag` word at the cursor against existing tags. A unique
+    // match is completed; otherwise the common prefix is filled in and the
+    // candidates are listed in the status bar.
+    pub fn complete_tag(&mut self) {
+        let word = self.command_input.word_before_cursor();
+        let Some(prefix) = word.strip_prefix('+') else {
+            return;
+        };
+        let prefix = prefix.to_lowercase();
+        let candidates: Vec<String> = tags::tag_counts(&self.tasks)
+            .into_iter()
+            .map(|(t, _)| t)
+            .filter(|t| t.starts_with(&prefix))
+            .collect();
+
+        match candidates.as_slice() {
+            [] => {}
+            [only] => self.command_input.replace_word_before_cursor(&format!("+{} ", only)),
+            many => {
+                let common = many.iter().skip(1).fold(many[0].clone(), |acc, t| {
+                    acc.chars()
+                        .zip(t.chars())
+                        .take_while(|(a, b)| a == b)
+                        .map(|(a, _)| a)
+                        .collect()
+                });
+                self.command_input.replace_word_before_cursor(&format!("+{}", common));
+                let list: Vec<String> = many.iter().map(|t| format!("+{}", t)).collect();
+                self.set_status(list.join("  "));
+            }
         }
     }
 
+    // Show the next parsed task, proposed breakdown, plan or list of
+    // duplicates for review. They
+    // finish in the background, so a result waits until the user is back in
+    // normal mode rather than interrupting whatever they're typing.
+    pub fn open_next_preview(&mut self) {
+        if !matches!(self.mode, Mode::Normal)
+            || self.preview.is_some()
+            || self.breakdown.is_some()
+            || self.plan.is_some()
+            || self.duplicates.is_some()
+        {
+            return;
+        }
+        if let Some(task) = self.pending_adds.pop_front() {
+            self.preview = Some(TaskPreview::new(task));
+            self.mode = Mode::Preview;
+        } else if let Some(breakdown) = self.pending_breakdowns.pop_front() {
+            self.breakdown = Some(breakdown);
+            self.mode = Mode::Breakdown;
+        } else if let Some(plan) = self.pending_plan.take() {
+            self.plan = Some(plan);
+            self.mode = Mode::Plan;
+        } else if let Some(duplicates) = self.pending_duplicates.take() {
+            self.duplicates = Some(duplicates);
+            self.mode = Mode::Dedupe;
+        }
+    }
+
+    pub fn set_view(&mut self, view: View) {
+        self.view = view;
+        self.selected = 0;
+    }
+
+    // Move the task at visible row `from` to row `to` and select it. The
+    // moved tasks swap their existing `position` values, so an adjacent move
+    // is a plain swap and tasks hidden by the current view keep their place.
+    // Returns the tasks whose position changed, or None when the rows are
+    // out of range or one of the tasks in between is read-only.
+    pub fn move_row(&mut self, from: usize, to: usize) -> Option<Vec<Task>> {
+        let visible = self.visible();
+        if from == to || from >= visible.len() || to >= visible.len() {
+            return None;
+        }
+
+        let (lo, hi) = (from.min(to), from.max(to));
+        let span: Vec<usize> = visible[lo..=hi].to_vec();
+        if span.iter().any(|&i| !self.can_edit(&self.tasks[i])) {
+            self.set_status("Read-only: you are a viewer of this project".to_string());
+            return None;
+        }
+        let positions: Vec<i32> = span.iter().map(|&i| self.tasks[i].position).collect();
+
+        let mut order = span;
+        if from < to {
+            order.rotate_left(1);
+        } else {
+            order.rotate_right(1);
+        }
+
+        let mut changed = Vec::new();
+        for (&idx, &pos) in order.iter().zip(positions.iter()) {
+            if self.tasks[idx].position != pos {
+                self.tasks[idx].position = pos;
+                changed.push(self.tasks[idx].clone());
+            }
+        }
+        // Manual order is the list's order, as loaded by position
+        self.tasks.sort_by_key(|t| t.position);
+        self.selected = to;
+        Some(changed)
+    }
+
+    // Keep the selection inside the visible list after tasks change
+    pub fn clamp_selection(&mut self) {
+        let len = self.visible().len();
+        if self.selected >= len {
+            self.selected = len.saturating_sub(1);
+        }
+    }
+
+    pub fn select_last(&mut self) {
+        self.selected = self.visible().len().saturating_sub(1);
+    }
+
+    // Move the selection by `delta` rows without wrapping
+    pub fn move_by(&mut self, delta: isize) {
+        let len = self.visible().len();
+        if len > 0 {
+            let target = self.selected as isize + delta;
+            self.selected = target.clamp(0, len as isize - 1) as usize;
+        }
+    }
+
+    pub fn page_down(&mut self) {
+        self.move_by(self.list_height.max(1) as isize);
+    }
+
+    pub fn page_up(&mut self) {
+        self.move_by(-(self.list_height.max(1) as isize));
+    }
+
+    pub fn half_page_down(&mut self) {
+        self.move_by((self.list_height / 2).max(1) as isize);
+    }
+
+    pub fn half_page_up(&mut self) {
+        self.move_by(-((self.list_height / 2).max(1) as isize));
+    }
+
     pub fn next(&mut self) {
-        if !self.tasks.is_empty() {
-            self.selected = (self.selected + 1) % self.tasks.len();
+        let len = self.visible().len();
+        if len > 0 {
+            self.selected = (self.selected + 1) % len;
         }
     }
     pub fn set_status(&mut self, message: String) {
@@ -72,17 +583,18 @@ impl AppState {
     }
 
     pub fn previous(&mut self) {
-        if !self.tasks.is_empty() {
+        let len = self.visible().len();
+        if len > 0 {
             if self.selected > 0 {
                 self.selected -= 1;
             } else {
-                self.selected = self.tasks.len() - 1;
+                self.selected = len - 1;
             }
         }
     }
 }
 
-pub fn render(f: &mut Frame, state: &AppState) {
+pub fn render(f: &mut Frame, state: &mut AppState) {
     if state.show_help {
         render_help(f);
         return;
@@ -94,68 +606,400 @@ pub fn render(f: &mut Frame, state: &AppState) {
         return;
     }
 
+    let chunks = main_layout(f.size());
+
+    let mut tab_block = Block::default()
+        .borders(Borders::ALL)
+        .title("TODO AI - Terminal Task Manager")
+        .title_style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))
+        .title(
+            Title::from(Span::styled(
+                format!("Sort: {}", state.sort),
+                Style::default().fg(Color::Gray),
+            ))
+            .alignment(Alignment::Right),
+        );
+    if state.is_offline() {
+        tab_block = tab_block.title(
+            Title::from(Span::styled(" OFFLINE ", Style::default().fg(Color::Black).bg(Color::Red)))
+                .alignment(Alignment::Right),
+        );
+    }
+    let tabs = Tabs::new(View::ALL.iter().map(|v| v.title()).collect::<Vec<_>>())
+        .select(View::ALL.iter().position(|v| *v == state.view).unwrap_or(0))
+        .style(Style::default().fg(Color::White))
+        .highlight_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+        .block(tab_block);
+    f.render_widget(tabs, chunks[0]);
+
+    let (sidebar, content) = content_layout(chunks[1], state);
+    if let Some(area) = sidebar {
+        match (state.show_projects, state.show_tags) {
+            (true, true) => {
+                let halves = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+                    .split(area);
+                render_project_sidebar(f, halves[0], state);
+                render_tag_sidebar(f, halves[1], state);
+            }
+            (true, false) => render_project_sidebar(f, area, state),
+            _ => render_tag_sidebar(f, area, state),
+        }
+    }
+
+    match state.view {
+        _ if matches!(state.mode, Mode::Chat) => chat::render_chat(f, content, state),
+        _ if state.detail.is_some() => detail::render_detail(f, content, state),
+        View::Board => board::render_board(f, content, state),
+        View::Agenda => calendar::render_agenda(f, content, state),
+        View::Calendar => calendar::render_calendar(f, content, state),
+        _ => {
+            let title = match (&state.project_scope, state.current_project()) {
+                (_, Some(project)) => project.name.clone(),
+                (ProjectScope::Inbox, _) => "Inbox".to_string(),
+                _ => "Tasks".to_string(),
+            };
+            render_list(f, content, state, &title)
+        }
+    }
+
+    if state.preview.is_some() {
+        preview::render_preview(f, content, state);
+    }
+    if state.breakdown.is_some() {
+        breakdown::render_breakdown(f, content, state);
+    }
+    if state.plan.is_some() {
+        plan::render_plan(f, content, state);
+    }
+    if state.duplicates.is_some() {
+        dedupe::render_duplicates(f, content, state);
+    }
+
+    let status_area = chunks[2];
+    let inner_width = status_area.width.saturating_sub(2) as usize;
+
+    let (status_text, cursor) = if let Some(msg) = &state.status_message {
+        (msg.clone(), None)
+    } else {
+        match state.mode {
+            Mode::Normal => {
+                ("q: quit | j/k: navigate | Shift+J/K: reorder | space: toggle | tab: view | :: command | ?: help".to_string(), None)
+            }
+            Mode::Command => match &state.history_search {
+                Some(search) => {
+                    let matched = search
+                        .matched
+                        .and_then(|i| state.command_history.get(i))
+                        .unwrap_or("");
+                    (format!("(reverse-i-search)`{}': {}", search.query, matched), None)
+                }
+                None => input_line(":", &state.command_input, inner_width),
+            },
+            Mode::Edit => input_line("Edit: ", &state.command_input, inner_width),
+            Mode::Detail => ("j/k: scroll | c: comment | Esc: back".to_string(), None),
+            Mode::Comment => input_line("Comment: ", &state.command_input, inner_width),
+            Mode::Preview => match &state.preview {
+                Some(preview) if preview.editing => {
+                    input_line(&format!("{}: ", preview.field().label()), &state.command_input, inner_width)
+                }
+                Some(preview) if preview.checking => ("Checking for duplicates…".to_string(), None),
+                Some(TaskPreview { similar: Some(similar), .. }) => {
+                    let title = state.tasks.iter().find(|t| t.id == similar.task_id).map_or("", |t| t.title.as_str());
+                    (format!("Similar to '{}' — a: add anyway | m: merge | c: cancel", title), None)
+                }
+                _ => ("j/k: field | e: edit | 1/2/3: priority | Enter: save | Esc: discard".to_string(), None),
+            },
+            Mode::Breakdown => match &state.breakdown {
+                Some(breakdown) if breakdown.editing => input_line("Subtask: ", &state.command_input, inner_width),
+                _ => ("j/k: move | space: keep/drop | e: edit | Enter: create checked | Esc: discard".to_string(), None),
+            },
+            Mode::Plan => ("Enter: apply order and priorities | o: order only | p: priorities only | Esc: dismiss".to_string(), None),
+            Mode::Chat => match &state.chat {
+                Some(chat) if !chat.proposal.is_empty() => ("y/Enter: apply changes | n/Esc: discard".to_string(), None),
+                _ => input_line("Ask: ", &state.command_input, inner_width),
+            },
+            Mode::Dedupe => ("j/k: move | Enter: go to task | Esc: close".to_string(), None),
+            Mode::Login | Mode::Register => (state.command_input.to_string(), None),
+        }
+    };
+
+
+    let mut status_block = Block::default().borders(Borders::ALL);
+    if let Some(indicator) = state.jobs.indicator() {
+        status_block = status_block.title(
+            Title::from(Span::styled(indicator, Style::default().fg(Color::Yellow))).alignment(Alignment::Right),
+        );
+    }
+    let status = Paragraph::new(status_text)
+        .style(Style::default().fg(Color::White))
+        .block(status_block);
+    f.render_widget(status, status_area);
+
+    if let Some(col) = cursor {
+        f.set_cursor(status_area.x + 1 + col, status_area.y + 1);
+    }
+}
+
+// Render an editor after a fixed prompt, returning the text and cursor column
+fn input_line(prompt: &str, editor: &LineEditor, width: usize) -> (String, Option<u16>) {
+    let prompt_width = prompt.width();
+    let (visible, col) = editor.view(width.saturating_sub(prompt_width));
+    (format!("{}{}", prompt, visible), Some((prompt_width + col) as u16))
+}
+
+const SIDEBAR_WIDTH: u16 = 24;
+
+// Split the body into the optional tag sidebar and the view's own area
+pub fn content_layout(area: Rect, state: &AppState) -> (Option<Rect>, Rect) {
+    if !(state.show_tags || state.show_projects) || area.width <= SIDEBAR_WIDTH * 2 {
+        return (None, area);
+    }
     let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Length(3),
-            Constraint::Min(1),
-            Constraint::Length(3),
-        ])
-        .split(f.size());
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Length(SIDEBAR_WIDTH), Constraint::Min(1)])
+        .split(area);
+    (Some(chunks[0]), chunks[1])
+}
 
-    let header = Paragraph::new("TODO AI - Terminal Task Manager")
-        .style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))
-        .alignment(Alignment::Center)
-        .block(Block::default().borders(Borders::ALL));
-    f.render_widget(header, chunks[0]);
+fn render_project_sidebar(f: &mut Frame, area: Rect, state: &AppState) {
+    let highlight = Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD);
+    let count = |scope: &ProjectScope| state.tasks.iter().filter(|t| scope.matches(t)).count();
+
+    let mut items = Vec::new();
+    for (label, scope) in [("All", ProjectScope::All), ("Inbox", ProjectScope::Inbox)] {
+        let style = if state.project_scope == scope { highlight } else { Style::default() };
+        items.push(ListItem::new(format!("{} ({})", label, count(&scope))).style(style));
+    }
 
-    let items: Vec<ListItem> = state
-        .tasks
+    for project in &state.projects {
+        let scope = ProjectScope::Project(project.id.clone());
+        let style = if state.project_scope == scope {
+            highlight
+        } else {
+            Style::default().fg(project_color(project))
+        };
+        let shared = if project.role == Role::Owner { "" } else { " ⇄" };
+        items.push(ListItem::new(format!("● {}{} ({})", project.name, shared, count(&scope))).style(style));
+    }
+
+    let list = List::new(items).block(Block::default().borders(Borders::ALL).title("Projects"));
+    f.render_widget(list, area);
+}
+
+fn project_color(project: &Project) -> Color {
+    project
+        .color
+        .as_deref()
+        .and_then(|c| c.parse().ok())
+        .unwrap_or(Color::Magenta)
+}
+
+fn render_tag_sidebar(f: &mut Frame, area: Rect, state: &AppState) {
+    let mut items = vec![ListItem::new(format!("All ({})", state.tasks.len())).style(
+        if state.tag_filter.is_none() {
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        },
+    )];
+
+    for (tag, count) in tags::tag_counts(&state.tasks) {
+        let style = if state.tag_filter.as_deref() == Some(tag.as_str()) {
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::Blue)
+        };
+        items.push(ListItem::new(format!("+{} ({})", tag, count)).style(style));
+    }
+
+    let list = List::new(items).block(Block::default().borders(Borders::ALL).title("Tags"));
+    f.render_widget(list, area);
+}
+
+// Written before a task's checkbox in the list; subtasks sit under their parent
+fn row_indent(task: &Task) -> &'static str {
+    if task.parent_id.is_some() {
+        "  ↳ "
+    } else {
+        ""
+    }
+}
+
+fn render_list(f: &mut Frame, area: Rect, state: &mut AppState, title: &str) {
+    let visible = state.visible();
+    let today = calendar::today();
+    let inner_height = area.height.saturating_sub(2) as usize;
+    state.list_height = inner_height;
+    state.list_offset = scroll_offset(state.selected, state.list_offset, inner_height, visible.len());
+
+    let items: Vec<ListItem> = visible
         .iter()
         .enumerate()
-        .map(|(i, task)| {
-            let status = if task.done { "[✓]" } else { "[ ]" };
+        .map(|(i, &idx)| {
+            let task = &state.tasks[idx];
+            let status = task.status.symbol();
             let priority = match task.priority {
                 Priority::Low => "P1",
                 Priority::Medium => "P2",
                 Priority::High => "P3",
             };
-            let content = format!("{} {} {}", status, priority, task.title);
+            let indent = row_indent(task);
+            let mut content = vec![Span::raw(format!("{}{} {} {}", indent, status, priority, task.title))];
+            if let Some(due) = task.due_date {
+                let (label, overdue) = calendar::due_label(due, today);
+                let due_style = if overdue && !task.status.is_closed() {
+                    Style::default().fg(Color::Red)
+                } else {
+                    Style::default().fg(Color::DarkGray)
+                };
+                content.push(Span::styled(format!("  due {}", label), due_style));
+            }
+            for tag in &task.tags {
+                content.push(Span::styled(format!(" +{}", tag), Style::default().fg(Color::Blue)));
+            }
+            if state.project_scope == ProjectScope::All {
+                let project = task
+                    .project_id
+                    .as_ref()
+                    .and_then(|id| state.projects.iter().find(|p| &p.id == id));
+                if let Some(project) = project {
+                    content.push(Span::styled(
+                        format!(" @{}", project.name),
+                        Style::default().fg(project_color(project)),
+                    ));
+                }
+            }
+            if let Some(assignee) = &task.assignee_id {
+                let name = state.usernames.get(assignee).map_or("?", String::as_str);
+                content.push(Span::styled(format!(" → {}", name), Style::default().fg(Color::Cyan)));
+            }
             
-            let style = if i == state.selected {
+            let style = if state.drag_from == Some(i) {
+                Style::default().fg(Color::Black).bg(Color::Yellow)
+            } else if i == state.selected {
                 Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
-            } else if task.done {
+            } else if task.status.is_closed() {
                 Style::default().fg(Color::DarkGray)
+            } else if task.status == Status::Blocked {
+                Style::default().fg(Color::Red)
+            } else if task.status == Status::InProgress {
+                Style::default().fg(Color::Cyan)
             } else {
                 Style::default()
             };
 
-            ListItem::new(content).style(style)
+            ListItem::new(Line::from(content)).style(style)
         })
         .collect();
 
-    let list = List::new(items)
-        .block(Block::default().borders(Borders::ALL).title("Tasks"));
-    f.render_widget(list, chunks[1]);
-
-    let status_text = if let Some(msg) = &state.status_message {
-        msg.clone()
+    let count = if visible.is_empty() {
+        "0/0".to_string()
     } else {
-        match state.mode {
-            Mode::Normal => {
-                "q: quit | j/k: navigate | Shift+J/K: reorder | space: toggle | :: command | ?: help".to_string()
+        format!("{}/{}", state.selected + 1, visible.len())
+    };
+
+    let list = List::new(items).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title.to_string())
+            .title(Title::from(count).alignment(Alignment::Right)),
+    );
+    let mut list_state = ListState::default()
+        .with_offset(state.list_offset)
+        .with_selected(if visible.is_empty() { None } else { Some(state.selected) });
+    f.render_stateful_widget(list, area, &mut list_state);
+
+    if visible.len() > inner_height {
+        let mut scrollbar_state = ScrollbarState::new(visible.len().saturating_sub(inner_height))
+            .position(state.list_offset);
+        let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+            .begin_symbol(None)
+            .end_symbol(None);
+        f.render_stateful_widget(
+            scrollbar,
+            area.inner(&Margin { vertical: 1, horizontal: 0 }),
+            &mut scrollbar_state,
+        );
+    }
+}
+
+// First visible row so that `selected` stays at least SCROLL_OFF rows away
+// from either edge, moving the viewport as little as possible.
+pub fn scroll_offset(selected: usize, offset: usize, height: usize, len: usize) -> usize {
+    if height == 0 {
+        return 0;
+    }
+
+    let margin = SCROLL_OFF.min(height.saturating_sub(1) / 2);
+    let mut offset = offset;
+    if selected < offset + margin {
+        offset = selected.saturating_sub(margin);
+    }
+    if selected + margin >= offset + height {
+        offset = selected + margin + 1 - height;
+    }
+    offset.min(len.saturating_sub(height))
+}
+
+pub fn main_layout(area: Rect) -> Rc<[Rect]> {
+    Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(1),
+            Constraint::Length(3),
+        ])
+        .split(area)
+}
+
+// What a mouse position lands on in the main screen
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Hit {
+    Tab(View),
+    Checkbox(usize),
+    Row(usize),
+}
+
+pub fn hit_test(area: Rect, state: &AppState, column: u16, row: u16) -> Option<Hit> {
+    let chunks = main_layout(area);
+    let header = inner(chunks[0]);
+    let list = inner(content_layout(chunks[1], state).1);
+
+    if row == header.y && column >= header.x {
+        // Mirrors Tabs' layout: " title " separated by a one-cell divider
+        let mut x = header.x;
+        for view in View::ALL {
+            let width = view.title().width() as u16 + 2;
+            if column >= x && column < x + width {
+                return Some(Hit::Tab(view));
             }
-            Mode::Command => format!(":{}", state.command_input),
-            Mode::Edit => format!("Edit: {}", state.command_input),
-            Mode::Login | Mode::Register => state.command_input.clone(),
+            x += width + 1;
         }
-    };
+        return None;
+    }
 
+    if !state.view.is_list() {
+        return None;
+    }
 
-    let status = Paragraph::new(status_text)
-        .style(Style::default().fg(Color::White))
-        .block(Block::default().borders(Borders::ALL));
-    f.render_widget(status, chunks[2]);
+    if row >= list.y && row < list.y + list.height && column >= list.x && column < list.x + list.width {
+        let index = state.list_offset + (row - list.y) as usize;
+        let task = &state.tasks[*state.visible().get(index)?];
+        // The "[ ]" checkbox follows the row's indent
+        let start = list.x + row_indent(task).width() as u16;
+        if column >= start && column < start + task.status.symbol().width() as u16 {
+            return Some(Hit::Checkbox(index));
+        }
+        return Some(Hit::Row(index));
+    }
+
+    None
+}
+
+fn inner(area: Rect) -> Rect {
+    Block::default().borders(Borders::ALL).inner(area)
 }
 
 fn render_help(f: &mut Frame) {
@@ -167,6 +1011,14 @@ fn render_help(f: &mut Frame) {
         Line::from("  k / ↑         - Move up"),
         Line::from("  gg            - Go to top"),
         Line::from("  G             - Go to bottom"),
+        Line::from("  PgDn / PgUp   - Move one page down/up"),
+        Line::from("  Ctrl+D/Ctrl+U - Move half a page down/up"),
+        Line::from("  Tab/Shift+Tab - Switch view (All/Open/Done/Assigned/Board/Agenda/Calendar)"),
+        Line::from("  h/l           - Board: focus previous/next column"),
+        Line::from("  H/L           - Board: move card to previous/next column"),
+        Line::from("  h/l, H/L      - Calendar: previous/next day, week"),
+        Line::from("  </>           - Calendar: previous/next month"),
+        Line::from("  m             - Calendar: pick up task, move cursor, Enter to drop"),
         Line::from(""),
         Line::from(vec![Span::styled("Reorder Tasks:", Style::default().fg(Color::Yellow))]),
         Line::from("  Shift+J       - Move task down in list"),
@@ -174,27 +1026,72 @@ fn render_help(f: &mut Frame) {
         Line::from(""),
         Line::from(vec![Span::styled("Actions:", Style::default().fg(Color::Yellow))]),
         Line::from("  space         - Toggle task done/undone"),
+        Line::from("  s             - Cycle status (todo/in progress/blocked/...)"),
         Line::from("  d             - Delete task"),
         Line::from("  e             - Edit task"),
+        Line::from("  Enter         - Open task details, activity and comments (c: comment)"),
         Line::from("  1/2/3         - Set priority (Low/Medium/High)"),
+        Line::from("  p             - Toggle project sidebar"),
+        Line::from("  { / }         - Switch to previous/next project"),
+        Line::from("  t             - Toggle tag sidebar"),
+        Line::from("  [ / ]         - Filter by previous/next tag"),
         Line::from("  ?             - Toggle this help screen"),
         Line::from("  q             - Quit application"),
         Line::from(""),
+        Line::from(vec![Span::styled("Mouse:", Style::default().fg(Color::Yellow))]),
+        Line::from("  Click         - Select task / toggle checkbox / switch view tab"),
+        Line::from("  Wheel         - Move selection"),
+        Line::from("  Drag          - Reorder task"),
+        Line::from(""),
         Line::from(vec![Span::styled("Command Mode (press :)", Style::default().fg(Color::Yellow))]),
-        Line::from("  :add <task>              - Add new task"),
-        Line::from("                             Example: :add Buy groceries"),
+        Line::from("  :add <task>              - Add new task, reviewed before saving"),
+        Line::from("                             Example: :add Buy milk at 5pm tomorrow +errands"),
+        Line::from("  :breakdown               - Propose subtasks for the selected task"),
+        Line::from("  :plan                    - Suggest an ordered plan for today"),
+        Line::from("  :chat [message]          - Ask about or change tasks in plain words"),
+        Line::from("  :dedupe                  - List groups of likely duplicate tasks"),
         Line::from(""),
         Line::from("  :done                    - Mark selected task as done"),
+        Line::from("  :status <status>         - Set status: todo, in_progress,"),
+        Line::from("                             blocked, waiting, done, cancelled"),
         Line::from(""),
         Line::from("  :sync                    - Sync tasks from cloud"),
         Line::from(""),
+        Line::from("  :board [status|priority] - Show board grouped by field"),
+        Line::from(""),
+        Line::from("  :project [name|inbox]    - Switch project (no name shows all)"),
+        Line::from("  :project new <name> [color]"),
+        Line::from("  :project rename <name>   - Rename the current project"),
+        Line::from("  :project delete          - Delete the current project"),
+        Line::from("  :project move <name|inbox> - Move selected task"),
+        Line::from("  (:add understands @project and \"for project X\")"),
+        Line::from("  :share <user> [editor|viewer] - Share the current project"),
+        Line::from("  :unshare <user>          - Stop sharing with a user"),
+        Line::from("  :members                 - List project members and roles"),
+        Line::from("  :assign <user|me>        - Assign selected task"),
+        Line::from("  :unassign                - Clear the assignee"),
+        Line::from(""),
+        Line::from("  :tag [name]              - Filter by tag (no name clears)"),
+        Line::from("  :tag rename <old> <new>  - Rename a tag on all tasks"),
+        Line::from("  :tag merge <from> <into> - Merge one tag into another"),
+        Line::from(""),
+        Line::from("  :sort <key>[,<key>...]   - Sort by manual, priority, due, created,"),
+        Line::from("                             updated, title, urgency (-key reverses)"),
+        Line::from("                             Example: :sort priority,due"),
+        Line::from(""),
         Line::from("  :quit or :q              - Quit application"),
         Line::from(""),
         Line::from(vec![Span::styled("Command Mode Controls:", Style::default().fg(Color::Yellow))]),
         Line::from("  Esc           - Exit command mode"),
         Line::from("  Enter         - Execute command"),
-        Line::from("  Backspace     - Delete character"),
-        Line::from("  ↑/↓           - Navigate command history"),
+        Line::from("  ←/→ Home/End  - Move cursor (also Ctrl+A/E, Ctrl+B/F)"),
+        Line::from("  Alt+B/Alt+F   - Move by word"),
+        Line::from("  Backspace/Del - Delete character"),
+        Line::from("  Ctrl+W        - Delete previous word"),
+        Line::from("  Ctrl+U/Ctrl+K - Delete to start/end of line"),
+        Line::from("  ↑/↓           - Navigate history matching typed prefix"),
+        Line::from("  Tab           - Complete +tag"),
+        Line::from("  Ctrl+R        - Reverse search history (repeat for older)"),
         Line::from(""),
         Line::from(vec![Span::styled("Press ? to close this help", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD))]),
     ];
@@ -231,16 +1128,435 @@ fn render_auth(f: &mut Frame, state: &AppState) {
     
     f.render_widget(title_widget, chunks[0]);
 
+    let instruction = state.jobs.indicator().unwrap_or_else(|| instruction.to_string());
     let instruction_widget = Paragraph::new(instruction)
         .style(Style::default().fg(Color::Yellow))
         .alignment(Alignment::Center);
     
     f.render_widget(instruction_widget, chunks[1]);
 
-    let input_widget = Paragraph::new(format!("> {}", state.command_input))
+    let input_area = chunks[2];
+    let (input_text, cursor) = input_line("> ", &state.command_input, input_area.width.saturating_sub(2) as usize);
+    let input_widget = Paragraph::new(input_text)
         .style(Style::default().fg(Color::White))
         .alignment(Alignment::Left)
         .block(Block::default().borders(Borders::ALL).title("Input"));
     
-    f.render_widget(input_widget, chunks[2]);
-}
\ No newline at end of file
+    f.render_widget(input_widget, input_area);
+
+    if let Some(col) = cursor {
+        f.set_cursor(input_area.x + 1 + col, input_area.y + 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::breakdown::Subtask;
+    use crate::db::SupabaseUser;
+    use ratatui::{backend::TestBackend, Terminal};
+
+    fn state_with_tasks(count: usize) -> AppState {
+        let mut state = AppState::with_config(Config::default(), CommandHistory::new());
+        state.current_user = Some(SupabaseUser {
+            id: "u1".to_string(),
+            username: "tester".to_string(),
+            email: "tester@example.com".to_string(),
+            created_at: String::new(),
+        });
+        state.tasks = (0..count)
+            .map(|i| {
+                let mut task = Task::new(format!("task {}", i + 1));
+                task.position = i as i32;
+                task
+            })
+            .collect();
+        state
+    }
+
+    fn draw(state: &mut AppState, width: u16, height: u16) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|f| render(f, state)).unwrap();
+        let buffer = terminal.backend().buffer().clone();
+        (0..buffer.area.height)
+            .map(|y| {
+                (0..buffer.area.width)
+                    .map(|x| buffer.get(x, y).symbol().to_string())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn scroll_offset_keeps_margin() {
+        // Moving down past the bottom margin scrolls by one row
+        assert_eq!(scroll_offset(6, 0, 10, 100), 0);
+        assert_eq!(scroll_offset(7, 0, 10, 100), 1);
+        // Moving up into the top margin scrolls back
+        assert_eq!(scroll_offset(12, 10, 10, 100), 9);
+        // Never scrolls past the end of the list
+        assert_eq!(scroll_offset(99, 0, 10, 100), 90);
+        // Small viewports shrink the margin instead of jittering
+        assert_eq!(scroll_offset(1, 0, 2, 100), 0);
+        assert_eq!(scroll_offset(5, 0, 0, 100), 0);
+    }
+
+    #[test]
+    fn selection_stays_visible_when_scrolling() {
+        let mut state = state_with_tasks(310);
+        state.selected = 41;
+
+        let screen = draw(&mut state, 60, 20);
+        assert!(screen.contains("task 42"));
+        assert!(screen.contains("42/310"));
+        // 12 list rows: selection sits SCROLL_OFF rows above the bottom edge
+        assert_eq!(state.list_height, 12);
+        assert_eq!(state.list_offset, 41 + SCROLL_OFF + 1 - 12);
+        assert!(!screen.contains("task 1 "));
+    }
+
+    #[test]
+    fn page_motions_use_viewport_height() {
+        let mut state = state_with_tasks(100);
+        draw(&mut state, 60, 20);
+
+        state.page_down();
+        assert_eq!(state.selected, 12);
+        state.half_page_down();
+        assert_eq!(state.selected, 18);
+        state.half_page_up();
+        state.page_up();
+        assert_eq!(state.selected, 0);
+
+        state.selected = 95;
+        state.page_down();
+        assert_eq!(state.selected, 99);
+
+        let screen = draw(&mut state, 60, 20);
+        assert!(screen.contains("task 100"));
+        assert!(screen.contains("100/100"));
+    }
+
+    #[test]
+    fn parsed_tasks_wait_for_normal_mode_before_preview() {
+        let mut state = state_with_tasks(1);
+        let mut task = Task::new("Buy milk".to_string());
+        task.estimate_minutes = Some(15);
+        state.pending_adds.push_back(task);
+
+        // Not while the user is typing a command
+        state.mode = Mode::Command;
+        state.open_next_preview();
+        assert!(state.preview.is_none());
+
+        state.mode = Mode::Normal;
+        state.open_next_preview();
+        assert!(matches!(state.mode, Mode::Preview));
+        assert!(state.pending_adds.is_empty());
+
+        let screen = draw(&mut state, 80, 20);
+        assert!(screen.contains("New task"));
+        assert!(screen.contains("Buy milk"));
+        assert!(screen.contains("15m"));
+    }
+
+    #[test]
+    fn breakdowns_open_after_pending_adds() {
+        let mut state = state_with_tasks(1);
+        let parent = state.tasks[0].clone();
+        let subtasks = vec![Subtask { title: "Write changelog".to_string(), estimate_minutes: Some(90) }];
+        state.pending_breakdowns.push_back(Breakdown::new(parent, subtasks));
+        state.pending_adds.push_back(Task::new("Buy milk".to_string()));
+
+        state.open_next_preview();
+        assert!(matches!(state.mode, Mode::Preview));
+        state.preview = None;
+        state.mode = Mode::Normal;
+
+        state.open_next_preview();
+        assert!(matches!(state.mode, Mode::Breakdown));
+        let screen = draw(&mut state, 80, 20);
+        assert!(screen.contains("Break down: task 1"));
+        assert!(screen.contains("[x] Write changelog  1h30m"));
+    }
+
+    // Whether the row showing `text` is drawn highlighted as the selection
+    fn is_highlighted(state: &mut AppState, text: &str) -> bool {
+        let mut terminal = Terminal::new(TestBackend::new(100, 20)).unwrap();
+        terminal.draw(|f| render(f, state)).unwrap();
+        let buffer = terminal.backend().buffer();
+        for y in 0..buffer.area.height {
+            let row: String = (0..buffer.area.width).map(|x| buffer.get(x, y).symbol().to_string()).collect();
+            if let Some(at) = row.find(text) {
+                let x = row[..at].chars().count() as u16;
+                return buffer.get(x, y).fg == Color::Yellow;
+            }
+        }
+        panic!("{:?} is not on screen", text);
+    }
+
+    #[test]
+    fn board_cards_follow_the_tag_filter() {
+        let mut state = state_with_tasks(3);
+        state.tasks[1].tags = vec!["work".to_string()];
+        state.tasks[2].tags = vec!["work".to_string()];
+        state.view = View::Board;
+        state.tag_filter = Some("work".to_string());
+
+        assert_eq!(state.selected_task().unwrap().title, "task 2");
+        assert!(is_highlighted(&mut state, "task 2"));
+        assert!(!draw(&mut state, 100, 20).contains("task 1"));
+        state.next();
+        assert_eq!(state.selected_task().unwrap().title, "task 3");
+        assert!(is_highlighted(&mut state, "task 3"));
+    }
+
+    #[test]
+    fn board_cards_follow_the_project_scope() {
+        let mut state = state_with_tasks(4);
+        let work = Project::new("Work".to_string(), None, "u1".to_string());
+        state.tasks[2].project_id = Some(work.id.clone());
+        state.tasks[3].project_id = Some(work.id.clone());
+        state.tasks[3].status = Status::InProgress;
+        state.project_scope = ProjectScope::Project(work.id.clone());
+        state.projects.push(work);
+        state.view = View::Board;
+
+        assert_eq!(state.selected_task().unwrap().title, "task 3");
+        assert!(is_highlighted(&mut state, "task 3"));
+        let screen = draw(&mut state, 100, 20);
+        assert!(!screen.contains("task 1"));
+        assert!(screen.contains("Todo (1)"));
+
+        state.move_column(1);
+        assert_eq!(state.selected_task().unwrap().title, "task 4");
+        assert!(is_highlighted(&mut state, "task 4"));
+    }
+
+    #[test]
+    fn board_column_movement_clamps_and_keeps_a_card_selected() {
+        let mut state = state_with_tasks(4);
+        state.tasks[1].status = Status::InProgress;
+        state.tasks[2].status = Status::InProgress;
+        state.tasks[3].status = Status::Done;
+        state.view = View::Board;
+
+        assert!(is_highlighted(&mut state, "task 1"));
+        state.move_column(-1);
+        assert_eq!(state.board_column, 0);
+
+        state.move_column(1);
+        state.next();
+        assert_eq!(state.selected_task().unwrap().title, "task 3");
+        assert!(is_highlighted(&mut state, "task 3"));
+
+        // An empty column selects nothing, and the selection is clamped to
+        // the shorter column it lands in
+        state.move_column(1);
+        assert!(state.selected_task().is_none());
+        assert!(draw(&mut state, 100, 20).contains("Blocked (0)"));
+        state.move_column(10);
+        assert_eq!(state.board_column, 4);
+        assert_eq!(state.selected_task().unwrap().title, "task 4");
+        assert!(is_highlighted(&mut state, "task 4"));
+
+        // Regrouping by priority keeps the column in range
+        state.board_group = BoardGroup::Priority;
+        state.move_column(1);
+        assert_eq!(state.board_column, 2);
+        state.move_column(-1);
+        assert_eq!(state.selected_task().unwrap().title, "task 1");
+    }
+
+    #[test]
+    fn board_cards_land_in_the_column_they_are_moved_to() {
+        let mut task = Task::new("card".to_string());
+        for group in [BoardGroup::Status, BoardGroup::Priority] {
+            for column in 0..group.columns().len() {
+                group.apply(&mut task, column);
+                assert_eq!(group.column_of(&task), Some(column), "{:?}", group.columns()[column]);
+            }
+        }
+        // Cancelled tasks aren't on the status board
+        task.status = Status::Cancelled;
+        assert_eq!(BoardGroup::Status.column_of(&task), None);
+    }
+
+    #[test]
+    fn blocked_cards_cant_skip_straight_to_done() {
+        let mut state = state_with_tasks(1);
+        state.tasks[0].status = Status::Blocked;
+        state.view = View::Board;
+        state.move_column(2);
+        assert_eq!(state.selected_task().unwrap().title, "task 1");
+
+        // The Done column is in reach on the board, but the default
+        // workflow only lets Blocked tasks reopen or be cancelled
+        let mut card = state.tasks[0].clone();
+        state.board_group.apply(&mut card, 4);
+        assert_eq!(card.status, Status::Done);
+        assert!(!state.transition_allowed(Status::Blocked, card.status));
+
+        let screen = draw(&mut state, 100, 20);
+        assert!(screen.contains("Can't move from Blocked to Done"));
+        assert!(screen.contains("Blocked (1)"));
+        assert_eq!(state.tasks[0].status, Status::Blocked);
+
+        // Cycling skips the statuses the workflow refuses
+        assert_eq!(state.config.workflow.cycle(Status::Blocked), Some(Status::Cancelled));
+        assert!(state.transition_allowed(Status::Blocked, Status::InProgress));
+        assert!(state.transition_allowed(Status::Blocked, Status::Blocked));
+    }
+
+    // The day under the calendar cursor, read back from the month grid
+    fn calendar_cursor(state: &mut AppState) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(100, 20)).unwrap();
+        terminal.draw(|f| render(f, state)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let cells: String = (0..buffer.area.height)
+            .flat_map(|y| (0..44).map(move |x| (x, y)))
+            .filter(|&(x, y)| buffer.get(x, y).modifier.contains(Modifier::REVERSED))
+            .map(|(x, y)| buffer.get(x, y).symbol().to_string())
+            .collect();
+        cells.trim().to_string()
+    }
+
+    #[test]
+    fn calendar_moves_across_month_and_year_boundaries() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let mut state = state_with_tasks(1);
+        state.tasks[0].due_date = calendar::reschedule(None, date(2024, 3, 1));
+        state.view = View::Calendar;
+        state.calendar_date = date(2024, 1, 31);
+
+        // A month from the 31st lands on the last day of shorter months
+        state.move_calendar_months(1);
+        assert_eq!(state.calendar_date, date(2024, 2, 29));
+        assert!(draw(&mut state, 100, 20).contains("February 2024"));
+        assert_eq!(calendar_cursor(&mut state), "29");
+        assert!(state.selected_task().is_none());
+
+        // Stepping a day past the end of the month turns the page
+        state.move_calendar(1);
+        assert_eq!(state.calendar_date, date(2024, 3, 1));
+        let screen = draw(&mut state, 100, 20);
+        assert!(screen.contains("March 2024"));
+        assert!(screen.contains("Friday March 01"));
+        assert_eq!(calendar_cursor(&mut state), "1·1");
+        assert_eq!(state.selected_task().unwrap().title, "task 1");
+
+        state.move_calendar(-1);
+        assert_eq!(state.calendar_date, date(2024, 2, 29));
+        state.move_calendar_months(12);
+        assert_eq!(state.calendar_date, date(2025, 2, 28));
+
+        state.calendar_date = date(2024, 12, 31);
+        state.move_calendar(1);
+        assert_eq!(state.calendar_date, date(2025, 1, 1));
+        assert!(draw(&mut state, 100, 20).contains("January 2025"));
+        state.move_calendar_months(-1);
+        assert_eq!(state.calendar_date, date(2024, 12, 1));
+        assert!(draw(&mut state, 100, 20).contains("December 2024"));
+    }
+
+    #[test]
+    fn rescheduled_tasks_follow_the_cursor_into_the_next_month() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let mut state = state_with_tasks(1);
+        state.tasks[0].due_date = calendar::reschedule(None, date(2024, 1, 31));
+        state.view = View::Calendar;
+        state.calendar_date = date(2024, 1, 31);
+
+        state.start_reschedule();
+        state.move_calendar_months(1);
+        assert_eq!(state.tasks[0].due_date.map(calendar::local_date), Some(date(2024, 2, 29)));
+        state.move_calendar(2);
+        assert_eq!(state.tasks[0].due_date.map(calendar::local_date), Some(date(2024, 3, 2)));
+        assert_eq!(state.selected_task().unwrap().title, "task 1");
+        assert!(draw(&mut state, 100, 20).contains("March 2024"));
+    }
+
+    #[test]
+    fn checkbox_clicks_account_for_the_subtask_indent() {
+        let mut state = state_with_tasks(2);
+        state.tasks[1].parent_id = Some(state.tasks[0].id.clone());
+        let area = Rect::new(0, 0, 60, 20);
+        let screen = draw(&mut state, 60, 20);
+
+        let rows: Vec<&str> = screen.lines().collect();
+        let row = |text: &str| rows.iter().position(|r| r.contains(text)).unwrap() as u16;
+        let column = |text: &str| rows[row(text) as usize].split("[ ]").next().unwrap().chars().count() as u16;
+        let (parent, child) = (row("task 1"), row("task 2"));
+
+        assert_eq!(hit_test(area, &state, column("task 1"), parent), Some(Hit::Checkbox(0)));
+        assert_eq!(hit_test(area, &state, column("task 2"), child), Some(Hit::Checkbox(1)));
+        assert_eq!(hit_test(area, &state, column("task 2") + 2, child), Some(Hit::Checkbox(1)));
+        // The indent in front of a subtask's checkbox is part of the row
+        assert_eq!(hit_test(area, &state, column("task 1"), child), Some(Hit::Row(1)));
+    }
+
+    #[test]
+    fn clicking_a_tab_switches_the_view() {
+        let mut state = state_with_tasks(2);
+        state.tasks[1].status = Status::Done;
+        let area = Rect::new(0, 0, 100, 20);
+        let screen = draw(&mut state, 100, 20);
+
+        let rows: Vec<&str> = screen.lines().collect();
+        let header = rows.iter().position(|r| r.contains("Assigned to me")).unwrap();
+        let column = |text: &str| rows[header].split(text).next().unwrap().chars().count() as u16;
+        let header = header as u16;
+
+        assert_eq!(hit_test(area, &state, column("Done"), header), Some(Hit::Tab(View::Done)));
+        assert_eq!(hit_test(area, &state, column("Calendar") + 7, header), Some(Hit::Tab(View::Calendar)));
+        // The divider between two tabs belongs to neither
+        assert_eq!(hit_test(area, &state, column("Done") - 2, header), None);
+
+        state.set_view(View::Done);
+        let screen = draw(&mut state, 100, 20);
+        assert!(screen.contains("task 2"));
+        assert!(!screen.contains("task 1"));
+    }
+
+    #[test]
+    fn dragging_a_row_moves_the_task() {
+        let mut state = state_with_tasks(3);
+        let area = Rect::new(0, 0, 60, 20);
+        let screen = draw(&mut state, 60, 20);
+
+        let rows: Vec<&str> = screen.lines().collect();
+        let row = |text: &str| rows.iter().position(|r| r.contains(text)).unwrap() as u16;
+        let column = rows[row("task 1") as usize].split("task 1").next().unwrap().chars().count() as u16;
+        assert_eq!(hit_test(area, &state, column, row("task 1")), Some(Hit::Row(0)));
+        assert_eq!(hit_test(area, &state, column, row("task 3")), Some(Hit::Row(2)));
+
+        // Dropping the first task on the last row shifts the ones in between up
+        let changed = state.move_row(0, 2).unwrap();
+        assert_eq!(changed.len(), 3);
+        let screen = draw(&mut state, 60, 20);
+        let order: Vec<usize> = ["task 1", "task 2", "task 3"]
+            .iter()
+            .map(|t| screen.lines().position(|r| r.contains(t)).unwrap())
+            .collect();
+        assert!(order[1] < order[2] && order[2] < order[0], "{}", screen);
+        assert_eq!(state.selected_task().unwrap().title, "task 1");
+
+        // Nothing moves when a task in the way belongs to a project the user
+        // only views
+        let mut project = Project::new("Shared".to_string(), None, "u2".to_string());
+        project.role = Role::Viewer;
+        state.tasks[1].project_id = Some(project.id.clone());
+        state.projects.push(project);
+        assert!(state.move_row(2, 0).is_none());
+        let titles: Vec<&str> = state.tasks.iter().map(|t| t.title.as_str()).collect();
+        assert_eq!(titles, ["task 2", "task 3", "task 1"]);
+    }
+
+    #[test]
+    fn empty_list_shows_zero_count() {
+        let mut state = state_with_tasks(0);
+        let screen = draw(&mut state, 60, 10);
+        assert!(screen.contains("0/0"));
+    }
+}
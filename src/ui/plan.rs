@@ -0,0 +1,194 @@
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+use crate::ai::plan::PlanStep;
+use crate::core::{dates, Priority, Task};
+use super::preview::popup_area;
+use super::{calendar, AppState};
+
+// A plan for the day suggested by `:plan`, shown until it is applied or
+// dismissed
+#[derive(Debug, Clone)]
+pub struct DayPlan {
+    pub steps: Vec<PlanStep>,
+    pub selected: usize,
+}
+
+impl DayPlan {
+    pub fn new(steps: Vec<PlanStep>) -> Self {
+        Self { steps, selected: 0 }
+    }
+
+    pub fn move_by(&mut self, delta: isize) {
+        let last = self.steps.len() as isize - 1;
+        self.selected = (self.selected as isize + delta).clamp(0, last.max(0)) as usize;
+    }
+
+    // Move the planned tasks to the top of the manual order, in plan order,
+    // with everything else following in its current order. Like a drag in
+    // the list, the tasks reuse the existing positions among themselves, so
+    // tasks `can_edit` rejects stay put. Returns the tasks that changed.
+    pub fn reorder(&self, tasks: &mut [Task], can_edit: impl Fn(&Task) -> bool) -> Vec<Task> {
+        let mut movable: Vec<usize> = (0..tasks.len()).filter(|&i| can_edit(&tasks[i])).collect();
+        movable.sort_by_key(|&i| tasks[i].position);
+        let positions: Vec<i32> = movable.iter().map(|&i| tasks[i].position).collect();
+
+        let rank = |i: &usize| self.steps.iter().position(|s| s.task_id == tasks[*i].id).unwrap_or(usize::MAX);
+        let mut order = movable.clone();
+        // Stable, so unplanned tasks keep their relative order
+        order.sort_by_key(rank);
+
+        let mut changed = Vec::new();
+        for (i, position) in order.into_iter().zip(positions) {
+            if tasks[i].position != position {
+                tasks[i].position = position;
+                changed.push(tasks[i].clone());
+            }
+        }
+        changed
+    }
+
+    // Copies of the planned tasks whose priority the plan changes
+    pub fn priority_changes(&self, tasks: &[Task]) -> Vec<Task> {
+        self.steps
+            .iter()
+            .filter_map(|step| {
+                let priority = step.priority?;
+                let task = tasks.iter().find(|t| t.id == step.task_id)?;
+                (task.priority != priority).then(|| Task { priority, ..task.clone() })
+            })
+            .collect()
+    }
+}
+
+fn priority_label(priority: Priority) -> &'static str {
+    match priority {
+        Priority::Low => "P1",
+        Priority::Medium => "P2",
+        Priority::High => "P3",
+    }
+}
+
+pub fn render_plan(f: &mut Frame, area: Rect, state: &AppState) {
+    let Some(plan) = &state.plan else {
+        return;
+    };
+
+    let today = calendar::today();
+    let dim = Style::default().fg(Color::DarkGray);
+    let mut lines = Vec::new();
+    let mut total = 0;
+    for (i, step) in plan.steps.iter().enumerate() {
+        let Some(task) = state.tasks.iter().find(|t| t.id == step.task_id) else {
+            continue;
+        };
+        let marker = if i == plan.selected { "› " } else { "  " };
+        let title_style = if i == plan.selected {
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+        let priority = match step.priority {
+            Some(p) if p != task.priority => format!("{}→{}", priority_label(task.priority), priority_label(p)),
+            _ => priority_label(task.priority).to_string(),
+        };
+
+        let mut spans = vec![
+            Span::raw(format!("{}{:>2}. ", marker, i + 1)),
+            Span::styled(format!("{:<6}", priority), Style::default().fg(Color::Magenta)),
+            Span::styled(task.title.clone(), title_style),
+        ];
+        if let Some(minutes) = task.estimate_minutes {
+            total += minutes;
+            spans.push(Span::styled(format!("  {}", dates::format_duration(minutes)), dim));
+        }
+        if let Some(due) = task.due_date {
+            spans.push(Span::styled(format!("  due {}", calendar::due_label(due, today).0), dim));
+        }
+        lines.push(Line::from(spans));
+        lines.push(Line::from(Span::styled(format!("           {}", step.reason), dim)));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(format!("{} estimated", dates::format_duration(total)), dim)));
+
+    // Each step takes two lines; scroll to keep the selected one in view
+    let popup = popup_area(area, 80, lines.len() as u16 + 2);
+    let scroll = (plan.selected * 2 + 2).saturating_sub(popup.height.saturating_sub(2) as usize);
+    f.render_widget(Clear, popup);
+    f.render_widget(
+        Paragraph::new(lines).wrap(Wrap { trim: false }).scroll((scroll as u16, 0)).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan))
+                .title("Plan for today"),
+        ),
+        popup,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tasks() -> Vec<Task> {
+        ["a", "b", "c", "d", "e"]
+            .iter()
+            .enumerate()
+            .map(|(i, title)| Task { position: i as i32 * 10, ..Task::new(title.to_string()) })
+            .collect()
+    }
+
+    fn step(task: &Task, priority: Option<Priority>) -> PlanStep {
+        PlanStep { task_id: task.id.clone(), reason: "because".to_string(), priority }
+    }
+
+    fn order(tasks: &[Task]) -> String {
+        let mut sorted: Vec<&Task> = tasks.iter().collect();
+        sorted.sort_by_key(|t| t.position);
+        sorted.iter().map(|t| t.title.as_str()).collect()
+    }
+
+    #[test]
+    fn planned_tasks_move_to_the_top_in_plan_order() {
+        let mut tasks = tasks();
+        let plan = DayPlan::new(vec![step(&tasks[3], None), step(&tasks[1], None)]);
+
+        let changed = plan.reorder(&mut tasks, |_| true);
+        assert_eq!(order(&tasks), "dbace");
+        // "b" and "e" already had their new positions
+        assert_eq!(changed.len(), 3);
+
+        // Applying the same plan again changes nothing
+        assert!(plan.reorder(&mut tasks, |_| true).is_empty());
+    }
+
+    #[test]
+    fn read_only_tasks_keep_their_position() {
+        let mut tasks = tasks();
+        let plan = DayPlan::new(vec![step(&tasks[4], None)]);
+        let locked = tasks[0].id.clone();
+
+        plan.reorder(&mut tasks, |t| t.id != locked);
+        assert_eq!(order(&tasks), "aebcd");
+        assert_eq!(tasks[0].position, 0);
+    }
+
+    #[test]
+    fn only_real_priority_changes_are_applied() {
+        let tasks = tasks();
+        let plan = DayPlan::new(vec![
+            step(&tasks[0], Some(Priority::High)),
+            step(&tasks[1], Some(Priority::Medium)),
+            step(&tasks[2], None),
+        ]);
+
+        let changed = plan.priority_changes(&tasks);
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].id, tasks[0].id);
+        assert_eq!(changed[0].priority, Priority::High);
+    }
+}
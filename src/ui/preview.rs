@@ -0,0 +1,366 @@
+use chrono::Local;
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+use crate::ai::similar::Similar;
+use crate::core::projects::resolve;
+use crate::core::recurrence::Recurrence;
+use crate::core::tags::{merge_into, normalize_tag};
+use crate::core::{dates, Priority, Project, Task};
+use super::AppState;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Field {
+    Title,
+    Description,
+    Priority,
+    Due,
+    Repeats,
+    Estimate,
+    Tags,
+    Project,
+}
+
+impl Field {
+    pub const ALL: [Field; 8] = [
+        Field::Title,
+        Field::Description,
+        Field::Priority,
+        Field::Due,
+        Field::Repeats,
+        Field::Estimate,
+        Field::Tags,
+        Field::Project,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Field::Title => "Title",
+            Field::Description => "Notes",
+            Field::Priority => "Priority",
+            Field::Due => "Due",
+            Field::Repeats => "Repeats",
+            Field::Estimate => "Estimate",
+            Field::Tags => "Tags",
+            Field::Project => "Project",
+        }
+    }
+}
+
+// A task parsed from `:add`, shown for review before it is saved
+#[derive(Debug, Clone)]
+pub struct TaskPreview {
+    pub task: Task,
+    pub selected: usize,
+    // The selected field is being edited in the status line
+    pub editing: bool,
+    // Set while the task is compared with the open ones before saving
+    pub checking: bool,
+    // An open task it looks like; the user picks add anyway, merge or cancel
+    pub similar: Option<Similar>,
+}
+
+impl TaskPreview {
+    pub fn new(task: Task) -> Self {
+        Self { task, selected: 0, editing: false, checking: false, similar: None }
+    }
+
+    // `existing` with what this task adds to it: its notes, tags, the higher
+    // priority, and a due date, repeat or estimate where `existing` has none.
+    // The title and project stay.
+    pub fn merge_into(&self, existing: &Task) -> Task {
+        let new = &self.task;
+        let mut merged = existing.clone();
+        let notes = new.description.trim();
+        if !notes.is_empty() && !merged.description.contains(notes) {
+            if !merged.description.is_empty() {
+                merged.description.push('\n');
+            }
+            merged.description.push_str(notes);
+        }
+        if new.priority as i32 > merged.priority as i32 {
+            merged.priority = new.priority;
+        }
+        merged.due_date = merged.due_date.or(new.due_date);
+        merged.recurrence = merged.recurrence.or(new.recurrence);
+        merged.estimate_minutes = merged.estimate_minutes.or(new.estimate_minutes);
+        merge_into(&mut merged, new.tags.clone());
+        merged
+    }
+
+    pub fn field(&self) -> Field {
+        Field::ALL[self.selected]
+    }
+
+    pub fn move_by(&mut self, delta: isize) {
+        let last = Field::ALL.len() as isize - 1;
+        self.selected = (self.selected as isize + delta).clamp(0, last) as usize;
+    }
+
+    // A field's value as text to edit; `set` accepts the same format back
+    pub fn text(&self, field: Field, projects: &[Project]) -> String {
+        let task = &self.task;
+        match field {
+            Field::Title => task.title.clone(),
+            Field::Description => task.description.clone(),
+            Field::Priority => priority_name(task.priority).to_lowercase(),
+            Field::Due => task.due_date.map(dates::format_local).unwrap_or_default(),
+            Field::Repeats => task.recurrence.map(|r| r.to_string()).unwrap_or_default(),
+            Field::Estimate => task.estimate_minutes.map(dates::format_duration).unwrap_or_default(),
+            Field::Tags => task.tags.iter().map(|t| format!("+{}", t)).collect::<Vec<_>>().join(" "),
+            Field::Project => project_name(task, projects).unwrap_or_default(),
+        }
+    }
+
+    // Set a field from edited text. An empty value clears optional fields.
+    pub fn set(&mut self, field: Field, text: &str, projects: &[Project]) -> Result<(), String> {
+        let text = text.trim();
+        let task = &mut self.task;
+        match field {
+            Field::Title if text.is_empty() => return Err("The title can't be empty".to_string()),
+            Field::Title => task.title = text.to_string(),
+            Field::Description => task.description = text.to_string(),
+            Field::Priority => {
+                task.priority = match text.to_lowercase().as_str() {
+                    "low" | "1" => Priority::Low,
+                    "medium" | "2" => Priority::Medium,
+                    "high" | "3" => Priority::High,
+                    _ => return Err("Priority must be low, medium or high".to_string()),
+                }
+            }
+            Field::Due if text.is_empty() => task.due_date = None,
+            Field::Due => {
+                task.due_date = Some(dates::parse_iso(text).ok_or("Use YYYY-MM-DD or YYYY-MM-DD HH:MM")?);
+            }
+            Field::Repeats if text.is_empty() => task.recurrence = None,
+            Field::Repeats => {
+                task.recurrence = Some(Recurrence::parse(text).ok_or("Use e.g. daily, weekly or every 3 days")?);
+            }
+            Field::Estimate if text.is_empty() => task.estimate_minutes = None,
+            Field::Estimate => {
+                task.estimate_minutes = Some(dates::parse_duration(text).ok_or("Use e.g. 45m, 2h or 1h30m")?);
+            }
+            Field::Tags => {
+                let mut tags: Vec<String> = Vec::new();
+                for tag in text.split_whitespace().filter_map(normalize_tag) {
+                    if !tags.contains(&tag) {
+                        tags.push(tag);
+                    }
+                }
+                task.tags = tags;
+            }
+            Field::Project if text.is_empty() || text.eq_ignore_ascii_case("inbox") => task.project_id = None,
+            Field::Project => {
+                let project = resolve(projects, text).ok_or_else(|| format!("No project named '{}'", text))?;
+                task.project_id = Some(project.id.clone());
+            }
+        }
+        Ok(())
+    }
+
+    // The field as shown in the popup
+    fn display(&self, field: Field, projects: &[Project]) -> String {
+        let task = &self.task;
+        let value = match field {
+            Field::Priority => priority_name(task.priority).to_string(),
+            Field::Due => task
+                .due_date
+                .map(|d| d.with_timezone(&Local).format("%a %Y-%m-%d %H:%M").to_string())
+                .unwrap_or_default(),
+            Field::Project => project_name(task, projects).unwrap_or_else(|| "Inbox".to_string()),
+            _ => self.text(field, projects),
+        };
+        if value.is_empty() {
+            "-".to_string()
+        } else {
+            value
+        }
+    }
+}
+
+fn priority_name(priority: Priority) -> &'static str {
+    match priority {
+        Priority::Low => "Low",
+        Priority::Medium => "Medium",
+        Priority::High => "High",
+    }
+}
+
+fn project_name(task: &Task, projects: &[Project]) -> Option<String> {
+    let id = task.project_id.as_ref()?;
+    projects.iter().find(|p| &p.id == id).map(|p| p.name.clone())
+}
+
+// Centered box of at most `width` x `height` cells inside `area`
+pub fn popup_area(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+pub fn render_preview(f: &mut Frame, area: Rect, state: &AppState) {
+    let Some(preview) = &state.preview else {
+        return;
+    };
+
+    let label = Style::default().fg(Color::DarkGray);
+    let mut lines: Vec<Line> = Field::ALL
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            let selected = i == preview.selected;
+            let marker = if selected { "› " } else { "  " };
+            let value_style = if selected {
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+            Line::from(vec![
+                Span::styled(format!("{}{:<10}", marker, field.label()), label),
+                Span::styled(preview.display(*field, &state.projects), value_style),
+            ])
+        })
+        .collect();
+
+    let similar = preview
+        .similar
+        .as_ref()
+        .and_then(|s| state.tasks.iter().find(|t| t.id == s.task_id).map(|t| (t, s.score)));
+    if let Some((task, score)) = similar {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            format!("Similar to '{}' ({:.0}% alike)", task.title, score * 100.0),
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+        )));
+    }
+
+    let popup = popup_area(area, 72, lines.len() as u16 + 2);
+    f.render_widget(Clear, popup);
+    f.render_widget(
+        Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan))
+                .title("New task — Enter: save, e: edit field, Esc: discard"),
+        ),
+        popup,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn preview() -> TaskPreview {
+        TaskPreview::new(Task::new("Buy milk".to_string()))
+    }
+
+    #[test]
+    fn edited_text_round_trips() {
+        let projects = vec![Project::new("Errands".to_string(), None, "u1".to_string())];
+        let mut preview = preview();
+        let edits = [
+            (Field::Title, "Buy oat milk"),
+            (Field::Description, "lactose-free"),
+            (Field::Priority, "high"),
+            (Field::Due, "2030-05-01 17:30"),
+            (Field::Repeats, "every 2 weeks"),
+            (Field::Estimate, "1h30m"),
+            (Field::Tags, "+errands +shopping"),
+            (Field::Project, "Errands"),
+        ];
+        for (field, text) in edits {
+            preview.set(field, text, &projects).unwrap();
+            assert_eq!(preview.text(field, &projects), text, "{:?}", field);
+        }
+        assert_eq!(preview.task.priority, Priority::High);
+        assert_eq!(preview.task.estimate_minutes, Some(90));
+        assert_eq!(preview.task.recurrence.map(|r| r.interval()).as_deref(), Some("2 weeks"));
+        assert_eq!(preview.task.project_id, Some(projects[0].id.clone()));
+    }
+
+    #[test]
+    fn invalid_edits_are_rejected_and_empty_ones_clear() {
+        let mut preview = preview();
+        preview.set(Field::Estimate, "2h", &[]).unwrap();
+        preview.set(Field::Due, "2030-05-01", &[]).unwrap();
+        preview.set(Field::Repeats, "Monthly", &[]).unwrap();
+        assert_eq!(preview.text(Field::Repeats, &[]), "monthly");
+
+        assert!(preview.set(Field::Title, "  ", &[]).is_err());
+        assert!(preview.set(Field::Priority, "asap", &[]).is_err());
+        assert!(preview.set(Field::Due, "next tuesday-ish", &[]).is_err());
+        assert!(preview.set(Field::Repeats, "now and then", &[]).is_err());
+        assert!(preview.set(Field::Estimate, "a while", &[]).is_err());
+        assert!(preview.set(Field::Project, "Nowhere", &[]).is_err());
+        assert_eq!(preview.task.title, "Buy milk");
+        assert_eq!(preview.task.estimate_minutes, Some(120));
+
+        preview.set(Field::Estimate, "", &[]).unwrap();
+        preview.set(Field::Due, "", &[]).unwrap();
+        preview.set(Field::Repeats, "", &[]).unwrap();
+        assert_eq!(preview.task.estimate_minutes, None);
+        assert_eq!(preview.task.due_date, None);
+        assert_eq!(preview.task.recurrence, None);
+    }
+
+    #[test]
+    fn durations_parse_in_several_forms() {
+        let cases = [
+            ("45m", Some(45)),
+            ("90", Some(90)),
+            ("2h", Some(120)),
+            ("1h30m", Some(90)),
+            ("1.5h", Some(90)),
+            ("1 hour 15 min", Some(75)),
+            ("1d", Some(480)),
+            ("0m", None),
+            ("soon", None),
+            ("", None),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(dates::parse_duration(text), expected, "{:?}", text);
+        }
+        assert_eq!(dates::format_duration(45), "45m");
+        assert_eq!(dates::format_duration(120), "2h");
+        assert_eq!(dates::format_duration(95), "1h35m");
+    }
+
+    #[test]
+    fn merging_keeps_the_existing_task_and_fills_in_the_rest() {
+        let mut existing = Task::new("Buy milk".to_string());
+        existing.description = "from the corner shop".to_string();
+        existing.tags = vec!["errands".to_string()];
+        existing.due_date = dates::parse_iso("2030-05-01");
+
+        let mut preview = TaskPreview::new(Task::new("buy oat milk".to_string()));
+        preview.task.description = "lactose-free".to_string();
+        preview.task.priority = Priority::High;
+        preview.task.due_date = dates::parse_iso("2030-04-01");
+        preview.task.estimate_minutes = Some(15);
+        preview.task.recurrence = Recurrence::parse("weekly");
+        preview.task.tags = vec!["errands".to_string(), "shopping".to_string()];
+
+        let merged = preview.merge_into(&existing);
+        assert_eq!(merged.id, existing.id);
+        assert_eq!(merged.title, "Buy milk");
+        assert_eq!(merged.description, "from the corner shop\nlactose-free");
+        assert_eq!(merged.priority, Priority::High);
+        assert_eq!(merged.due_date, existing.due_date);
+        assert_eq!(merged.estimate_minutes, Some(15));
+        assert_eq!(merged.recurrence, preview.task.recurrence);
+        assert_eq!(merged.tags, ["errands", "shopping"]);
+
+        // Merging the same notes twice doesn't repeat them
+        assert_eq!(preview.merge_into(&merged).description, merged.description);
+    }
+}